@@ -2,7 +2,7 @@ use std::hash::{Hash, Hasher};
 use std::ops::Range;
 
 use crc32fast::Hasher as CRCHasher;
-use criterion::{black_box, Criterion, criterion_group, criterion_main};
+use criterion::{BenchmarkId, black_box, Criterion, criterion_group, criterion_main};
 use fastmurmur3::murmur3_x64_128;
 use fxhash::{FxHasher64, hash64};
 use getrandom::getrandom;
@@ -147,10 +147,140 @@ fn bloom_add_bench(c: &mut Criterion) {
     c.bench_function("bloom_add_test", |b| b.iter(|| bloom_add_test(&mut filter, black_box("hellohellohellohello".as_bytes()))));
     c.bench_function("random_test", |b| b.iter(|| random_test(&mut random, &range)));
     c.bench_function("bloom_add_random_test", |b| b.iter(|| bloom_add_random_test(&mut filter, &mut random, &range)));
+    c.bench_function("bloom_hash_indices_random_test", |b| b.iter(|| {
+        filter.get_hash_indices(&i64::to_le_bytes(random.gen_range(0..10_000_000)))
+    }));
     c.bench_function("bloom_add_all_test", |b| b.iter(|| bloom_add_all_test(&mut filter, &inputs[..])));
 
     c.bench_function("bloom_contains_test", |b| b.iter(|| filter.contains(black_box(hello.as_bytes()))));
     c.bench_function("bloom_not_contains_test", |b| b.iter(|| filter.contains(black_box(b"hellohellohello"))));
+
+    let mut builder = FilterBuilder::new(items_count as u64, 0.001);
+    builder.power_of_two_size(true);
+    let mut filter = builder.build_bloom_filter();
+    c.bench_function("bloom_power_of_two_add_random_test", |b| b.iter(|| bloom_add_random_test(&mut filter, &mut random, &range)));
+    c.bench_function("bloom_power_of_two_add_all_test", |b| b.iter(|| bloom_add_all_test(&mut filter, &inputs[..])));
+}
+
+/// Builds a filter for 1_000_000 elements and inserts distinct integers until at least `fill` of
+/// its bits are set.
+fn bloom_filled_to(fill: f64) -> BloomFilter {
+    let mut filter = FilterBuilder::new(1_000_000, 0.01).build_bloom_filter();
+    let mut next = 0i64;
+    while filter.fill_ratio() < fill {
+        for value in next..next + 10_000 {
+            filter.add(&i64::to_le_bytes(value));
+        }
+        next += 10_000;
+    }
+    filter
+}
+
+fn bloom_miss_by_fill_bench(c: &mut Criterion) {
+    // negative integers are never inserted by `bloom_filled_to`, so these are all true misses
+    // apart from the false positives of the fuller filters.
+    let misses: Vec<[u8; 8]> = (1..=4096i64).map(|value| i64::to_le_bytes(-value)).collect();
+
+    let mut group = c.benchmark_group("bloom_miss_by_fill");
+    for fill in [0.1, 0.5, 0.9] {
+        let filter = bloom_filled_to(fill);
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}%", (fill * 100.0) as u32)),
+                               &filter, |b, filter| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) & 4095;
+                filter.contains(black_box(&misses[i]))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bloom_unchecked_bench(c: &mut Criterion) {
+    let inputs: Vec<[u8; 8]> = (0..4096i64).map(i64::to_le_bytes).collect();
+    let mut filter = bloom_filled_to(0.5);
+    assert!(filter.check_invariants().is_ok());
+
+    let mut group = c.benchmark_group("bloom_unchecked");
+    let mut i = 0;
+    group.bench_function("contains", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        filter.contains(black_box(&inputs[i]))
+    }));
+    group.bench_function("contains_unchecked", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        unsafe { filter.contains_unchecked(black_box(&inputs[i])) }
+    }));
+    group.bench_function("add", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        filter.add(black_box(&inputs[i]))
+    }));
+    group.bench_function("add_unchecked", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        unsafe { filter.add_unchecked(black_box(&inputs[i])) }
+    }));
+    group.finish();
+}
+
+/// Queries members and misses of a regular and a frozen filter, half full, from one which fits in
+/// cache to one bound by memory latency.
+fn bloom_frozen_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bloom_frozen");
+    for elements in [10_000u64, 1_000_000, 50_000_000] {
+        let mut filter = FilterBuilder::new(elements, 0.01).build_bloom_filter();
+        for x in 0..elements / 2 {
+            filter.add(&x.to_le_bytes());
+        }
+        let frozen = filter.clone().freeze();
+        let members: Vec<[u8; 8]> = (0..4096u64).map(u64::to_le_bytes).collect();
+        let misses: Vec<[u8; 8]> = (0..4096u64).map(|x| (elements + x).to_le_bytes()).collect();
+
+        for (name, inputs) in [("member", &members), ("miss", &misses)] {
+            let mut i = 0;
+            group.bench_function(BenchmarkId::new(format!("contains_{name}"), elements), |b| b.iter(|| {
+                i = (i + 1) & 4095;
+                filter.contains(black_box(&inputs[i]))
+            }));
+            group.bench_function(BenchmarkId::new(format!("frozen_contains_{name}"), elements), |b| b.iter(|| {
+                i = (i + 1) & 4095;
+                frozen.contains(black_box(&inputs[i]))
+            }));
+        }
+    }
+    group.finish();
+}
+
+fn bloom_u64_bench(c: &mut Criterion) {
+    let filter = bloom_filled_to(0.5);
+
+    let mut group = c.benchmark_group("bloom_u64");
+    let mut i = 0u64;
+    group.bench_function("contains_le_bytes", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        filter.contains(&black_box(i).to_le_bytes())
+    }));
+    group.bench_function("contains_u64", |b| b.iter(|| {
+        i = (i + 1) & 4095;
+        filter.contains_u64(black_box(i))
+    }));
+    group.finish();
+}
+
+/// Unions filters of 8KB, which stay in cache and are bound by the word loop, and of 1GB, which
+/// are bound by memory bandwidth.
+fn bloom_union_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bloom_union");
+    for (name, size) in [("8kb", 1u64 << 16), ("1gb", 8 << 30)] {
+        let mut builder = FilterBuilder::from_size_and_hashes(size, 7);
+        let mut filter = builder.build_bloom_filter();
+        let mut other = builder.build_bloom_filter();
+        for x in 0..1_000_000u64 {
+            other.add(&x.to_le_bytes());
+        }
+        group.sample_size(10);
+        group.bench_function(name, |b| b.iter(|| filter.union(black_box(&other))));
+    }
+    group.finish();
 }
 
 fn counting_bloom_add_bench(c: &mut Criterion) {
@@ -167,5 +297,7 @@ fn counting_bloom_add_bench(c: &mut Criterion) {
     }));
 }
 
-criterion_group!(benches, bloom_add_bench, counting_bloom_add_bench);
+criterion_group!(benches, bloom_add_bench, bloom_miss_by_fill_bench, bloom_unchecked_bench,
+                 bloom_frozen_bench, bloom_u64_bench, bloom_union_bench,
+                 counting_bloom_add_bench);
 criterion_main!(benches);
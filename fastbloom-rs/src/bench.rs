@@ -0,0 +1,88 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::{FilterBuilder, Membership};
+
+/// Timing and accuracy of a Bloom filter workload, see [run_profile].
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct ProfileResult {
+    /// Total time spent adding the `n_insert` elements.
+    pub insert_time: Duration,
+    /// Total time spent querying `n_query` elements that were inserted.
+    pub hit_query_time: Duration,
+    /// Total time spent querying `n_query` elements that were never inserted.
+    pub miss_query_time: Duration,
+    /// Fraction of the `n_query` never inserted elements reported as present.
+    pub false_positive_rate: f64,
+    /// Size of the underlying bit vector in bytes.
+    pub memory_bytes: usize,
+}
+
+/// Builds a Bloom filter from `config`, adds `n_insert` elements and queries `n_query` inserted
+/// and `n_query` never inserted elements, measuring each phase. Elements are the integers
+/// `0..n_insert` in the canonical `i64` encoding (see [Membership::contains_int]); hit queries
+/// cycle through them and miss queries use integers from `n_insert` upwards.
+///
+/// This is meant for external benchmark harnesses, which can call the Rust core directly instead
+/// of reimplementing the workload.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::FilterBuilder;
+/// use fastbloom_rs::bench::run_profile;
+///
+/// let result = run_profile(FilterBuilder::new(10_000, 0.01), 10_000, 10_000);
+/// assert!(result.false_positive_rate < 0.02);
+/// ```
+pub fn run_profile(mut config: FilterBuilder, n_insert: u64, n_query: u64) -> ProfileResult {
+    assert!(n_insert > 0, "n_insert must larger than 0!");
+    let mut bloom = config.build_bloom_filter();
+
+    let start = Instant::now();
+    for x in 0..n_insert {
+        bloom.add(&(x as i64).to_le_bytes());
+    }
+    let insert_time = start.elapsed();
+
+    let start = Instant::now();
+    for x in 0..n_query {
+        black_box(bloom.contains(&((x % n_insert) as i64).to_le_bytes()));
+    }
+    let hit_query_time = start.elapsed();
+
+    let mut false_positives = 0u64;
+    let start = Instant::now();
+    for x in n_insert..n_insert + n_query {
+        if bloom.contains(&(x as i64).to_le_bytes()) {
+            false_positives += 1;
+        }
+    }
+    let miss_query_time = start.elapsed();
+
+    ProfileResult {
+        insert_time,
+        hit_query_time,
+        miss_query_time,
+        false_positive_rate: if n_query == 0 { 0.0 } else { false_positives as f64 / n_query as f64 },
+        memory_bytes: bloom.get_u8_array().len(),
+    }
+}
+
+#[test]
+fn run_profile_test() {
+    let result = run_profile(FilterBuilder::new(100_000, 0.01), 100_000, 100_000);
+    assert!(result.insert_time > Duration::ZERO);
+    assert!(result.hit_query_time > Duration::ZERO);
+    assert!(result.miss_query_time > Duration::ZERO);
+    assert!(result.false_positive_rate > 0.0 && result.false_positive_rate < 0.02,
+            "{}", result.false_positive_rate);
+    assert_eq!(result.memory_bytes as u64 * 8,
+               FilterBuilder::new(100_000, 0.01).build_bloom_filter().config().size);
+
+    // a larger filter for the same elements uses more memory and has fewer false positives.
+    let larger = run_profile(FilterBuilder::new(100_000, 0.001), 100_000, 100_000);
+    assert!(larger.memory_bytes > result.memory_bytes);
+    assert!(larger.false_positive_rate < result.false_positive_rate);
+}
@@ -1,79 +1,118 @@
+use std::borrow::Cow;
 use std::cmp::min;
 use std::fs::{File, OpenOptions};
 use std::fs;
 use std::io::{Write, Read};
-use std::ptr::slice_from_raw_parts;
+use std::ptr::{slice_from_raw_parts, slice_from_raw_parts_mut};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::{Deletable, Hashes, Membership};
+use crate::builder::{FilterBuilder, HashWidth, MAX_HASHES, optimal_k, SUFFIX};
+use crate::error::FilterError;
+#[cfg(feature = "metrics")]
+use crate::metrics::{OpCounters, OpStats};
+use crate::hashing::{indices_128, next_index, pair_probes, probes, seeded_indices, xxh3_64_u64};
+use crate::vec::{BloomBitVec, COUNTER_MAX, CountingVec};
 use xxhash_rust::xxh3::xxh3_64_with_seed;
 
-use crate::{Deletable, Hashes, Membership};
-use crate::builder::FilterBuilder;
-use crate::vec::{BloomBitVec, CountingVec};
+#[inline]
+fn bit_set(bit_set: &mut BloomBitVec, value: &[u8], m: u64, k: u64, seeds: (u64, u64)) {
+    bit_set.set_all(probes(value, m, k, seeds));
+}
 
 #[inline]
-fn bit_set(bit_set: &mut BloomBitVec, value: &[u8], m: u64, k: u64) {
-    // let len = m >> 5;
-    // let hash1 = (murmur3_x64_128(value, 0) % m) as u64;
-    // let hash2 = (murmur3_x64_128(value, 32) % m) as u64;
-    let hash1 = xxh3_64_with_seed(value, 0) % m;
-    let hash2 = xxh3_64_with_seed(value, 32) % m;
-
-    let m = m as u64;
-    for i in 1..k {
-        let mo = ((hash1 + i * hash2) % m) as usize;
-        bit_set.set(mo);
-    };
-    bit_set.set(hash1 as usize);
+fn bit_check(bit_set: &BloomBitVec, value: &[u8], m: u64, k: u64, seeds: (u64, u64)) -> bool {
+    probes(value, m, k, seeds).all(|index| bit_set.get(index as usize))
 }
 
 #[inline]
-fn bit_check(bit_set: &BloomBitVec, value: &[u8], m: u64, k: u64) -> bool {
-    // let hash1 = (murmur3_x64_128(value, 0) % m) as u64;
-    // let hash2 = (murmur3_x64_128(value, 32) % m) as u64;
-    let hash1 = xxh3_64_with_seed(value, 0) % m;
-    let hash2 = xxh3_64_with_seed(value, 32) % m;
-    let mut res = bit_set.get(hash1 as usize);
-    if !res { return false; }
-    // let m = m as u64;
-    for i in 1..k {
-        let mo = ((hash1 + i * hash2) % m) as usize;
-        res = res && bit_set.get(mo);
-        if !res { return false; }
-    }
+fn bit_check_and_set(bit_set: &mut BloomBitVec, value: &[u8], m: u64, k: u64, seeds: (u64, u64)) -> bool {
+    let probes = probes(value, m, k, seeds);
+    let res = probes.clone().all(|index| bit_set.get(index as usize));
+    bit_set.set_all(probes);
     res
 }
 
-#[inline]
-fn bit_check_and_set(bit_set: &mut BloomBitVec, value: &[u8], m: u64, k: u64) -> bool {
-    // let hash1 = (murmur3_x64_128(value, 0) % m) as u64;
-    // let hash2 = (murmur3_x64_128(value, 32) % m) as u64;
-    let hash1 = xxh3_64_with_seed(value, 0) % m;
-    let hash2 = xxh3_64_with_seed(value, 32) % m;
-    let mut res = bit_set.get(hash1 as usize);
-    bit_set.set(hash1 as usize);
-    // let m = m as u64;
-    for i in 1..k {
-        let mo = ((hash1 + i * hash2) % m) as usize;
-        res = res && bit_set.get(mo);
-        bit_set.set(mo);
+/// Returns the little-endian bytes of `array`, borrowing them on little-endian targets where they
+/// already are the in-memory representation.
+fn le_bytes<T: Copy, const N: usize>(array: &[T], to_le_bytes: fn(T) -> [u8; N]) -> Cow<'_, [u8]> {
+    if cfg!(target_endian = "little") {
+        let ptr = slice_from_raw_parts(array.as_ptr() as *const u8, array.len() * N);
+        Cow::Borrowed(unsafe { &*ptr })
+    } else {
+        Cow::Owned(array.iter().flat_map(|x| to_le_bytes(*x)).collect())
     }
-    res
 }
 
+/// Packs `words` in pairs into u64 with the first word of each pair in the lower half, padding
+/// an odd last word with a zero upper half.
+#[cfg_attr(target_pointer_width = "64", allow(dead_code))]
+fn pack_u32_pairs(words: &[u32]) -> Vec<u64> {
+    words.chunks(2)
+        .map(|pair| pair[0] as u64 | (pair.get(1).copied().unwrap_or(0) as u64) << 32)
+        .collect()
+}
+
+/// Advances the SplitMix64 generator `state` and returns its next output.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Estimates the number of elements inserted into a Bloom filter of `m` bits and `k` hashes from
+/// the number of bits that are still zero.
 #[inline]
-fn get_bit_indices(bit_set: &BloomBitVec, value: &[u8], m: u64, k: u64) -> Vec<u64> {
-    let mut res = Vec::<u64>::with_capacity(k as usize);
-    // let hash1 = (murmur3_x64_128(value, 0) % m) as u64;
-    // let hash2 = (murmur3_x64_128(value, 32) % m) as u64;
-    let hash1 = xxh3_64_with_seed(value, 0) % m;
-    let hash2 = xxh3_64_with_seed(value, 32) % m;
-    res.push(hash1);
-    // let m = m as u64;
-    for i in 1..k {
-        let mo = ((hash1 + i * hash2) % m) as usize;
-        res.push(mo as u64);
+fn estimate_cardinality(zeros: u64, m: u64, k: u32) -> f64 {
+    (zeros as f64 / m as f64).ln() / (k as f64 * (1.0 - 1.0 / m as f64).ln())
+}
+
+/// Number of adds after which [BloomFilter::should_stop_inserting] recomputes its estimate.
+const FPR_REFRESH_INSERTS: u64 = 4096;
+
+/// The false positive probability last computed by [BloomFilter::should_stop_inserting] and the
+/// number of adds since. Atomics let it refresh through `&self` while the filter stays `Sync`.
+#[derive(Debug)]
+struct FprCache {
+    inserts: AtomicU64,
+    /// Bits of the `f64` estimate, [FprCache::STALE] when it must be recomputed.
+    fpr: AtomicU64,
+}
+
+impl FprCache {
+    const STALE: u64 = u64::MAX;
+
+    fn invalidate(&mut self) {
+        *self.fpr.get_mut() = FprCache::STALE;
+    }
+}
+
+impl Default for FprCache {
+    fn default() -> Self {
+        FprCache { inserts: AtomicU64::new(0), fpr: AtomicU64::new(FprCache::STALE) }
+    }
+}
+
+impl Clone for FprCache {
+    fn clone(&self) -> Self {
+        FprCache {
+            inserts: AtomicU64::new(self.inserts.load(Ordering::Relaxed)),
+            fpr: AtomicU64::new(self.fpr.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// The bit indices of `element` in a Bloom filter built from the completed `config`.
+fn hash_indices(config: &FilterBuilder, element: &[u8]) -> Vec<u64> {
+    match config.hash_width {
+        HashWidth::Bits64 => seeded_indices(element, config.size, config.hashes, config.hash_seeds)
+            .into_vec(),
+        width => indices_128(element, config.size, config.hashes as u64, width).collect(),
     }
-    res
 }
 
 /// A Bloom filter is a space-efficient probabilistic data structure, conceived by Burton Howard
@@ -83,37 +122,76 @@ fn get_bit_indices(bit_set: &BloomBitVec, value: &[u8], m: u64, k: u64) -> Vec<u
 /// **Reference**: Bloom, B. H. (1970). Space/time trade-offs in hash coding with allowable errors.
 /// Communications of the ACM, 13(7), 422-426.
 /// [Full text article](http://crystal.uta.edu/~mcguigan/cse6350/papers/Bloom.pdf)
+///
+/// The filters are `Send + Sync`: a filter can be shared immutably across threads, e.g. in an
+/// `Arc`, and queried concurrently, while mutation needs external synchronization such as a
+/// `RwLock`.
 #[derive(Clone)]
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BloomFilter {
     config: FilterBuilder,
     bit_set: BloomBitVec,
+    /// Bits of elements added more than once, see [FilterBuilder::enable_duplicate_tracking].
+    #[cfg_attr(feature = "serde", serde(default))]
+    duplicates: Option<BloomBitVec>,
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: OpCounters,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fpr_cache: FprCache,
 }
 
 impl Membership for BloomFilter {
     /// Adds the passed value to the filter.
     fn add(&mut self, element: &[u8]) {
-        bit_set(&mut self.bit_set, element, self.config.size,
-                self.config.hashes as u64);
+        #[cfg(feature = "metrics")]
+            let start = std::time::Instant::now();
+        *self.fpr_cache.inserts.get_mut() += 1;
+        if self.duplicates.is_some() {
+            if self.add_if_not_contains(element) {
+                let indices = self.get_hash_indices(element);
+                let duplicates = self.duplicates.as_mut().unwrap();
+                for index in indices {
+                    duplicates.set(index as usize);
+                }
+            }
+        } else {
+            match self.config.hash_width {
+                HashWidth::Bits64 => bit_set(&mut self.bit_set, element, self.config.size,
+                                             self.config.hashes as u64, self.config.hash_seeds),
+                width => self.bit_set.set_all(indices_128(element, self.config.size,
+                                                          self.config.hashes as u64, width)),
+            }
+        }
+        #[cfg(feature = "metrics")]
+        self.stats.record_add(start);
     }
 
     /// Tests whether an element is present in the filter (subject to the specified false
     /// positive rate).
     #[inline]
     fn contains(&self, element: &[u8]) -> bool {
-        bit_check(&self.bit_set, element, self.config.size,
-                  self.config.hashes as u64)
+        #[cfg(feature = "metrics")]
+            let start = std::time::Instant::now();
+        let found = match self.config.hash_width {
+            HashWidth::Bits64 => bit_check(&self.bit_set, element, self.config.size,
+                                           self.config.hashes as u64, self.config.hash_seeds),
+            width => indices_128(element, self.config.size, self.config.hashes as u64, width)
+                .all(|index| self.bit_set.get(index as usize)),
+        };
+        #[cfg(feature = "metrics")]
+        self.stats.record_contains(start);
+        found
     }
 
     /// Get the hashes indices of the element in the filter.
     fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
-        get_bit_indices(&self.bit_set, element, self.config.size,
-                        self.config.hashes as u64)
+        hash_indices(&self.config, element)
     }
 
     /// Tests whether a hashes indices is present in the filter
-    fn contains_hash_indices(&self, indices: &Vec<u64>) -> bool {
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool {
         for x in indices.iter() {
             let index = *x;
             if !self.bit_set.get(index as usize) { return false; }
@@ -124,6 +202,10 @@ impl Membership for BloomFilter {
     /// Removes all elements from the filter (i.e. resets all bits to zero).
     fn clear(&mut self) {
         self.bit_set.clear();
+        if let Some(duplicates) = &mut self.duplicates {
+            duplicates.clear();
+        }
+        self.fpr_cache.invalidate();
     }
 }
 
@@ -135,6 +217,19 @@ impl Hashes for BloomFilter {
 }
 
 impl BloomFilter {
+    fn from_parts(config: FilterBuilder, bit_set: BloomBitVec) -> Self {
+        let duplicates = config.enable_duplicate_tracking
+            .then(|| BloomBitVec::new(bit_set.storage.len()));
+        BloomFilter {
+            config,
+            bit_set,
+            duplicates,
+            #[cfg(feature = "metrics")]
+            stats: OpCounters::default(),
+            fpr_cache: FprCache::default(),
+        }
+    }
+
     /// Build a Bloom filter form [FilterBuilder].
     ///
     /// # Examples:
@@ -148,18 +243,180 @@ impl BloomFilter {
     pub fn new(mut config: FilterBuilder) -> Self {
         config.complete();
         #[cfg(target_pointer_width = "64")]
-            let bit_set = BloomBitVec::new((config.size >> 6) as usize);
+            let mut bit_set = BloomBitVec::new((config.size >> 6) as usize);
+        #[cfg(target_pointer_width = "32")]
+            let mut bit_set = BloomBitVec::new((config.size >> 5) as usize);
+        if config.enable_dirty_tracking {
+            bit_set.enable_dirty_tracking();
+        }
+        BloomFilter::from_parts(config, bit_set)
+    }
+
+    /// Build a Bloom filter from [FilterBuilder] with exactly the bits at `indices` set, e.g. for a
+    /// reproducible bit pattern in tests and fixtures without inserting elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not smaller than the size.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder};
+    ///
+    /// let bloom = BloomFilter::with_bits_set(FilterBuilder::new(10_000, 0.01), &[3, 100]);
+    /// assert!(bloom.get_bit(3) && bloom.get_bit(100) && !bloom.get_bit(4));
+    /// ```
+    pub fn with_bits_set(config: FilterBuilder, indices: &[u64]) -> Self {
+        let mut bloom = BloomFilter::new(config);
+        for index in indices {
+            bloom.set_bit(*index);
+        }
+        bloom
+    }
+
+    /// Build a Bloom filter form [FilterBuilder] whose bit vector is an anonymous memory map.
+    /// Untouched pages are backed by the shared zero page and only committed when first written,
+    /// which keeps the resident memory of huge, sparsely used filters low. The filter works
+    /// exactly like one built by [BloomFilter::new]; cloning it copies the bits to the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut bloom = BloomFilter::new_lazy(FilterBuilder::new(100_000_000, 0.01)).unwrap();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn new_lazy(mut config: FilterBuilder) -> std::io::Result<Self> {
+        config.complete();
+        #[cfg(target_pointer_width = "64")]
+            let mut bit_set = BloomBitVec::new_lazy((config.size >> 6) as usize)?;
         #[cfg(target_pointer_width = "32")]
-            let bit_set = BloomBitVec::new((config.size >> 5) as usize);
-        BloomFilter { config, bit_set }
+            let mut bit_set = BloomBitVec::new_lazy((config.size >> 5) as usize)?;
+        if config.enable_dirty_tracking {
+            bit_set.enable_dirty_tracking();
+        }
+        Ok(BloomFilter::from_parts(config, bit_set))
+    }
+
+    /// Build a Bloom filter form [FilterBuilder] whose bit vector is the file at `path`, created
+    /// or truncated to the size of the filter and mapped writable. Changes to the filter are
+    /// written to the file by the operating system at any time, and at the latest when
+    /// [BloomFilter::flush] is called. The file holds the underlying byte vector, like
+    /// [BloomFilter::save_to_file], and can be opened again with [BloomFilter::open_mmap].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Membership};
+    ///
+    /// let path = std::env::temp_dir().join("fastbloom_create_mmap_doc.bloom");
+    /// let path = path.to_str().unwrap();
+    /// let mut bloom = BloomFilter::create_mmap(path, FilterBuilder::new(10_000, 0.01)).unwrap();
+    /// bloom.add(b"hello");
+    /// bloom.flush().unwrap();
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn create_mmap(path: &str, mut config: FilterBuilder) -> std::io::Result<Self> {
+        config.complete();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(config.size >> 3)?;
+        let mut bit_set = BloomBitVec::map_file(&file)?;
+        if config.enable_dirty_tracking {
+            bit_set.enable_dirty_tracking();
+        }
+        Ok(BloomFilter::from_parts(config, bit_set))
+    }
+
+    /// Build a Bloom filter from the file at `path` mapped writable, the counterpart of
+    /// [BloomFilter::create_mmap]. The content is the underlying byte vector of the Bloom filter,
+    /// whose length must be a multiple of 8 bytes.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(path: &str, hashes: u32) -> std::io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len();
+        if len == 0 || len % 8 != 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                                           format!("file length {len} is not a multiple of 8 bytes")));
+        }
+        let mut config = FilterBuilder::from_size_and_hashes(len * 8, hashes);
+        config.complete();
+        let bit_set = BloomBitVec::map_file(&file)?;
+        Ok(BloomFilter::from_parts(config, bit_set))
+    }
+
+    /// Writes the changes of a Bloom filter built by [BloomFilter::create_mmap] or
+    /// [BloomFilter::open_mmap] to disk, returning once they are durable. This is a no-op for
+    /// in-memory filters.
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.bit_set.storage.flush()
+    }
+
+    /// Adds `element` to the filter and returns it, for chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter()
+    ///     .with(b"hello")
+    ///     .with(b"world");
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn with(mut self, element: &[u8]) -> Self {
+        self.add(element);
+        self
+    }
+
+    /// Adds all `elements` to the filter and returns it, for chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter()
+    ///     .with_all(["hello", "world"]);
+    /// assert!(bloom.contains(b"world"));
+    /// ```
+    pub fn with_all<T: AsRef<[u8]>>(mut self, elements: impl IntoIterator<Item=T>) -> Self {
+        for element in elements {
+            self.add(element.as_ref());
+        }
+        self
     }
 
     /// Tests whether an element is present in the filter (subject to the specified false
     /// positive rate). And if it is not in this filter, add it to the filter.
     #[inline]
     pub fn add_if_not_contains(&mut self, element: &[u8]) -> bool {
-        bit_check_and_set(&mut self.bit_set, element, self.config.size,
-                          self.config.hashes as u64)
+        match self.config.hash_width {
+            HashWidth::Bits64 => bit_check_and_set(&mut self.bit_set, element, self.config.size,
+                                                   self.config.hashes as u64,
+                                                   self.config.hash_seeds),
+            width => {
+                let indices = indices_128(element, self.config.size, self.config.hashes as u64, width);
+                let res = indices.clone().all(|index| self.bit_set.get(index as usize));
+                self.bit_set.set_all(indices);
+                res
+            }
+        }
+    }
+
+    /// Tests whether an element was added more than once (subject to the false positive rate),
+    /// see [FilterBuilder::enable_duplicate_tracking]. Always false when duplicate tracking is
+    /// disabled.
+    pub fn seen_multiple(&self, element: &[u8]) -> bool {
+        match &self.duplicates {
+            Some(duplicates) => self.get_hash_indices(element).iter()
+                .all(|index| duplicates.get(*index as usize)),
+            None => false,
+        }
     }
 
     /// Build a Bloom filter from file with first four bytes is hashes which is encode by big-endian.
@@ -177,7 +434,7 @@ impl BloomFilter {
 
         let bit_set = BloomBitVec::from_file(&mut f, 4, len);
         
-        BloomFilter { config, bit_set }
+        BloomFilter::from_parts(config, bit_set)
     }
 
     /// Build a Bloom filter from file. The content is underlying byte vector of the Bloom filter.
@@ -190,124 +447,171 @@ impl BloomFilter {
 
         let bit_set = BloomBitVec::from_file(&mut f, 0, len);
         
-        BloomFilter { config, bit_set }
+        BloomFilter::from_parts(config, bit_set)
     }
 
-    /// Build a Bloom filter form `&[u8]`.
+    /// Build a Bloom filter form `&[u8]` in the canonical byte order of
+    /// [BloomFilter::to_vec]: bit `i` of the filter is bit `i % 8` of byte `i / 8`, i.e. the
+    /// bytes are a stream of little-endian 64 bit words. This does not depend on the word width of
+    /// the target, so filters can be exchanged between 32 bit and 64 bit machines.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `array` is not a multiple of 8, i.e. does not hold whole 64 bit
+    /// words. Use [BloomFilter::try_from] to get an error instead.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use fastbloom_rs::BloomFilter;
     /// let mut array = vec![0u8; 4096];
-    /// let bloom = BloomFilter::from_u8_array(array.as_bytes(), 4);
+    /// let bloom = BloomFilter::from_u8_array(&array, 4);
     /// ```
     pub fn from_u8_array(array: &[u8], hashes: u32) -> Self {
+        assert!(array.len().is_multiple_of(8), "{}", FilterError::LengthNotWordAligned(array.len()));
         let mut config =
             FilterBuilder::from_size_and_hashes((array.len() * 8) as u64, hashes);
         config.complete();
-        #[cfg(target_pointer_width = "64")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 5) as usize);
+        let bit_set = BloomBitVec::from_le_bytes(array);
 
-        let ptr = array.as_ptr() as *const usize;
-        #[cfg(target_pointer_width = "64")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 5) as usize);
+        BloomFilter::from_parts(config, bit_set)
+    }
+
+    /// Build a Bloom filter from the bytes of a filter persisted by an older version of the crate
+    /// which hashed elements with murmur3 rather than xxh3, see [HashWidth::LegacyMurmur128].
+    ///
+    /// The two hashing schemes set different bits, so loading such bytes with
+    /// [BloomFilter::from_u8_array] silently reports its members absent. The byte layout itself is
+    /// the same. The returned filter answers queries with the old scheme; convert it with
+    /// [BloomFilter::migrate_to_current] if the elements are still at hand.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, HashWidth};
+    ///
+    /// let bloom = BloomFilter::from_u8_array_legacy(&[0u8; 4096], 4);
+    /// assert_eq!(bloom.config().hash_width, HashWidth::LegacyMurmur128);
+    /// ```
+    pub fn from_u8_array_legacy(array: &[u8], hashes: u32) -> Self {
+        let mut bloom = BloomFilter::from_u8_array(array, hashes);
+        bloom.config.hash_width(HashWidth::LegacyMurmur128);
+        bloom
+    }
+
+    /// Returns a filter in the current xxh3 format ([HashWidth::Bits64] with the default
+    /// [FilterBuilder::hash_seeds]) of the same size and hashes, holding `elements`. Meant for a
+    /// filter from [BloomFilter::from_u8_array_legacy]: its bits cannot be rehashed, so the
+    /// elements it was built from have to be added again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, HashWidth, Membership};
+    ///
+    /// let legacy = BloomFilter::from_u8_array_legacy(&[0u8; 4096], 4);
+    /// let current = legacy.migrate_to_current([b"hello"]);
+    /// assert_eq!(current.config().hash_width, HashWidth::Bits64);
+    /// assert!(current.contains(b"hello"));
+    /// ```
+    pub fn migrate_to_current<I>(&self, elements: I) -> BloomFilter
+        where I: IntoIterator, I::Item: AsRef<[u8]> {
+        let mut bloom = BloomFilter::new(
+            FilterBuilder::from_size_and_hashes(self.config.size, self.config.hashes));
+        for element in elements {
+            bloom.add(element.as_ref());
+        }
+        bloom
+    }
 
-        bit_vec.storage.copy_from_slice(unsafe { &*usize_array });
+    /// Return the bytes of [BloomFilter::to_vec] behind a header carrying everything needed
+    /// to query them: the magic `b"FBLM"`, the hashes (`u32` little-endian), the hash width (one
+    /// byte, 0 for [HashWidth::Bits64], 1 for [HashWidth::Bits128] and 2 for
+    /// [HashWidth::LegacyMurmur128]) and the two
+    /// [FilterBuilder::hash_seeds] (`u64` little-endian). Load it with
+    /// [BloomFilter::from_u8_array_with_header], without passing the hashes separately.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let loaded = BloomFilter::from_u8_array_with_header(&bloom.to_u8_array_with_header()).unwrap();
+    /// assert!(loaded.contains(b"hello"));
+    /// ```
+    pub fn to_u8_array_with_header(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.get_u8_array().len());
+        bytes.extend_from_slice(HEADER_MAGIC);
+        bytes.extend_from_slice(&self.config.hashes.to_le_bytes());
+        write_hash_config(&mut bytes, &self.config);
+        bytes.extend(Vec::<u8>::from(self));
+        bytes
+    }
 
-        BloomFilter { config, bit_set: bit_vec }
+    /// Build a Bloom filter from the format of [BloomFilter::to_u8_array_with_header].
+    pub fn from_u8_array_with_header(bytes: &[u8]) -> Result<Self, FilterError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != HEADER_MAGIC {
+            return Err(FilterError::InvalidEncoding("missing header"));
+        }
+        let hashes = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let (hash_width, seeds) = read_hash_config(&mut &bytes[8..HEADER_LEN])?;
+        let mut bloom = BloomFilter::try_from((&bytes[HEADER_LEN..], hashes))?;
+        bloom.config.hash_width(hash_width);
+        bloom.config.hash_seeds(seeds.0, seeds.1);
+        Ok(bloom)
     }
 
-    /// Build a Bloom filter form `&[u16]`.
+    /// Build a Bloom filter form `&[u16]`. Bit `i` of the filter is bit `i % 16` of element
+    /// `i / 16`, consistent with [BloomFilter::from_u8_array].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array` does not hold whole 64 bit words.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use fastbloom_rs::BloomFilter;
     /// let mut array = vec![0u16; 2048];
-    /// let bloom = BloomFilter::from_u16_array(array.as_bytes(), 4);
+    /// let bloom = BloomFilter::from_u16_array(&array, 4);
     /// ```
     pub fn from_u16_array(array: &[u16], hashes: u32) -> Self {
-        let mut config =
-            FilterBuilder::from_size_and_hashes((array.len() * 16) as u64, hashes);
-        config.complete();
-        #[cfg(target_pointer_width = "64")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 5) as usize);
-
-        let ptr = array.as_ptr() as *const usize;
-        #[cfg(target_pointer_width = "64")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 5) as usize);
-
-        bit_vec.storage.copy_from_slice(unsafe { &*usize_array });
-
-        BloomFilter { config, bit_set: bit_vec }
+        BloomFilter::from_u8_array(&le_bytes(array, u16::to_le_bytes), hashes)
     }
 
 
-    /// Build a Bloom filter form `&[u32]`.
+    /// Build a Bloom filter form `&[u32]`. Bit `i` of the filter is bit `i % 32` of element
+    /// `i / 32`, consistent with [BloomFilter::from_u8_array].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array` does not hold whole 64 bit words.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use fastbloom_rs::BloomFilter;
     /// let mut array = vec![0u32; 1024];
-    /// let bloom = BloomFilter::from_u32_array(array.as_bytes(), 4);
+    /// let bloom = BloomFilter::from_u32_array(&array, 4);
     /// ```
     pub fn from_u32_array(array: &[u32], hashes: u32) -> Self {
-        let mut config =
-            FilterBuilder::from_size_and_hashes((array.len() * 32) as u64, hashes);
-        config.complete();
-        #[cfg(target_pointer_width = "64")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 5) as usize);
-
-        let ptr = array.as_ptr() as *const usize;
-        #[cfg(target_pointer_width = "64")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 5) as usize);
-
-        bit_vec.storage.copy_from_slice(unsafe { &*usize_array });
-
-        BloomFilter { config, bit_set: bit_vec }
+        BloomFilter::from_u8_array(&le_bytes(array, u32::to_le_bytes), hashes)
     }
 
-    /// Build a Bloom filter form `&[u64]`.
+    /// Build a Bloom filter form `&[u64]`. Bit `i` of the filter is bit `i % 64` of element
+    /// `i / 64`, consistent with [BloomFilter::from_u8_array].
     ///
     /// # Examples
     ///
     /// ```rust
     /// use fastbloom_rs::BloomFilter;
     /// let mut array = vec![0u64; 512];
-    /// let bloom = BloomFilter::from_u32_array(array.as_bytes(), 4);
+    /// let bloom = BloomFilter::from_u64_array(&array, 4);
     /// ```
     pub fn from_u64_array(array: &[u64], hashes: u32) -> Self {
-        let mut config =
-            FilterBuilder::from_size_and_hashes((array.len() * 64) as u64, hashes);
-        config.complete();
-        #[cfg(target_pointer_width = "64")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let mut bit_vec = BloomBitVec::new((config.size >> 5) as usize);
-
-        let ptr = array.as_ptr() as *const usize;
-        #[cfg(target_pointer_width = "64")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 6) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let usize_array = slice_from_raw_parts(ptr, (config.size >> 5) as usize);
-
-        bit_vec.storage.copy_from_slice(unsafe { &*usize_array });
-
-        BloomFilter { config, bit_set: bit_vec }
+        BloomFilter::from_u8_array(&le_bytes(array, u64::to_le_bytes), hashes)
     }
 
     /// Returns the configuration/builder of the Bloom filter.
@@ -336,6 +640,25 @@ impl BloomFilter {
         file.write_all(bytes).unwrap();
     }
 
+    /// Returns an owned copy of the bytes of [BloomFilter::get_u8_array], a snapshot which stays
+    /// valid after the filter changes. The copy is in canonical little-endian order on every
+    /// target, including big-endian ones where [BloomFilter::get_u8_array] is not.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let empty = bloom.to_vec();
+    /// bloom.add(b"hello");
+    /// assert_ne!(bloom.to_vec(), empty);
+    /// assert!(empty.iter().all(|byte| *byte == 0));
+    /// ```
+    pub fn to_vec(&self) -> Vec<u8> {
+        Vec::<u8>::from(self)
+    }
+
     /// Save the bloom filter to file, and the content of the file is underlying byte 
     /// vector of the Bloom filter.
     pub fn save_to_file(&mut self, path: &str) {
@@ -344,16 +667,59 @@ impl BloomFilter {
         file.write_all(bytes).unwrap();
     }
 
-    /// Return the underlying byte vector of the Bloom filter.
+    /// Return the underlying byte vector of the Bloom filter. The bytes are in canonical order:
+    /// bit `i` of the filter is bit `i % 8` of byte `i / 8`, i.e. a stream of little-endian 64 bit
+    /// words, whatever the word width of the target. They can be loaded on any machine with
+    /// [BloomFilter::from_u8_array].
+    ///
+    /// The slice is a view of the native words, so it is in canonical order on little-endian
+    /// targets only; [BloomFilter::to_vec] returns the canonical bytes on any target.
+    ///
+    /// The bytes borrow the filter, so it can not outlive a later `add` or `clear`, which would
+    /// change the bytes under it; copy it with [BloomFilter::to_vec] to keep a snapshot:
+    ///
+    /// ```compile_fail
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let bytes = bloom.get_u8_array();
+    /// bloom.add(b"hello");
+    /// assert!(bytes.iter().all(|byte| *byte == 0));
+    /// ```
     pub fn get_u8_array(&self) -> &[u8] {
         let storage = &self.bit_set.storage;
-        let ptr = storage.as_ptr();
-        let u8_ptr = ptr as *const u8;
+        let u8_ptr = storage.as_ptr() as *const u8;
+        let len = storage.len() * std::mem::size_of::<usize>();
+        unsafe { &*slice_from_raw_parts(u8_ptr, len) }
+    }
+
+    /// Return the underlying byte vector of the Bloom filter mutably, so that external code can
+    /// populate the filter in place. The slice is a view of the native words like
+    /// [BloomFilter::get_u8_array], so it is in the canonical order of [BloomFilter::to_vec] on
+    /// little-endian targets only. The filter trusts whatever bits are written; the caller must
+    /// use the same hashing scheme to stay meaningful, and the length of the slice is fixed.
+    ///
+    /// Writes through this slice are not seen by dirty tracking, so the next
+    /// [BloomFilter::clear_dirty] falls back to a full clear.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.as_bytes_mut().fill(0xff);
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.bit_set.untrack_dirty();
+        let storage = &mut self.bit_set.storage;
+        let u8_ptr = storage.as_mut_ptr() as *mut u8;
         #[cfg(target_pointer_width = "64")]
-            let ptr = slice_from_raw_parts(u8_ptr, storage.len() * 8);
+            let ptr = slice_from_raw_parts_mut(u8_ptr, storage.len() * 8);
         #[cfg(target_pointer_width = "32")]
-            let ptr = slice_from_raw_parts(u8_ptr, storage.len() * 4);
-        unsafe { &*ptr }
+            let ptr = slice_from_raw_parts_mut(u8_ptr, storage.len() * 4);
+        unsafe { &mut *ptr }
     }
 
     /// Return the underlying u16 vector of the Bloom filter.
@@ -367,6 +733,26 @@ impl BloomFilter {
         unsafe { &*ptr }
     }
 
+    /// Return the bytes of [BloomFilter::to_vec] as an Arrow buffer, the same type as
+    /// `arrow::buffer::Buffer`, e.g. to wrap them in a `UInt8Array`. The bytes are copied once
+    /// into memory owned by the buffer, so it stays valid after the filter changes or is dropped,
+    /// and are in canonical order on every target.
+    /// Only available with the `arrow` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert_eq!(bloom.to_arrow_buffer().as_slice(), &bloom.to_vec()[..]);
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow_buffer(&self) -> arrow_buffer::Buffer {
+        arrow_buffer::Buffer::from_vec(self.to_vec())
+    }
+
     /// Return the underlying u32 vector of the Bloom filter.
     pub fn get_u32_array(&self) -> &[u32] {
         let storage = &self.bit_set.storage;
@@ -378,23 +764,144 @@ impl BloomFilter {
         unsafe { &*ptr }
     }
 
-    /// Return the underlying u64 vector of the Bloom filter.
+    /// Return the underlying u64 vector of the Bloom filter, where bit `i` of the filter is bit
+    /// `i % 64` of element `i / 64`, consistent with [BloomFilter::from_u64_array].
+    ///
+    /// Only available on 64 bit targets, where it borrows the storage. The `u32` words of a 32 bit
+    /// target can not be viewed as `u64`, use [BloomFilter::to_u64_vec] there.
+    #[cfg(target_pointer_width = "64")]
     pub fn get_u64_array(&self) -> &[u64] {
         let storage = &self.bit_set.storage;
-        let ptr = storage.as_ptr() as *const u64;
+        let ptr = slice_from_raw_parts(storage.as_ptr() as *const u64, storage.len());
+        unsafe { &*ptr }
+    }
+
+    /// Returns a copy of the words of [BloomFilter::get_u64_array] on any target. On 32 bit
+    /// targets the words are packed in pairs, and an odd number of words is padded with a zero
+    /// upper half, so the call never fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Hashes, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let words = bloom.to_u64_vec();
+    /// assert!(BloomFilter::from_u64_array(&words, bloom.hashes()).contains(b"hello"));
+    /// ```
+    pub fn to_u64_vec(&self) -> Vec<u64> {
         #[cfg(target_pointer_width = "64")]
-            let ptr = slice_from_raw_parts(ptr, storage.len());
-        if cfg!(target_pointer_width= "32") {
-            if storage.len() % 2 != 0 {
-                panic!("BloomBitVec with len {} can't export as u64 array!", storage.len())
-            }
-        }
+        return self.get_u64_array().to_vec();
         #[cfg(target_pointer_width = "32")]
-            let ptr = slice_from_raw_parts(ptr, storage.len() / 2usize);
+        return pack_u32_pairs(self.get_u32_array());
+    }
 
-        unsafe { &*ptr }
+
+    /// Fold the Bloom filter `times` times into a filter of size `m >> times` by ORing the halves
+    /// of the bit vector together (`new[i] = old[i] | old[i + m / 2]`), a lossy downsize for
+    /// shipping a smaller approximate filter. Returns [None] if the size is not evenly divisible,
+    /// the folded size is not a whole number of words, or it is smaller than the number of hashes.
+    ///
+    /// Because the folded size divides the original size, every index of an element in the
+    /// original filter maps to the same index modulo the new size, so members remain present.
+    /// Each fold roughly doubles the false positive probability.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::from_size_and_hashes(1 << 20, 7).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let folded = bloom.fold(1).unwrap();
+    /// assert_eq!(folded.config().size, bloom.config().size / 2);
+    /// assert!(folded.contains(b"hello"));
+    /// ```
+    pub fn fold(&self, times: u32) -> Option<BloomFilter> {
+        if times >= u64::BITS { return None; }
+        let size = self.config.size >> times;
+        if size == 0 || size << times != self.config.size || size & SUFFIX as u64 != 0
+            || size < self.config.hashes as u64 {
+            return None;
+        }
+        let mut config = self.config.clone();
+        config.size = size;
+        let mut folded = BloomFilter::new(config);
+        let words = folded.bit_set.storage.len();
+        for (i, word) in self.bit_set.storage.iter().enumerate() {
+            folded.bit_set.storage[i % words] |= *word;
+        }
+        Some(folded)
+    }
+
+    /// Concatenate the underlying bit vectors of `filters` into one larger Bloom filter whose size
+    /// is the sum of their sizes. All filters must use the same number of hashes.
+    ///
+    /// This is only meaningful when each filter is a shard covering a disjoint range of the bits
+    /// of the larger filter, i.e. the shards were populated with indices of the larger filter
+    /// minus the shard offset. Concatenating independently built filters does **not** produce a
+    /// filter containing their elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::from_size_and_hashes(1 << 16, 7).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let (low, high) = bloom.get_u8_array().split_at(1 << 12);
+    /// let shards = [BloomFilter::from_u8_array(low, 7), BloomFilter::from_u8_array(high, 7)];
+    /// let concat = BloomFilter::concat(&shards).unwrap();
+    /// assert!(concat.contains(b"hello"));
+    /// ```
+    pub fn concat(filters: &[BloomFilter]) -> Result<BloomFilter, FilterError> {
+        let first = filters.first().ok_or(FilterError::NoFilters)?;
+        let hashes = first.hashes();
+        if let Some(other) = filters.iter().find(|filter| filter.hashes() != hashes) {
+            return Err(FilterError::HashesMismatch { expected: hashes, found: other.hashes() });
+        }
+        if filters.iter().any(|filter| filter.config.hash_width != first.config.hash_width
+            || filter.config.hash_seeds != first.config.hash_seeds) {
+            return Err(FilterError::HashConfigMismatch);
+        }
+        let mut config = first.config.clone();
+        config.size = filters.iter().map(|filter| filter.config.size).sum();
+        let size = config.size;
+        let mut storage = Vec::with_capacity(filters.iter().map(|f| f.bit_set.storage.len()).sum());
+        for filter in filters {
+            storage.extend_from_slice(&filter.bit_set.storage);
+        }
+        let mut bit_set = BloomBitVec { storage: storage.into(), nbits: size, dirty: None };
+        if config.enable_dirty_tracking {
+            bit_set.enable_dirty_tracking();
+        }
+        Ok(BloomFilter::from_parts(config, bit_set))
     }
 
+    /// Build an empty Bloom filter with the configuration of `other`, i.e. the same size, hashes,
+    /// hash width and seeds, e.g. as the accumulator to [BloomFilter::union] filters into.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let mut acc = BloomFilter::empty_like(&bloom);
+    /// assert!(acc.is_empty());
+    /// assert!(acc.union(&bloom));
+    /// assert!(acc.contains(b"hello"));
+    /// ```
+    pub fn empty_like(other: &BloomFilter) -> BloomFilter {
+        let mut bit_set = BloomBitVec::new(other.bit_set.storage.len());
+        bit_set.nbits = other.bit_set.nbits;
+        if other.config.enable_dirty_tracking {
+            bit_set.enable_dirty_tracking();
+        }
+        BloomFilter::from_parts(other.config.clone(), bit_set)
+    }
 
     /// Performs the union operation on two compatible bloom filters. This is achieved through a
     /// bitwise OR operation on their bit vectors. This operations is lossless, i.e. no elements
@@ -407,584 +914,4699 @@ impl BloomFilter {
         } else { false }
     }
 
-    /// Performs the intersection operation on two compatible bloom filters. This is achieved
-    /// through a bitwise AND operation on their bit vectors. The operations doesn't introduce
-    /// any false negatives but it does raise the false positive probability. The the false
-    /// positive probability in the resulting Bloom filter is at most the false-positive probability
-    /// in one of the constituent bloom filters
-    pub fn intersect(&mut self, other: &BloomFilter) -> bool {
-        if self.compatible(other) {
-            self.bit_set.and(&other.bit_set);
-            true
-        } else { false }
-    }
-
-    /// Returns [true] if the Bloom filter does not contain any elements
+    /// Performs the union operation on two filters of the same size, hash width and seeds whose
+    /// hashes differ, e.g. built for slightly different false positive probabilities. The bit
+    /// vectors are ORed and queries keep using `self.hashes`, so every member of `self` stays
+    /// present. A member of `other` is present as well when `other` uses at least as many hashes,
+    /// because the probes of an element are a prefix of the same sequence for any number of
+    /// hashes; with fewer hashes in `other` its members are probed at bits it never set. Returns
+    /// false, leaving the filter unchanged, if the filters can not be combined.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::from_size_and_hashes(1 << 16, 5).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let mut other = FilterBuilder::from_size_and_hashes(1 << 16, 7).build_bloom_filter();
+    /// other.add(b"world");
+    /// assert!(bloom.union_ignore_hashes(&other));
+    /// assert!(bloom.contains(b"hello"));
+    /// assert!(bloom.contains(b"world"));
+    /// ```
+    pub fn union_ignore_hashes(&mut self, other: &BloomFilter) -> bool {
+        if self.config.size == other.config.size && self.config.hash_width == other.config.hash_width
+            && self.config.hash_seeds == other.config.hash_seeds {
+            self.bit_set.or(&other.bit_set);
+            true
+        } else { false }
+    }
+
+    /// Performs the union operation with a filter of a different size, when one size divides the
+    /// other and both use the same hashes. A smaller `other` is tiled over this filter: every
+    /// index of an element in this filter is its index in `other` plus a multiple of `other`'s
+    /// size, so all members of `other` are present afterwards. A larger `other` is folded into
+    /// this filter like [BloomFilter::fold]. Returns false, leaving the filter unchanged, if the
+    /// filters can not be combined.
+    ///
+    /// Tiling sets `self.size / other.size` bits for every bit of `other`, so the fill ratio and
+    /// with it the false positive probability rise faster than with a plain
+    /// [BloomFilter::union]; folding raises them like [BloomFilter::fold].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut other = FilterBuilder::from_size_and_hashes(1 << 16, 7).build_bloom_filter();
+    /// other.add(b"hello");
+    /// let mut bloom = FilterBuilder::from_size_and_hashes(1 << 17, 7).build_bloom_filter();
+    /// assert!(bloom.union_resample(&other));
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn union_resample(&mut self, other: &BloomFilter) -> bool {
+        let (m, n) = (self.config.size, other.config.size);
+        let mut same = other.config.clone();
+        same.size = m;
+        if n == 0 || (m % n != 0 && n % m != 0) || !self.config.is_compatible_to(&same) {
+            return false;
+        }
+        self.bit_set.or_resampled(&other.bit_set);
+        true
+    }
+
+    /// Performs the union operation with a serialized Bloom filter, as produced by
+    /// [BloomFilter::to_vec], without constructing an intermediate filter. The geometry is
+    /// validated from the length of `bytes` and `hashes` before any bit is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let builder = FilterBuilder::new(10_000, 0.01);
+    /// let mut other = builder.clone().build_bloom_filter();
+    /// other.add(b"hello");
+    /// let mut bloom = builder.clone().build_bloom_filter();
+    /// bloom.union_bytes(&other.to_vec(), other.config().hashes).unwrap();
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn union_bytes(&mut self, bytes: &[u8], hashes: u32) -> Result<(), FilterError> {
+        if hashes != self.config.hashes {
+            return Err(FilterError::HashesMismatch { expected: self.config.hashes, found: hashes });
+        }
+        let size = bytes.len() as u64 * 8;
+        if size != self.config.size {
+            return Err(FilterError::SizeMismatch { expected: self.config.size, found: size });
+        }
+        self.bit_set.or_bytes(bytes);
+        Ok(())
+    }
+
+    /// Folds serialized Bloom filters given as `(bytes, hashes)` pairs into this one with
+    /// [BloomFilter::union_bytes], e.g. in a reducer, returning how many were merged. Stops at the
+    /// first incompatible pair and returns its error; the pairs before it stay merged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let builder = FilterBuilder::new(10_000, 0.01);
+    /// let shards: Vec<(Vec<u8>, u32)> = ["hello", "world"].iter().map(|element| {
+    ///     let mut shard = builder.clone().build_bloom_filter();
+    ///     shard.add(element.as_bytes());
+    ///     (shard.to_vec(), shard.config().hashes)
+    /// }).collect();
+    /// let mut bloom = builder.clone().build_bloom_filter();
+    /// assert_eq!(bloom.union_bytes_many(shards).unwrap(), 2);
+    /// assert!(bloom.contains(b"world"));
+    /// ```
+    pub fn union_bytes_many<I: IntoIterator<Item=(Vec<u8>, u32)>>(&mut self, iter: I)
+                                                                   -> Result<usize, FilterError> {
+        let mut merged = 0;
+        for (bytes, hashes) in iter {
+            self.union_bytes(&bytes, hashes)?;
+            merged += 1;
+        }
+        Ok(merged)
+    }
+
+    /// Performs the intersection operation on two compatible bloom filters. This is achieved
+    /// through a bitwise AND operation on their bit vectors. The operations doesn't introduce
+    /// any false negatives but it does raise the false positive probability. The the false
+    /// positive probability in the resulting Bloom filter is at most the false-positive probability
+    /// in one of the constituent bloom filters
+    pub fn intersect(&mut self, other: &BloomFilter) -> bool {
+        if self.compatible(other) {
+            self.bit_set.and(&other.bit_set);
+            true
+        } else { false }
+    }
+
+    /// Resets the bits in `[bit_start, bit_end)` to zero, leaving the rest of the filter intact.
+    /// This is useful when ranges of bits are assigned to separate buckets, e.g. time windows.
+    /// Note that clearing bits of a plain Bloom filter may introduce false negatives for the
+    /// other elements whose hashes fall into the range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let size = bloom.config().size;
+    /// bloom.clear_range(0, size);
+    /// assert!(!bloom.contains(b"hello"));
+    /// ```
+    pub fn clear_range(&mut self, bit_start: u64, bit_end: u64) {
+        assert!(bit_start <= bit_end, "bit_start must not larger than bit_end!");
+        assert!(bit_end <= self.config.size, "bit_end must not larger than size!");
+        self.bit_set.clear_range(bit_start as usize, bit_end as usize);
+    }
+
+    /// Sets bit `index` of the filter, for custom encodings on top of the bit vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not smaller than the size.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.set_bit(42);
+    /// assert!(bloom.get_bit(42));
+    /// bloom.clear_bit(42);
+    /// assert!(!bloom.get_bit(42));
+    /// ```
+    pub fn set_bit(&mut self, index: u64) {
+        assert!(index < self.config.size, "index must smaller than size!");
+        self.bit_set.set(index as usize);
+    }
+
+    /// Clears bit `index` of the filter, see [BloomFilter::set_bit]. Clearing a bit shared with
+    /// other elements removes them from the filter too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not smaller than the size.
+    pub fn clear_bit(&mut self, index: u64) {
+        assert!(index < self.config.size, "index must smaller than size!");
+        self.bit_set.unset(index as usize);
+    }
+
+    /// Returns bit `index` of the filter, see [BloomFilter::set_bit].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not smaller than the size.
+    pub fn get_bit(&self, index: u64) -> bool {
+        assert!(index < self.config.size, "index must smaller than size!");
+        self.bit_set.get(index as usize)
+    }
+
+    /// Returns the bit indices of `element` split into `(word, bit)` pairs, where `word` is the
+    /// index of the `usize` word holding the bit and `bit` its position in that word. These are
+    /// the positions `add` sets, e.g. to route each probe to the shard of a custom bitmap store.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let pairs = bloom.get_word_bit_indices(b"hello");
+    /// let indices: Vec<u64> = pairs.iter()
+    ///     .map(|&(word, bit)| word * usize::BITS as u64 + bit as u64)
+    ///     .collect();
+    /// assert_eq!(indices, bloom.get_hash_indices(b"hello"));
+    /// ```
+    pub fn get_word_bit_indices(&self, element: &[u8]) -> Vec<(u64, u32)> {
+        let shift = usize::BITS.trailing_zeros();
+        self.get_hash_indices(element).into_iter()
+            .map(|index| (index >> shift, (index & SUFFIX as u64) as u32))
+            .collect()
+    }
+
+    /// Like [Membership::get_hash_indices], but as `u32`, which halves the size of index dumps
+    /// for filters of at most `2^32` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter has more than `2^32` bits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let indices: Vec<u64> = bloom.get_hash_indices_u32(b"hello").into_iter()
+    ///     .map(u64::from)
+    ///     .collect();
+    /// assert_eq!(indices, bloom.get_hash_indices(b"hello"));
+    /// ```
+    pub fn get_hash_indices_u32(&self, element: &[u8]) -> Vec<u32> {
+        assert!(self.config.size <= 1 << 32, "size must not larger than 2^32 for u32 indices!");
+        self.get_hash_indices(element).into_iter().map(|index| index as u32).collect()
+    }
+
+    /// Like [Membership::contains_hash_indices], for indices from
+    /// [BloomFilter::get_hash_indices_u32].
+    pub fn contains_hash_indices_u32(&self, indices: &[u32]) -> bool {
+        indices.iter().all(|index| self.bit_set.get(*index as usize))
+    }
+
+    /// Adds an element by its indices from [BloomFilter::get_hash_indices_u32], e.g. computed on
+    /// another node that shipped only the indices instead of the element itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(10_000, 0.01);
+    /// let mapper = builder.build_bloom_filter();
+    /// let mut reducer = builder.build_bloom_filter();
+    /// reducer.add_hash_indices_u32(&mapper.get_hash_indices_u32(b"hello"));
+    /// assert!(reducer.contains(b"hello"));
+    /// ```
+    pub fn add_hash_indices_u32(&mut self, indices: &[u32]) {
+        self.add_indices(indices.iter().map(|index| *index as u64));
+    }
+
+    /// Adds an element by the two xxh3 hashes its indices are derived from, as returned by
+    /// [crate::hashing::hash_pair] with the seed of the filter, e.g. precomputed elsewhere in
+    /// bulk. Equivalent to adding each element with [Membership::add], without hashing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter uses [HashWidth::Bits128], whose indices come from another hash.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    /// use fastbloom_rs::hashing::hash_pair;
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add_hash_pairs(&[hash_pair(b"hello", 0), hash_pair(b"world", 0)]);
+    /// assert!(bloom.contains(b"hello"));
+    /// assert_eq!(bloom.contains_hash_pairs(&[hash_pair(b"world", 0), hash_pair(b"fastbloom", 0)]),
+    ///            vec![true, false]);
+    /// ```
+    pub fn add_hash_pairs(&mut self, pairs: &[(u64, u64)]) {
+        assert_eq!(self.config.hash_width, HashWidth::Bits64, "hash pairs need HashWidth::Bits64!");
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        for pair in pairs {
+            self.add_indices(pair_probes(*pair, m, k));
+        }
+    }
+
+    /// Tests whether each element given by its hash pair is present in the filter, see
+    /// [BloomFilter::add_hash_pairs].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter uses [HashWidth::Bits128].
+    pub fn contains_hash_pairs(&self, pairs: &[(u64, u64)]) -> Vec<bool> {
+        assert_eq!(self.config.hash_width, HashWidth::Bits64, "hash pairs need HashWidth::Bits64!");
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        pairs.iter()
+            .map(|pair| pair_probes(*pair, m, k).all(|index| self.bit_set.get(index as usize)))
+            .collect()
+    }
+
+    /// Adds a `u64`, e.g. a numeric ID, as its 8 little-endian bytes: the same as
+    /// `add(&value.to_le_bytes())`, but hashed by xxh3's fixed 8 byte path directly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add_u64(87);
+    /// assert!(bloom.contains_u64(87));
+    /// assert!(bloom.contains(&87u64.to_le_bytes()));
+    /// ```
+    pub fn add_u64(&mut self, value: u64) {
+        match self.config.hash_width {
+            HashWidth::Bits64 => {
+                let (m, k) = (self.config.size, self.config.hashes as u64);
+                self.add_indices(pair_probes(self.u64_hash_pair(value), m, k));
+            }
+            _ => self.add(&value.to_le_bytes()),
+        }
+    }
+
+    /// Tests whether a `u64` added by [BloomFilter::add_u64] (or as its 8 little-endian bytes) is
+    /// present in the filter, the same as `contains(&value.to_le_bytes())` but faster.
+    #[inline]
+    pub fn contains_u64(&self, value: u64) -> bool {
+        match self.config.hash_width {
+            HashWidth::Bits64 => {
+                let (m, k) = (self.config.size, self.config.hashes as u64);
+                pair_probes(self.u64_hash_pair(value), m, k)
+                    .all(|index| self.bit_set.get(index as usize))
+            }
+            _ => self.contains(&value.to_le_bytes()),
+        }
+    }
+
+    #[inline(always)]
+    fn u64_hash_pair(&self, value: u64) -> (u64, u64) {
+        let (seed1, seed2) = self.config.hash_seeds;
+        (xxh3_64_u64(value, seed1), xxh3_64_u64(value, seed2))
+    }
+
+    /// Sets the bits at `indices` of one element, tracking it as a duplicate when they were all
+    /// set already.
+    fn add_indices(&mut self, indices: impl Iterator<Item=u64> + Clone) {
+        *self.fpr_cache.inserts.get_mut() += 1;
+        let seen = self.duplicates.is_some()
+            && indices.clone().all(|index| self.bit_set.get(index as usize));
+        self.bit_set.set_all(indices.clone());
+        if seen {
+            let duplicates = self.duplicates.as_mut().unwrap();
+            for index in indices {
+                duplicates.set(index as usize);
+            }
+        }
+    }
+
+    /// Returns every `stride`-th bit of the filter, starting with bit 0, e.g. to render an
+    /// approximate heatmap of its fill. This is a diagnostic view only: it does not preserve
+    /// membership and cannot be turned back into a filter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let preview = bloom.subsample(64);
+    /// assert_eq!(preview.len() as u64, (bloom.config().size + 63) / 64);
+    /// assert!(preview.iter().all(|bit| !bit));
+    /// ```
+    pub fn subsample(&self, stride: usize) -> Vec<bool> {
+        assert!(stride > 0, "stride must larger than 0!");
+        (0..self.config.size as usize).step_by(stride).map(|i| self.bit_set.get(i)).collect()
+    }
+
+    /// Returns the number of set bits in each of `buckets` equal regions of the bit space, as a
+    /// diagnostic of hash quality: uniformly hashed keys give a roughly flat histogram, while
+    /// keys clustering under the hash show up as skewed buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let histogram = bloom.probe_histogram(16);
+    /// assert_eq!(histogram.len(), 16);
+    /// assert_eq!(histogram.iter().sum::<u64>(), bloom.get_hash_indices(b"hello").len() as u64);
+    /// ```
+    pub fn probe_histogram(&self, buckets: usize) -> Vec<u64> {
+        assert!(buckets > 0, "buckets must larger than 0!");
+        let size = self.config.size as u128;
+        let bound = |i: usize| (size * i as u128 / buckets as u128) as usize;
+        (0..buckets).map(|i| self.bit_set.count_ones_in(bound(i), bound(i + 1))).collect()
+    }
+
+    /// Returns the number of bits that differ between two compatible bloom filters, or [None] if
+    /// they are not compatible. Neither filter is modified. This is useful to detect the drift of
+    /// a filter from a previous snapshot of it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let snapshot = bloom.clone();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.hamming_distance(&snapshot).unwrap() > 0);
+    /// ```
+    pub fn hamming_distance(&self, other: &BloomFilter) -> Option<u64> {
+        if !self.compatible(other) { return None; }
+        Some(self.bit_set.xor_count_ones(&other.bit_set))
+    }
+
+    /// Checks the internal consistency of the filter: at least one hash, a size of whole 64 bit
+    /// words matching the bit vector, and a storage of exactly the words needed for the size. This
+    /// is an explicit and cheap check, meant to be asserted in tests after `from_*_array` calls or
+    /// writes through [BloomFilter::as_bytes_mut].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// assert_eq!(bloom.check_invariants(), Ok(()));
+    /// ```
+    ///
+    /// [BloomFilter::contains_checked] answers `false` for every element while this fails.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let size = self.config.size;
+        let word_bits = usize::BITS as u64;
+        if self.config.hashes == 0 {
+            return Err("hashes must larger than 0".to_string());
+        }
+        if size == 0 || !size.is_multiple_of(64) {
+            return Err(format!("size must be a positive multiple of 64 bits, found {size}"));
+        }
+        if self.bit_set.nbits != size {
+            return Err(format!("bit vector has {} bits but size is {size}", self.bit_set.nbits));
+        }
+        let words = self.bit_set.storage.len() as u64;
+        if words != size.div_ceil(word_bits) {
+            return Err(format!("storage has {words} words but size {size} needs {}",
+                               size.div_ceil(word_bits)));
+        }
+        Ok(())
+    }
+
+    /// Removes all elements from the filter. When dirty tracking is enabled (see
+    /// [FilterBuilder::enable_dirty_tracking]) only the words written since construction or the
+    /// last clear are zeroed, otherwise this falls back to a full [Membership::clear].
+    pub fn clear_dirty(&mut self) {
+        self.bit_set.clear_dirty();
+        if let Some(duplicates) = &mut self.duplicates {
+            duplicates.clear();
+        }
+        if self.config.enable_dirty_tracking && self.bit_set.dirty.is_none() {
+            self.bit_set.enable_dirty_tracking();
+        }
+    }
+
+    /// Returns [true] if the Bloom filter does not contain any elements
     pub fn is_empty(&self) -> bool {
         self.bit_set.is_empty()
     }
 
-    /// Returns estimated cardinality of the set
-    /// see [Scalable and Efficient Privacy Preserving Global Itemset Support Approximation Using Bloom Filters](https://inria.hal.science/hal-01284874/document) as reference
-    pub fn estimate_set_cardinality(&self) -> f64 {
-        (self.bit_set.count_zeros() as f64 / self.config.size as f64).ln() / (self.hashes() as f64 * (1.0 - 1.0/self.config.size as f64).ln())
+    /// Returns the number of calls to [Membership::add] and [Membership::contains] on this filter
+    /// and the total nanoseconds spent in them, e.g. to compare hits and misses without a
+    /// profiler. Only available with the `metrics` feature; without it nothing is recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert_eq!(bloom.op_stats().adds, 1);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn op_stats(&self) -> OpStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the estimated number of distinct elements in the filter, i.e. the rounded
+    /// [BloomFilter::estimate_set_cardinality]. This is an estimate, not an exact count, and it
+    /// saturates at [u64::MAX] once every bit is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// bloom.add(b"world");
+    /// assert_eq!(bloom.len(), 2);
+    /// assert_eq!(bloom.capacity(), 10_000);
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.estimate_set_cardinality().round() as u64
+    }
+
+    /// Returns the number of elements the filter was sized for, i.e. the expected elements of its
+    /// configuration. More elements can be added, at the cost of a higher false positive rate.
+    pub fn capacity(&self) -> u64 {
+        self.config.expected_elements
+    }
+
+    /// Returns the size of the filter in bits.
+    pub fn size_bits(&self) -> u64 {
+        self.config.size
+    }
+
+    /// Returns the size of the filter in bytes, the length of [BloomFilter::get_u8_array].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// assert_eq!(bloom.size_bytes(), bloom.size_bits() / 8);
+    /// ```
+    pub fn size_bytes(&self) -> u64 {
+        (self.bit_set.storage.len() * std::mem::size_of::<usize>()) as u64
+    }
+
+    /// Returns estimated cardinality of the set
+    /// see [Scalable and Efficient Privacy Preserving Global Itemset Support Approximation Using Bloom Filters](https://inria.hal.science/hal-01284874/document) as reference
+    pub fn estimate_set_cardinality(&self) -> f64 {
+        estimate_cardinality(self.bit_set.count_zeros() as u64, self.config.size, self.hashes())
+    }
+
+    /// Returns the optimal hashes for the size of the filter and the elements it holds according
+    /// to [BloomFilter::estimate_set_cardinality]. A suggestion lower than [Hashes::hashes] means
+    /// the filter was over-provisioned and a rebuild with fewer hashes queries faster at no cost
+    /// in false positives. This is advisory only: the hashes of an existing filter cannot change
+    /// without losing its elements. An empty filter returns its current hashes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Hashes, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::from_size_and_hashes(102_400, 30).build_bloom_filter();
+    /// for x in 0..5000u64 {
+    ///     bloom.add(&x.to_le_bytes());
+    /// }
+    /// assert!(bloom.suggested_hashes() < bloom.hashes());
+    /// ```
+    pub fn suggested_hashes(&self) -> u32 {
+        let zeros = self.bit_set.count_zeros() as u64;
+        if zeros == self.config.size { return self.hashes(); }
+        let estimate = estimate_cardinality(zeros, self.config.size, self.hashes());
+        if !estimate.is_finite() { return 1; }
+        optimal_k((estimate.round() as u64).max(1), self.config.size).clamp(1, MAX_HASHES)
+    }
+
+    /// Returns estimated cardinality of the set by linear counting, `-(m / k) * ln(zeros / m)`.
+    /// Linear counting estimates the number of distinct bit positions set from the fraction of
+    /// zero bits, which is exact for `k = 1`; every element sets up to `k` positions, so the count
+    /// is divided by `k`. This ignores the probes of an element colliding with each other, and it
+    /// is the first order approximation of [BloomFilter::estimate_set_cardinality], so both agree
+    /// closely for large `m` and can be compared as a sanity check. Both are accurate while a fair
+    /// share of the bits are still zero and return infinity once none are left.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// for x in 0..1000u64 {
+    ///     bloom.add(&x.to_le_bytes());
+    /// }
+    /// assert!((bloom.estimate_set_cardinality_linear_counting() - 1000.0).abs() < 50.0);
+    /// ```
+    pub fn estimate_set_cardinality_linear_counting(&self) -> f64 {
+        let m = self.config.size as f64;
+        let zeros = self.bit_set.count_zeros() as f64;
+        -(m / self.config.hashes as f64) * (zeros / m).ln()
+    }
+
+    /// Returns estimated cardinality of the union of two compatible bloom filters, or [None] if
+    /// they are not compatible. The union is computed on the fly while counting bits, so neither
+    /// filter is modified and no merged filter is allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let builder = FilterBuilder::new(10_000, 0.01);
+    /// let mut a = builder.clone().build_bloom_filter();
+    /// let mut b = builder.clone().build_bloom_filter();
+    /// a.add(b"hello");
+    /// b.add(b"hello");
+    /// b.add(b"world");
+    /// let estimate = a.estimate_union_cardinality(&b).unwrap();
+    /// assert!((estimate - 2.0).abs() < 0.5);
+    /// ```
+    pub fn estimate_union_cardinality(&self, other: &BloomFilter) -> Option<f64> {
+        if !self.compatible(other) { return None; }
+        let zeros = self.bit_set.union_count_zeros(&other.bit_set);
+        Some(estimate_cardinality(zeros, self.config.size, self.hashes()))
+    }
+
+    /// Returns the fraction of bits that are set in the Bloom filter. This scans the whole
+    /// underlying bit vector.
+    pub fn fill_ratio(&self) -> f64 {
+        1.0 - self.bit_set.count_zeros() as f64 / self.config.size as f64
+    }
+
+    /// Like [Membership::contains], but never panics on a corrupt filter, e.g. one whose size and
+    /// storage disagree after misuse of [BloomFilter::as_bytes_mut], for servers answering from
+    /// untrusted input. Returns `false` when [BloomFilter::check_invariants] fails or a probe falls
+    /// outside of the storage, at the cost of checking every access.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains_checked(b"hello"));
+    /// assert!(!bloom.contains_checked(b"world"));
+    /// ```
+    pub fn contains_checked(&self, element: &[u8]) -> bool {
+        if self.check_invariants().is_err() { return false; }
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        let set = |index: u64| self.bit_set.get_checked(index as usize) == Some(true);
+        match self.config.hash_width {
+            HashWidth::Bits64 => probes(element, m, k, self.config.hash_seeds).all(set),
+            width => indices_128(element, m, k, width).all(set),
+        }
+    }
+
+    /// Like [Membership::add], but without bounds checks on the underlying bit vector, for hot
+    /// loops over a filter whose integrity the caller has verified. Adds are not counted by the
+    /// operation stats of the `metrics` feature. With duplicate tracking enabled (see
+    /// [FilterBuilder::enable_duplicate_tracking]) this falls back to the checked
+    /// [Membership::add].
+    ///
+    /// # Safety
+    ///
+    /// [BloomFilter::check_invariants] must return `Ok`, i.e. the storage must hold every bit
+    /// below the size. This holds for every filter built by this crate, but not necessarily for
+    /// one deserialized from untrusted input.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// assert!(bloom.check_invariants().is_ok());
+    /// unsafe { bloom.add_unchecked(b"hello") };
+    /// assert!(unsafe { bloom.contains_unchecked(b"hello") });
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub unsafe fn add_unchecked(&mut self, element: &[u8]) {
+        if self.duplicates.is_some() {
+            self.add(element);
+            return;
+        }
+        *self.fpr_cache.inserts.get_mut() += 1;
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        match self.config.hash_width {
+            HashWidth::Bits64 => for index in probes(element, m, k, self.config.hash_seeds) {
+                self.bit_set.set_unchecked(index as usize);
+            },
+            width => for index in indices_128(element, m, k, width) {
+                self.bit_set.set_unchecked(index as usize);
+            },
+        }
+    }
+
+    /// Like [Membership::contains], but without bounds checks on the underlying bit vector, see
+    /// [BloomFilter::add_unchecked]. Queries are not counted by the operation stats of the
+    /// `metrics` feature.
+    ///
+    /// # Safety
+    ///
+    /// [BloomFilter::check_invariants] must return `Ok`, see [BloomFilter::add_unchecked].
+    #[inline]
+    pub unsafe fn contains_unchecked(&self, element: &[u8]) -> bool {
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        let set = |index: u64| self.bit_set.get_unchecked(index as usize);
+        match self.config.hash_width {
+            HashWidth::Bits64 => probes(element, m, k, self.config.hash_seeds).all(set),
+            width => indices_128(element, m, k, width).all(set),
+        }
+    }
+
+    /// Turns a filter which is done receiving elements into a [FrozenBloomFilter], which only
+    /// answers queries, without divisions and bounds checks. The gain is modest, about 10-20% per
+    /// miss on small and on memory bound filters in the `bloom_frozen` bench, so only freeze
+    /// filters on a hot query path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [BloomFilter::check_invariants] fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let frozen = bloom.freeze();
+    /// assert!(frozen.contains(b"hello"));
+    /// assert!(!frozen.contains(b"world"));
+    /// ```
+    pub fn freeze(self) -> FrozenBloomFilter {
+        if let Err(issue) = self.check_invariants() {
+            panic!("{issue}");
+        }
+        let len = self.bit_set.storage.len().div_ceil(WORDS_PER_LINE);
+        let mut lines = vec![CacheLine([0; WORDS_PER_LINE]); len];
+        for (i, word) in self.bit_set.storage.iter().enumerate() {
+            lines[i / WORDS_PER_LINE].0[i % WORDS_PER_LINE] = *word;
+        }
+        let magic = (u128::MAX / self.config.size as u128).wrapping_add(1);
+        FrozenBloomFilter { lines: lines.into_boxed_slice(), magic, config: self.config }
+    }
+
+    /// Returns the probability that an element which was never added is reported present, given
+    /// the current fill, i.e. `fill_ratio ^ hashes`. This scans the whole underlying bit vector.
+    pub fn current_false_positive_probability(&self) -> f64 {
+        self.fill_ratio().powi(self.config.hashes as i32)
+    }
+
+    /// Measures the false positive rate by querying `samples` pseudo-random 32 byte strings, generated
+    /// deterministically from `seed`, and returns the fraction reported present. Random 32 byte
+    /// strings are practically never real members, so this is an empirical complement to
+    /// [BloomFilter::current_false_positive_probability], e.g. for health checks. Each sample is a
+    /// regular query, so it is counted by the operation stats of the `metrics` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// for x in 0..10_000u64 {
+    ///     bloom.add(&x.to_le_bytes());
+    /// }
+    /// assert!(bloom.sample_fpr(100_000, 7) < 0.02);
+    /// ```
+    pub fn sample_fpr(&self, samples: usize, seed: u64) -> f64 {
+        assert!(samples > 0, "samples must larger than 0!");
+        let mut state = seed;
+        let mut probe = [0u8; 32];
+        let hits = (0..samples).filter(|_| {
+            for chunk in probe.chunks_exact_mut(8) {
+                chunk.copy_from_slice(&splitmix64(&mut state).to_le_bytes());
+            }
+            self.contains(&probe)
+        }).count();
+        hits as f64 / samples as f64
+    }
+
+    /// Returns whether [BloomFilter::current_false_positive_probability] has reached `target_fpr`,
+    /// as a termination signal for consumers filling the filter from a stream of unknown length.
+    /// To stay cheap when called after every add, the estimate is only recomputed once 4096
+    /// elements were added through [Membership::add] since the last one, so the signal can lag
+    /// by that many elements. Bits changed by other means, e.g. unions, are only seen once the
+    /// estimate is refreshed; [Membership::clear] forces a refresh.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// let mut inserted = 0u64;
+    /// while !bloom.should_stop_inserting(0.01) {
+    ///     bloom.add(&inserted.to_le_bytes());
+    ///     inserted += 1;
+    /// }
+    /// assert!(inserted >= 10_000);
+    /// ```
+    pub fn should_stop_inserting(&self, target_fpr: f64) -> bool {
+        let cached = self.fpr_cache.fpr.load(Ordering::Relaxed);
+        let fpr = if cached == FprCache::STALE
+            || self.fpr_cache.inserts.load(Ordering::Relaxed) >= FPR_REFRESH_INSERTS {
+            let fpr = self.current_false_positive_probability();
+            self.fpr_cache.fpr.store(fpr.to_bits(), Ordering::Relaxed);
+            self.fpr_cache.inserts.store(0, Ordering::Relaxed);
+            fpr
+        } else {
+            f64::from_bits(cached)
+        };
+        fpr >= target_fpr
+    }
+
+    /// Tests whether an element is present in the filter, returning [None] if it is absent.
+    /// Otherwise returns the probability that the answer is a false positive given the current
+    /// fill of the filter, i.e. `fill_ratio ^ hashes`. Computing the fill ratio scans the whole
+    /// underlying bit vector.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains_with_confidence(b"hello").unwrap() < 0.01);
+    /// assert_eq!(bloom.contains_with_confidence(b"world"), None);
+    /// ```
+    pub fn contains_with_confidence(&self, element: &[u8]) -> Option<f64> {
+        if self.contains(element) {
+            Some(self.fill_ratio().powi(self.config.hashes as i32))
+        } else { None }
+    }
+
+    /// Returns the positions of the `elements` that may be present, in order, together with their
+    /// count, in a single pass over the slice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert_eq!(bloom.filter_present(&["world", "hello"]), (vec![1], 1));
+    /// ```
+    pub fn filter_present(&self, elements: &[impl AsRef<[u8]>]) -> (Vec<usize>, usize) {
+        let present: Vec<usize> = elements.iter().enumerate()
+            .filter(|(_, element)| self.contains(element.as_ref()))
+            .map(|(i, _)| i)
+            .collect();
+        let hits = present.len();
+        (present, hits)
+    }
+
+    /// Returns the positions of the `candidates` that may be present in this filter but are
+    /// definitely not in `other`, in order. This is a set difference over a known universe of
+    /// candidates: unlike [BloomFilter::intersect] it can not be done on the bits, as clearing the
+    /// bits of `other` would also clear bits of members shared with it. A false positive of this
+    /// filter can be reported, while one of `other` hides a candidate. The filters do not need to
+    /// be compatible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(10_000, 0.01);
+    /// let mut a = builder.clone().build_bloom_filter();
+    /// let mut b = builder.build_bloom_filter();
+    /// a.add(b"hello");
+    /// a.add(b"world");
+    /// b.add(b"hello");
+    /// assert_eq!(a.present_only_in(&b, &["hello", "world", "other"]), vec![1]);
+    /// ```
+    pub fn present_only_in(&self, other: &BloomFilter, candidates: &[impl AsRef<[u8]>]) -> Vec<usize> {
+        candidates.iter().enumerate()
+            .filter(|(_, candidate)| {
+                self.contains(candidate.as_ref()) && !other.contains(candidate.as_ref())
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub(crate) fn set_bit_vec(&mut self, bit_vec: BloomBitVec) {
+        assert_eq!(self.config.size, bit_vec.nbits as u64);
+        self.bit_set = bit_vec
+    }
+
+    /// Checks if two Bloom filters are compatible, i.e. have compatible parameters (hash function,
+    /// size, etc.)
+    fn compatible(&self, other: &BloomFilter) -> bool {
+        self.config.is_compatible_to(&other.config)
+    }
+}
+
+/// Encodings of [BloomFilter::export]. The first byte of an export identifies its format, so
+/// [BloomFilter::import] detects it.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The bytes of [BloomFilter::to_vec].
+    Raw,
+    /// The byte length of the filter followed by the gaps between set bits, as LEB128 varints.
+    /// Smallest when few bits are set, roughly below a fill ratio of 10%.
+    SparseIndices,
+    /// The byte length of the filter followed by the lengths of alternating runs of unset and
+    /// set bits, starting with unset bits, as LEB128 varints.
+    Rle,
+}
+
+impl ExportFormat {
+    fn tag(self) -> u8 {
+        match self {
+            ExportFormat::Raw => 0,
+            ExportFormat::SparseIndices => 1,
+            ExportFormat::Rle => 2,
+        }
+    }
+}
+
+impl BloomFilter {
+    /// Encode the bits of the Bloom filter in `format`, so a caller can pick the smallest
+    /// encoding for transmission. The format byte is followed by the hash width and the
+    /// [FilterBuilder::hash_seeds] as in [BloomFilter::to_u8_array_with_header], but the number of
+    /// hashes is not included. Decode it with [BloomFilter::import].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, ExportFormat, FilterBuilder, Hashes, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let bytes = bloom.export(ExportFormat::SparseIndices);
+    /// assert!(bytes.len() < bloom.get_u8_array().len());
+    /// let imported = BloomFilter::import(&bytes, bloom.hashes()).unwrap();
+    /// assert!(imported.contains(b"hello"));
+    /// ```
+    pub fn export(&self, format: ExportFormat) -> Vec<u8> {
+        let raw = Vec::<u8>::from(self);
+        let mut bytes = vec![format.tag()];
+        write_hash_config(&mut bytes, &self.config);
+        match format {
+            ExportFormat::Raw => bytes.extend_from_slice(&raw),
+            ExportFormat::SparseIndices => {
+                write_varint(&mut bytes, raw.len() as u64);
+                let mut next = 0;
+                for index in (0..raw.len() as u64 * 8).filter(|i| raw[(i / 8) as usize] >> (i % 8) & 1 == 1) {
+                    write_varint(&mut bytes, index - next);
+                    next = index + 1;
+                }
+            }
+            ExportFormat::Rle => {
+                write_varint(&mut bytes, raw.len() as u64);
+                let mut bit = 0;
+                let mut run = 0;
+                for i in 0..raw.len() * 8 {
+                    if raw[i / 8] >> (i % 8) & 1 != bit {
+                        write_varint(&mut bytes, run);
+                        bit ^= 1;
+                        run = 0;
+                    }
+                    run += 1;
+                }
+                write_varint(&mut bytes, run);
+            }
+        }
+        bytes
+    }
+
+    /// Decode a Bloom filter encoded by [BloomFilter::export] in any [ExportFormat], with the
+    /// number of hashes it was built with.
+    ///
+    /// The bytes may come from untrusted input: the whole encoding is validated before the filter
+    /// is allocated. The filter is allocated zeroed, so only the pages bits are set in are
+    /// committed, and a length the allocator refuses is returned as an error. A sparse or
+    /// run-length encoding of a nearly empty filter is legitimately much smaller than the filter,
+    /// so the length is not bounded by the size of the input; where the system overcommits memory
+    /// a huge length is granted, so check [BloomFilter::size_bytes] before operations over the
+    /// whole filter, which commit all of it.
+    pub fn import(bytes: &[u8], hashes: u32) -> Result<Self, FilterError> {
+        let (&tag, mut rest) = bytes.split_first()
+            .ok_or(FilterError::InvalidEncoding("missing format byte"))?;
+        if tag > 2 {
+            return Err(FilterError::UnknownExportFormat(tag));
+        }
+        let (hash_width, seeds) = read_hash_config(&mut rest)?;
+        let mut bloom = match tag {
+            0 => BloomFilter::try_from((rest, hashes))?,
+            1 => {
+                let (len, bits) = read_length(&mut rest)?;
+                let mut indices = Vec::new();
+                let mut next = 0u64;
+                while !rest.is_empty() {
+                    let index = next.checked_add(read_varint(&mut rest)?)
+                        .filter(|index| *index < bits)
+                        .ok_or(FilterError::InvalidEncoding("bit index out of range"))?;
+                    indices.push(index);
+                    next = index + 1;
+                }
+                let mut bloom = zeroed(len, hashes)?;
+                for index in indices {
+                    bloom.bit_set.set(index as usize);
+                }
+                bloom
+            }
+            _ => {
+                let (len, bits) = read_length(&mut rest)?;
+                let mut runs = Vec::new();
+                let mut index = 0u64;
+                let mut bit = 0;
+                while !rest.is_empty() {
+                    let end = index.checked_add(read_varint(&mut rest)?)
+                        .filter(|end| *end <= bits)
+                        .ok_or(FilterError::InvalidEncoding("runs longer than the filter"))?;
+                    if bit == 1 {
+                        runs.push(index..end);
+                    }
+                    index = end;
+                    bit ^= 1;
+                }
+                if index != bits {
+                    return Err(FilterError::InvalidEncoding("runs shorter than the filter"));
+                }
+                let mut bloom = zeroed(len, hashes)?;
+                for i in runs.into_iter().flatten() {
+                    bloom.bit_set.set(i as usize);
+                }
+                bloom
+            }
+        };
+        bloom.config.hash_width(hash_width);
+        bloom.config.hash_seeds(seeds.0, seeds.1);
+        Ok(bloom)
+    }
+}
+
+/// Appends the hash width (one byte, 0 for [HashWidth::Bits64], 1 for [HashWidth::Bits128] and
+/// 2 for [HashWidth::LegacyMurmur128]) and the two [FilterBuilder::hash_seeds] (`u64`
+/// little-endian) of `config`.
+fn write_hash_config(bytes: &mut Vec<u8>, config: &FilterBuilder) {
+    bytes.push(match config.hash_width {
+        HashWidth::Bits64 => 0,
+        HashWidth::Bits128 => 1,
+        HashWidth::LegacyMurmur128 => 2,
+    });
+    bytes.extend_from_slice(&config.hash_seeds.0.to_le_bytes());
+    bytes.extend_from_slice(&config.hash_seeds.1.to_le_bytes());
+}
+
+/// Reads what [write_hash_config] wrote from the front of `bytes` and advances past it.
+fn read_hash_config(bytes: &mut &[u8]) -> Result<(HashWidth, (u64, u64)), FilterError> {
+    if bytes.len() < 17 {
+        return Err(FilterError::InvalidEncoding("missing hash width and seeds"));
+    }
+    let hash_width = match bytes[0] {
+        0 => HashWidth::Bits64,
+        1 => HashWidth::Bits128,
+        2 => HashWidth::LegacyMurmur128,
+        _ => return Err(FilterError::InvalidEncoding("unknown hash width")),
+    };
+    let seed1 = u64::from_le_bytes(bytes[1..9].try_into().unwrap());
+    let seed2 = u64::from_le_bytes(bytes[9..17].try_into().unwrap());
+    *bytes = &bytes[17..];
+    Ok((hash_width, (seed1, seed2)))
+}
+
+/// Reads the byte length of an encoded filter, returning it with the number of bits.
+fn read_length(bytes: &mut &[u8]) -> Result<(u64, u64), FilterError> {
+    let len = read_varint(bytes)?;
+    let bits = len.checked_mul(8).ok_or(FilterError::InvalidEncoding("length overflows"))?;
+    Ok((len, bits))
+}
+
+/// Returns an empty filter of `len` bytes, validated like [BloomFilter::try_from], or an error if
+/// its words can not be allocated.
+fn zeroed(len: u64, hashes: u32) -> Result<BloomFilter, FilterError> {
+    let too_large = FilterError::InvalidEncoding("filter too large to allocate");
+    let len = usize::try_from(len).map_err(|_| too_large.clone())?;
+    if len == 0 {
+        return Err(FilterError::ZeroSize);
+    }
+    if !len.is_multiple_of(8) {
+        return Err(FilterError::LengthNotWordAligned(len));
+    }
+    if hashes == 0 {
+        return Err(FilterError::ZeroHashes);
+    }
+    let bit_set = BloomBitVec::try_new(len / std::mem::size_of::<usize>()).ok_or(too_large)?;
+    let mut config = FilterBuilder::from_size_and_hashes(len as u64 * 8, hashes);
+    config.complete();
+    Ok(BloomFilter::from_parts(config, bit_set))
+}
+
+/// Appends `value` as an unsigned LEB128 varint.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads an unsigned LEB128 varint from the front of `bytes` and advances past it.
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, FilterError> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(FilterError::InvalidEncoding("truncated varint"))
+}
+
+impl From<&BloomFilter> for Vec<u8> {
+    /// Copies the bits of the filter in the canonical byte order, the same bytes as
+    /// [BloomFilter::to_vec].
+    /// The number of hashes is not included and must be kept along to restore the filter.
+    fn from(bloom: &BloomFilter) -> Self {
+        bloom.bit_set.storage.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+}
+
+impl TryFrom<(&[u8], u32)> for BloomFilter {
+    type Error = FilterError;
+
+    /// Restores a Bloom filter from bytes in the portable byte format of
+    /// [BloomFilter::to_vec] and its number of hashes, like [BloomFilter::from_u8_array]
+    /// but rejecting empty bytes, bytes not holding whole 64 bit words or zero hashes instead of
+    /// building an unusable filter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Hashes, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// let bytes = Vec::<u8>::from(&bloom);
+    /// let restored = BloomFilter::try_from((&bytes[..], bloom.hashes())).unwrap();
+    /// assert!(restored.contains(b"hello"));
+    /// ```
+    fn try_from((bytes, hashes): (&[u8], u32)) -> Result<Self, Self::Error> {
+        if bytes.is_empty() {
+            return Err(FilterError::ZeroSize);
+        }
+        if !bytes.len().is_multiple_of(8) {
+            return Err(FilterError::LengthNotWordAligned(bytes.len()));
+        }
+        if hashes == 0 {
+            return Err(FilterError::ZeroHashes);
+        }
+        Ok(BloomFilter::from_u8_array(bytes, hashes))
+    }
+}
+
+/// First bytes of [BloomFilter::to_u8_array_with_header].
+const HEADER_MAGIC: &[u8; 4] = b"FBLM";
+/// Length of the header of [BloomFilter::to_u8_array_with_header].
+const HEADER_LEN: usize = 25;
+
+/// The false positive probability used when a [BloomFilter] is built by [FromIterator].
+pub const DEFAULT_FALSE_POSITIVE_PROBABILITY: f64 = 0.01;
+
+impl FromIterator<Vec<u8>> for BloomFilter {
+    /// Build a Bloom filter from elements with a false positive probability of
+    /// [DEFAULT_FALSE_POSITIVE_PROBABILITY]. When the iterator reports an exact length the filter
+    /// is sized from it directly, otherwise the elements are collected first and then counted.
+    /// Use [FilterBuilder] instead to choose another false positive probability.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, Membership};
+    ///
+    /// let keys = vec![b"hello".to_vec(), b"world".to_vec()];
+    /// let bloom: BloomFilter = keys.into_iter().collect();
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    fn from_iter<I: IntoIterator<Item=Vec<u8>>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        match iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => {
+                let mut bloom = FilterBuilder::new(lower.max(1) as u64,
+                                                   DEFAULT_FALSE_POSITIVE_PROBABILITY)
+                    .build_bloom_filter();
+                for element in iter {
+                    bloom.add(&element);
+                }
+                bloom
+            }
+            _ => {
+                let elements: Vec<Vec<u8>> = iter.collect();
+                elements.into_iter().collect()
+            }
+        }
+    }
+}
+
+/// A Rolling Bloom filter answers "seen within the last `N` windows" queries by keeping `N`
+/// [BloomFilter]s of the same geometry. Elements are added to the active filter, `contains` checks
+/// all of them, and [RollingBloomFilter::rotate] clears the oldest filter and makes it active, so
+/// an element ages out after `N` rotations.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::{FilterBuilder, Membership};
+///
+/// let mut rolling = FilterBuilder::new(100_000, 0.01).build_rolling_bloom_filter(2);
+/// rolling.add(b"hello");
+/// rolling.rotate();
+/// assert!(rolling.contains(b"hello"));
+/// rolling.rotate();
+/// assert!(!rolling.contains(b"hello"));
+/// ```
+#[derive(Clone)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollingBloomFilter {
+    filters: Vec<BloomFilter>,
+    active: usize,
+}
+
+impl RollingBloomFilter {
+    /// Build a Rolling Bloom filter of `windows` Bloom filters from [FilterBuilder].
+    pub fn new(config: FilterBuilder, windows: usize) -> Self {
+        assert!(windows > 0, "windows must larger than 0!");
+        let first = BloomFilter::new(config);
+        let filters = vec![first; windows];
+        RollingBloomFilter { filters, active: 0 }
+    }
+
+    /// Clears the oldest Bloom filter and makes it the active one.
+    pub fn rotate(&mut self) {
+        self.active = (self.active + 1) % self.filters.len();
+        self.filters[self.active].clear();
+    }
+
+    /// Returns the number of windows, i.e. the number of underlying Bloom filters.
+    pub fn windows(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the configuration/builder shared by the underlying Bloom filters.
+    pub fn config(&self) -> FilterBuilder {
+        self.filters[self.active].config()
+    }
+
+    /// Tests whether every index of `probes`, which is replayed for each window, is set in any
+    /// window.
+    fn contains_probes(&self, probes: impl Iterator<Item=u64> + Clone) -> bool {
+        self.filters.iter().any(|filter| probes.clone().all(|index| filter.bit_set.get(index as usize)))
+    }
+}
+
+impl Membership for RollingBloomFilter {
+    /// Adds the passed value to the active filter.
+    fn add(&mut self, element: &[u8]) {
+        self.filters[self.active].add(element);
+    }
+
+    /// Tests whether an element is present in any window. The element is hashed once, the same
+    /// probes are then checked against each window.
+    fn contains(&self, element: &[u8]) -> bool {
+        let config = &self.filters[self.active].config;
+        let (m, k) = (config.size, config.hashes as u64);
+        match config.hash_width {
+            HashWidth::Bits64 => self.contains_probes(probes(element, m, k, config.hash_seeds)),
+            width => self.contains_probes(indices_128(element, m, k, width)),
+        }
+    }
+
+    /// Get the hashes indices of the element, which are the same for every window.
+    fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
+        self.filters[self.active].get_hash_indices(element)
+    }
+
+    /// Tests whether a hashes indices is present in any window.
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool {
+        self.filters.iter().any(|filter| filter.contains_hash_indices(indices))
+    }
+
+    /// Removes all elements from every window.
+    fn clear(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.clear();
+        }
+    }
+}
+
+impl Hashes for RollingBloomFilter {
+    fn hashes(&self) -> u32 {
+        self.filters[self.active].hashes()
+    }
+}
+
+/// A [BloomFilter] wrapped with a small cache of recently confirmed misses, for workloads where
+/// the same absent keys are queried again and again. A cached miss is answered from one xxh3
+/// hash of the key instead of the `k` probes into the bit set.
+///
+/// The cache holds at most `capacity` key hashes and evicts the least recently used one when
+/// full. Any [Membership::add] empties it, since the new element may be one of the cached keys.
+/// Keys are cached by their 64-bit hash, so a key colliding with a cached miss is reported as
+/// absent until the next `add`; with 64-bit hashes this is vanishingly unlikely.
+///
+/// Every `contains` takes the lock of the cache once, so concurrent readers are serialized on
+/// it. The wrapper is meant for one thread or a few; share a plain [BloomFilter] for highly
+/// concurrent reads.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::{CachedBloomFilter, FilterBuilder, Membership};
+///
+/// let bloom = FilterBuilder::new(100_000, 0.01).build_bloom_filter();
+/// let mut cached = CachedBloomFilter::new(bloom, 1024);
+/// assert!(!cached.contains(b"hello"));
+/// assert!(!cached.contains(b"hello"));
+/// assert_eq!(cached.cache_hits(), 1);
+/// cached.add(b"hello");
+/// assert!(cached.contains(b"hello"));
+/// ```
+#[derive(Debug)]
+pub struct CachedBloomFilter {
+    filter: BloomFilter,
+    misses: Mutex<MissCache>,
+    hits: AtomicU64,
+}
+
+/// The least recently used key hashes of [CachedBloomFilter]: `stamps` maps a key hash to its
+/// last use and `order` maps uses back to key hashes, so the oldest entry is `order`'s first.
+#[derive(Clone)]
+#[derive(Debug)]
+struct MissCache {
+    capacity: usize,
+    tick: u64,
+    stamps: HashMap<u64, u64>,
+    order: BTreeMap<u64, u64>,
+}
+
+impl MissCache {
+    fn new(capacity: usize) -> Self {
+        MissCache { capacity, tick: 0, stamps: HashMap::new(), order: BTreeMap::new() }
+    }
+
+    /// Marks `key` as used and returns whether it was cached.
+    fn touch(&mut self, key: u64) -> bool {
+        self.tick += 1;
+        match self.stamps.get_mut(&key) {
+            Some(stamp) => {
+                self.order.remove(stamp);
+                *stamp = self.tick;
+                self.order.insert(self.tick, key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, key: u64) {
+        if self.stamps.len() == self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.stamps.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.stamps.insert(key, self.tick);
+        self.order.insert(self.tick, key);
+    }
+
+    fn clear(&mut self) {
+        self.stamps.clear();
+        self.order.clear();
+    }
+}
+
+impl CachedBloomFilter {
+    /// Wraps `filter` with a cache of at most `capacity` recent misses.
+    pub fn new(filter: BloomFilter, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must larger than 0!");
+        CachedBloomFilter {
+            filter,
+            misses: Mutex::new(MissCache::new(capacity)),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the wrapped Bloom filter.
+    pub fn filter(&self) -> &BloomFilter {
+        &self.filter
+    }
+
+    /// Unwraps the Bloom filter, dropping the cache.
+    pub fn into_inner(self) -> BloomFilter {
+        self.filter
+    }
+
+    /// Returns how many `contains` calls were answered from the cache without probing the
+    /// filter.
+    pub fn cache_hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn cache(&self) -> MutexGuard<'_, MissCache> {
+        self.misses.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A clone copies the cached misses and the hit count.
+impl Clone for CachedBloomFilter {
+    fn clone(&self) -> Self {
+        CachedBloomFilter {
+            filter: self.filter.clone(),
+            misses: Mutex::new(self.cache().clone()),
+            hits: AtomicU64::new(self.cache_hits()),
+        }
+    }
+}
+
+impl Membership for CachedBloomFilter {
+    /// Adds the passed value to the filter and empties the miss cache.
+    fn add(&mut self, element: &[u8]) {
+        self.filter.add(element);
+        self.misses.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    }
+
+    /// Tests whether an element is present in the filter, answering recent misses from the cache.
+    fn contains(&self, element: &[u8]) -> bool {
+        let key = xxh3_64_with_seed(element, self.filter.config.hash_seeds.0);
+        let mut cache = self.cache();
+        if cache.touch(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        let found = self.filter.contains(element);
+        if !found {
+            cache.insert(key);
+        }
+        found
+    }
+
+    /// Get the hashes indices of the element in the filter.
+    fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
+        self.filter.get_hash_indices(element)
+    }
+
+    /// Tests whether a hashes indices is present in the filter, bypassing the cache.
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool {
+        self.filter.contains_hash_indices(indices)
+    }
+
+    /// Removes all elements from the filter. Cached misses stay valid and are kept.
+    fn clear(&mut self) {
+        self.filter.clear();
+    }
+}
+
+impl Hashes for CachedBloomFilter {
+    fn hashes(&self) -> u32 {
+        self.filter.hashes()
+    }
+}
+
+/// A set which stores its elements exactly while they are few, and turns into a [BloomFilter]
+/// once it holds more than `small_threshold` elements, see [FilterBuilder::build_adaptive]. Below
+/// the threshold `contains` has no false positives and only the elements take memory; the
+/// stored elements are added to the Bloom filter when it is created.
+///
+/// [Membership::clear] drops the Bloom filter and starts over with an empty exact set.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::{FilterBuilder, Membership};
+///
+/// let mut set = FilterBuilder::new(100_000, 0.01).build_adaptive(2);
+/// set.add(b"hello");
+/// set.add(b"world");
+/// assert!(set.is_exact());
+/// set.add(b"fastbloom");
+/// assert!(!set.is_exact());
+/// assert!(set.contains(b"hello"));
+/// ```
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct SmallMembership {
+    config: FilterBuilder,
+    small_threshold: usize,
+    backing: SmallBacking,
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+enum SmallBacking {
+    Exact(HashSet<Vec<u8>>),
+    Bloom(BloomFilter),
+}
+
+impl SmallMembership {
+    /// Creates an empty set which switches to a Bloom filter built from `config` after
+    /// `small_threshold` elements.
+    pub fn new(mut config: FilterBuilder, small_threshold: usize) -> Self {
+        config.complete();
+        SmallMembership { config, small_threshold, backing: SmallBacking::Exact(HashSet::new()) }
+    }
+
+    /// Returns whether the elements are still stored exactly, i.e. without false positives.
+    pub fn is_exact(&self) -> bool {
+        matches!(self.backing, SmallBacking::Exact(_))
+    }
+
+    /// Returns the Bloom filter once the set has switched to it.
+    pub fn bloom_filter(&self) -> Option<&BloomFilter> {
+        match &self.backing {
+            SmallBacking::Exact(_) => None,
+            SmallBacking::Bloom(bloom) => Some(bloom),
+        }
+    }
+
+    /// Returns the configuration/builder of the Bloom filter the set switches to.
+    pub fn config(&self) -> FilterBuilder {
+        self.config.clone()
+    }
+}
+
+impl Membership for SmallMembership {
+    /// Adds the passed value to the set, switching to the Bloom filter when the exact set
+    /// grows past the threshold.
+    fn add(&mut self, element: &[u8]) {
+        match &mut self.backing {
+            SmallBacking::Exact(elements) => {
+                elements.insert(element.to_vec());
+                if elements.len() > self.small_threshold {
+                    let mut bloom = BloomFilter::new(self.config.clone());
+                    for element in elements.iter() {
+                        bloom.add(element);
+                    }
+                    self.backing = SmallBacking::Bloom(bloom);
+                }
+            }
+            SmallBacking::Bloom(bloom) => bloom.add(element),
+        }
+    }
+
+    /// Tests whether an element is present in the set, exactly while below the threshold.
+    fn contains(&self, element: &[u8]) -> bool {
+        match &self.backing {
+            SmallBacking::Exact(elements) => elements.contains(element),
+            SmallBacking::Bloom(bloom) => bloom.contains(element),
+        }
+    }
+
+    /// Get the hashes indices of the element in the Bloom filter, which are the same before
+    /// and after the switch.
+    fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
+        hash_indices(&self.config, element)
+    }
+
+    /// Tests whether a hashes indices is present in the set, i.e. whether the Bloom filter the
+    /// set would switch to has all of them set.
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool {
+        match &self.backing {
+            SmallBacking::Exact(elements) => {
+                let set: HashSet<u64> = elements.iter()
+                    .flat_map(|element| hash_indices(&self.config, element))
+                    .collect();
+                indices.iter().all(|index| set.contains(index))
+            }
+            SmallBacking::Bloom(bloom) => bloom.contains_hash_indices(indices),
+        }
+    }
+
+    /// Removes all elements, going back to an empty exact set.
+    fn clear(&mut self) {
+        self.backing = SmallBacking::Exact(HashSet::new());
+    }
+}
+
+impl Hashes for SmallMembership {
+    fn hashes(&self) -> u32 {
+        self.config.hashes
+    }
+}
+
+const WORDS_PER_LINE: usize = 64 / std::mem::size_of::<usize>();
+
+/// The words of a [FrozenBloomFilter] in 64 byte blocks aligned to cache lines.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[repr(C, align(64))]
+struct CacheLine([usize; WORDS_PER_LINE]);
+
+/// A read-only [BloomFilter] laid out for fast queries, see [BloomFilter::freeze]. The bits are
+/// stored in cache line aligned blocks, the reduction of the hashes into the filter is precomputed
+/// to avoid divisions, and the probes skip the bounds checks, which
+/// [BloomFilter::check_invariants] makes unnecessary.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::{FilterBuilder, Membership};
+///
+/// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+/// bloom.add(b"hello");
+/// let frozen = bloom.clone().freeze();
+/// assert_eq!(frozen.get_hash_indices(b"hello"), bloom.get_hash_indices(b"hello"));
+/// ```
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct FrozenBloomFilter {
+    lines: Box<[CacheLine]>,
+    /// `u128::MAX / size + 1`, which turns `hash % size` into multiplications, see [fast_mod].
+    magic: u128,
+    config: FilterBuilder,
+}
+
+/// Returns `hash % m` given `magic == u128::MAX / m + 1`, by the direct computation of the
+/// remainder from Lemire, Kaser and Kurz (2019), "Faster remainder by direct computation".
+#[inline(always)]
+fn fast_mod(hash: u64, m: u64, magic: u128) -> u64 {
+    let low = magic.wrapping_mul(hash as u128);
+    let bottom = ((low as u64) as u128 * m as u128) >> 64;
+    let top = (low >> 64) * m as u128;
+    ((bottom + top) >> 64) as u64
+}
+
+impl FrozenBloomFilter {
+    #[inline(always)]
+    fn get(&self, index: u64) -> bool {
+        let word = index as usize / usize::BITS as usize;
+        // the invariants checked by `freeze` keep every probe below the size of the storage.
+        let word = unsafe { *(self.lines.as_ptr() as *const usize).add(word) };
+        word & (1 << (index as usize & SUFFIX)) != 0
+    }
+
+    /// Tests whether an element is present in the filter (subject to the specified false
+    /// positive rate).
+    #[inline]
+    pub fn contains(&self, element: &[u8]) -> bool {
+        let (m, k) = (self.config.size, self.config.hashes as u64);
+        match self.config.hash_width {
+            HashWidth::Bits64 => {
+                let seeds = self.config.hash_seeds;
+                let start = fast_mod(xxh3_64_with_seed(element, seeds.0), m, self.magic);
+                let step = fast_mod(xxh3_64_with_seed(element, seeds.1), m, self.magic);
+                let mut index = start;
+                for _ in 0..k {
+                    if !self.get(index) { return false; }
+                    index = next_index(index, step, m);
+                }
+                true
+            }
+            width => indices_128(element, m, k, width).all(|i| self.get(i)),
+        }
+    }
+
+    /// Get the hashes indices of the element in the filter.
+    pub fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
+        hash_indices(&self.config, element)
+    }
+
+    /// Tests whether a hashes indices is present in the filter. Indices past the size are
+    /// reported absent.
+    pub fn contains_hash_indices(&self, indices: &[u64]) -> bool {
+        indices.iter().all(|index| *index < self.config.size && self.get(*index))
+    }
+
+    /// Returns the configuration/builder of the Bloom filter.
+    pub fn config(&self) -> FilterBuilder {
+        self.config.clone()
+    }
+}
+
+impl Hashes for FrozenBloomFilter {
+    fn hashes(&self) -> u32 {
+        self.config.hashes
+    }
+}
+
+/// A Counting Bloom filter works in a similar manner as a regular Bloom filter; however, it is
+/// able to keep track of insertions and deletions. In a counting Bloom filter, each entry in the
+/// Bloom filter is a small counter associated with a basic Bloom filter bit.
+///
+/// **Reference**: F. Bonomi, M. Mitzenmacher, R. Panigrahy, S. Singh, and G. Varghese, “An Improved
+/// Construction for Counting Bloom Filters,” in 14th Annual European Symposium on
+/// Algorithms, LNCS 4168, 2006
+///
+/// Like [BloomFilter] it is `Send + Sync`, so it can be shared immutably across threads while
+/// mutation needs external synchronization.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct CountingBloomFilter {
+    config: FilterBuilder,
+    counting_vec: CountingVec,
+    overlay: Option<ExactOverlay>,
+}
+
+/// Exact counts of the elements added to a [CountingBloomFilter] while there was room, see
+/// [CountingBloomFilter::with_exact_overlay].
+#[derive(Clone)]
+#[derive(Debug)]
+struct ExactOverlay {
+    capacity: usize,
+    counts: HashMap<Vec<u8>, usize>,
+    /// Whether every element in the filter is in `counts`, so that any other element was never
+    /// added.
+    complete: bool,
+}
+
+impl ExactOverlay {
+    fn record_add(&mut self, element: &[u8], n: usize) {
+        if let Some(count) = self.counts.get_mut(element) {
+            *count += n;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(element.to_vec(), n);
+        } else {
+            self.complete = false;
+        }
+    }
+
+    /// Returns how many of `n` removals of `element` may decrement its counters: up to its exact
+    /// count if it is tracked, none if it was never added, or `None` if the overlay cannot tell.
+    fn record_remove(&mut self, element: &[u8], n: usize) -> Option<usize> {
+        match self.counts.get_mut(element) {
+            Some(count) => {
+                let removed = min(*count, n);
+                *count -= removed;
+                if *count == 0 {
+                    self.counts.remove(element);
+                }
+                Some(removed)
+            }
+            None if self.complete => Some(0),
+            None => None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.clear();
+        self.complete = true;
+    }
+}
+
+/// Length of the header of [CountingBloomFilter::to_portable_bytes].
+const PORTABLE_HEADER_LEN: usize = 30;
+/// Width of the counters in [CountingBloomFilter::to_portable_bytes].
+const PORTABLE_COUNTER_BITS: u8 = 4;
+
+macro_rules! get_array {
+    ($name:ident, $native:ty, $len:expr) => {
+        impl CountingBloomFilter {
+            pub fn $name(&self) -> &[$native] {
+                let ptr = self.counting_vec.storage.as_ptr() as *const $native;
+                #[cfg(target_pointer_width = "64")]
+                    let arr = slice_from_raw_parts(ptr, self.counting_vec.storage.len() * $len);
+                #[cfg(target_pointer_width = "32")]
+                    if cfg!(target_pointer_width= "32") {
+                        if self.counting_vec.storage.len() % 2 != 0 {
+                            panic!("CountingVec with len {} can't export as u64 array!", self.counting_vec.storage.len())
+                        }
+                    }
+                #[cfg(target_pointer_width = "32")]
+                    let arr = slice_from_raw_parts(ptr, self.counting_vec.storage.len() * $len / 2);
+                unsafe { &*arr }
+            }
+        }
+    };
+}
+
+get_array!(get_u8_array, u8, 8);
+get_array!(get_u16_array, u16, 4);
+get_array!(get_u32_array, u32, 2);
+get_array!(get_u64_array, u64, 1);
+
+impl CountingBloomFilter {
+    pub fn new(mut config: FilterBuilder) -> Self {
+        config.complete();
+        #[cfg(target_pointer_width = "64")]
+            let counting_vec = CountingVec::new((config.size >> 4) as usize);
+        #[cfg(target_pointer_width = "32")]
+            let counting_vec = CountingVec::new((config.size >> 3) as usize);
+        CountingBloomFilter { config, counting_vec, overlay: None }
+    }
+
+    pub(crate) fn set_counting_vec(&mut self, counting_vec: CountingVec) {
+        assert_eq!(self.config.size, counting_vec.counters as u64);
+        self.counting_vec = counting_vec;
+        if let Some(overlay) = &mut self.overlay {
+            overlay.complete = false;
+        }
+    }
+
+    /// Tracks the exact count of up to `capacity` distinct elements next to the counters, so that
+    /// [Deletable::remove] and [CountingBloomFilter::remove_n] of a tracked element decrement at
+    /// most as often as it was added, and removing an element that was never added leaves the
+    /// counters alone even when it is a false positive. Without the overlay such a remove
+    /// decrements counters shared with other elements, which can then turn into false negatives.
+    ///
+    /// Elements added once the overlay is full are not tracked, and from then on removing an
+    /// untracked element falls back to the usual check that it appears present. Each tracked
+    /// element costs a copy of its bytes plus a hash map entry, so keep `capacity` to the critical
+    /// keys. Merging counters with [CountingBloomFilter::union_bytes] also ends the exact
+    /// knowledge of untracked elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{Deletable, FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter()
+    ///     .with_exact_overlay(1024);
+    /// cbf.add(b"hello");
+    /// cbf.remove(b"world"); // never added, so no counter changes.
+    /// cbf.remove(b"hello");
+    /// cbf.remove(b"hello"); // only added once, so no counter changes.
+    /// assert!(!cbf.contains(b"hello"));
+    /// ```
+    pub fn with_exact_overlay(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must larger than 0!");
+        let complete = self.nonzero_counters().next().is_none();
+        self.overlay = Some(ExactOverlay { capacity, counts: HashMap::new(), complete });
+        self
+    }
+
+    /// Returns how many of `n` removals of `element` decrement its counters, see
+    /// [CountingBloomFilter::with_exact_overlay].
+    fn removals(&mut self, element: &[u8], n: usize) -> usize {
+        match self.overlay.as_mut().and_then(|overlay| overlay.record_remove(element, n)) {
+            Some(removed) => removed,
+            None if self.contains(element) => n,
+            None => 0,
+        }
+    }
+
+    /// Checks if two Counting Bloom filters are compatible, i.e. have compatible parameters (hash
+    /// function, size, etc.)
+    fn compatible(&self, other: &BloomFilter) -> bool {
+        self.config.is_compatible_to(&other.config)
+    }
+
+    /// Returns the configuration/builder of the Bloom filter.
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder};
+    ///
+    /// let bloom = FilterBuilder::new(100_000_000, 0.01).build_bloom_filter();
+    /// let builder = bloom.config();
+    /// ```
+    ///
+    pub fn config(&self) -> FilterBuilder {
+        self.config.clone()
+    }
+}
+
+macro_rules! from_array {
+    ($name:ident, $native:ty, $num:expr) => {
+        impl CountingBloomFilter {
+            /// Build a counting bloom filter from the native counter slots returned by the
+            /// matching `get_*_array` on a target with the same pointer width.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `array` does not fill a whole number of `usize` slots, e.g. an odd number
+            /// of `u32` on a 64 bit target, rather than dropping or reading past the tail.
+            pub fn $name(array: &[$native], hashes: u32, enable_repeat_insert:bool) -> Self {
+                assert!(std::mem::size_of_val(array) % std::mem::size_of::<usize>() == 0,
+                        "array must fill whole usize slots!");
+                let mut config =
+                    FilterBuilder::from_size_and_hashes((array.len() * $num) as u64, hashes);
+                config.enable_repeat_insert(enable_repeat_insert);
+                config.complete();
+                #[cfg(target_pointer_width = "64")]
+                    let mut counting_vec = CountingVec::new((config.size >> 4) as usize);
+                #[cfg(target_pointer_width = "32")]
+                    let mut counting_vec = CountingVec::new((config.size >> 3) as usize);
+
+                let ptr = array.as_ptr() as *const usize;
+                #[cfg(target_pointer_width = "64")]
+                    let usize_array = slice_from_raw_parts(ptr, (config.size >> 4) as usize);
+                #[cfg(target_pointer_width = "32")]
+                    let usize_array = slice_from_raw_parts(ptr, (config.size >> 3) as usize);
+
+                counting_vec.storage.copy_from_slice(unsafe { &*usize_array });
+
+                CountingBloomFilter { config, counting_vec, overlay: None }
+            }
+        }
+    };
+}
+
+from_array!(from_u8_array, u8, 2);
+from_array!(from_u16_array, u16, 4);
+from_array!(from_u32_array, u32, 8);
+from_array!(from_u64_array, u64, 16);
+
+impl CountingBloomFilter {
+    /// Get the estimate count for element in this counting bloom filter.
+    /// See: https://github.com/yankun1992/fastbloom/issues/3
+    pub fn estimate_count(&self, element: &[u8]) -> usize {
+        let mut res = usize::MAX;
+        for index in self.probes(element) {
+            let count = self.counting_vec.get(index as usize);
+            if count == 0 { return 0; } else { res = min(count, res) }
+        }
+        res
+    }
+
+    /// Returns the [CountingBloomFilter::estimate_count] of every element, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add_n(b"hello", 3);
+    /// assert_eq!(cbf.estimate_count_all([&b"hello"[..], b"world"]), vec![3, 0]);
+    /// ```
+    pub fn estimate_count_all<T: AsRef<[u8]>>(&self, elements: impl IntoIterator<Item=T>) -> Vec<usize> {
+        elements.into_iter().map(|element| self.estimate_count(element.as_ref())).collect()
+    }
+
+    /// The probes of `element`, see [crate::hashing::probes].
+    #[inline]
+    fn probes(&self, element: &[u8]) -> impl Iterator<Item=u64> + Clone {
+        probes(element, self.config.size, self.config.hashes as u64, self.config.hash_seeds)
+    }
+
+    /// Returns the minimum counter over `indices`, e.g. from [Membership::get_hash_indices], which
+    /// is the [CountingBloomFilter::estimate_count] of the element they belong to, without
+    /// hashing it again. Returns 0 if any counter is zero or `indices` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// cbf.add(b"hello");
+    /// let indices = cbf.get_hash_indices(b"hello");
+    /// assert_eq!(cbf.min_count_hash_indices(&indices), 2);
+    /// ```
+    pub fn min_count_hash_indices(&self, indices: &[u64]) -> usize {
+        indices.iter().map(|index| self.counting_vec.get(*index as usize)).min().unwrap_or(0)
+    }
+
+    /// Returns the number of counters of the filter, 4 bits each.
+    pub fn size_counters(&self) -> u64 {
+        self.config.size
+    }
+
+    /// Returns the size of the filter in bytes, the length of [CountingBloomFilter::get_u8_array].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// assert_eq!(cbf.size_bytes(), cbf.size_counters() / 2);
+    /// ```
+    pub fn size_bytes(&self) -> u64 {
+        self.get_u8_array().len() as u64
+    }
+
+    /// Adds `element` `n` times, incrementing each of its counters by `n` at once (saturating at
+    /// the largest counter value) and hashing it only once. Without repeat insert (see
+    /// [FilterBuilder::enable_repeat_insert]) this is the same as a single [Membership::add].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add_n(b"hello", 5);
+    /// assert_eq!(cbf.estimate_count(b"hello"), 5);
+    /// ```
+    pub fn add_n(&mut self, element: &[u8], n: usize) {
+        if n == 0 { return; }
+        if !self.config.enable_repeat_insert {
+            self.add(element);
+            return;
+        }
+        for index in self.probes(element) {
+            self.counting_vec.increment_by(index as usize, n);
+        }
+        if let Some(overlay) = &mut self.overlay {
+            overlay.record_add(element, n);
+        }
+    }
+
+    /// Adds the counters of a serialized counting bloom filter, as produced by
+    /// [CountingBloomFilter::get_u8_array] on a target with the same pointer width, to this one
+    /// without constructing an intermediate filter, e.g. to aggregate shards. Counters are summed
+    /// a whole slot at a time, saturating each at the largest counter value. The geometry is
+    /// validated from the length of `bytes` and `hashes` before any counter is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let builder = FilterBuilder::new(10_000, 0.01);
+    /// let mut shard = builder.clone().build_counting_bloom_filter();
+    /// shard.add_n(b"hello", 2);
+    /// let mut total = builder.clone().build_counting_bloom_filter();
+    /// total.add_n(b"hello", 3);
+    /// total.union_bytes(shard.get_u8_array(), shard.config().hashes).unwrap();
+    /// assert_eq!(total.estimate_count(b"hello"), 5);
+    /// ```
+    pub fn union_bytes(&mut self, bytes: &[u8], hashes: u32) -> Result<(), FilterError> {
+        if hashes != self.config.hashes {
+            return Err(FilterError::HashesMismatch { expected: self.config.hashes, found: hashes });
+        }
+        let size = bytes.len() as u64 * 2;
+        if size != self.config.size {
+            return Err(FilterError::SizeMismatch { expected: self.config.size, found: size });
+        }
+        self.counting_vec.saturating_add_bytes(bytes);
+        if let Some(overlay) = &mut self.overlay {
+            overlay.complete = false;
+        }
+        Ok(())
+    }
+
+    /// Subtracts the counters of a compatible counting bloom filter from this one counter by
+    /// counter, saturating at zero, e.g. to count the events of this filter not accounted for by
+    /// `other`. Returns `false` without changing anything if the filters are not compatible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let builder = FilterBuilder::new(10_000, 0.01);
+    /// let mut a = builder.clone().build_counting_bloom_filter();
+    /// a.add_n(b"hello", 5);
+    /// let mut b = builder.clone().build_counting_bloom_filter();
+    /// b.add_n(b"hello", 2);
+    /// assert!(a.subtract(&b));
+    /// assert_eq!(a.estimate_count(b"hello"), 3);
+    /// ```
+    pub fn subtract(&mut self, other: &CountingBloomFilter) -> bool {
+        if !self.config.is_compatible_to(&other.config) {
+            return false;
+        }
+        self.counting_vec.saturating_sub(&other.counting_vec);
+        if let Some(overlay) = &mut self.overlay {
+            overlay.complete = false;
+        }
+        true
+    }
+
+    /// Removes `element` `n` times, decrementing each of its counters by `n` at once (saturating
+    /// at zero) and hashing it only once. Like [Deletable::remove] nothing happens unless the
+    /// element appears present, so that counters of other elements are not corrupted. Without
+    /// repeat insert (see [FilterBuilder::enable_repeat_insert]) this is the same as a single
+    /// [Deletable::remove].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add_n(b"hello", 5);
+    /// cbf.remove_n(b"hello", 2);
+    /// assert_eq!(cbf.estimate_count(b"hello"), 3);
+    /// ```
+    pub fn remove_n(&mut self, element: &[u8], n: usize) {
+        if n == 0 { return; }
+        if !self.config.enable_repeat_insert {
+            self.remove(element);
+            return;
+        }
+        let n = self.removals(element, n);
+        if n == 0 { return; }
+        for index in self.probes(element) {
+            self.counting_vec.decrement_by(index as usize, n);
+        }
+    }
+
+    /// Get the underlying counter at index.
+    pub fn counter_at(&self, index: u64) -> usize {
+        self.counting_vec.get(index as usize)
+    }
+
+    /// Iterate `(index, count)` pairs of all non-zero counters, in index order, without
+    /// materializing the counter array. Slots whose counters are all zero are skipped as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// let counters: Vec<(u64, usize)> = cbf.nonzero_counters().collect();
+    /// assert!(counters.iter().all(|(_, count)| *count == 1));
+    /// ```
+    pub fn nonzero_counters(&self) -> impl Iterator<Item=(u64, usize)> + '_ {
+        self.counting_vec.nonzero().map(|(index, count)| (index as u64, count))
+    }
+
+    /// Estimate the total number of insertions, repeats included, as the sum of all counters
+    /// divided by `hashes`, since every insertion increments `hashes` counters. This is only an
+    /// estimate: hash collisions within an element, saturated counters and removals of elements
+    /// that were never added skew it, and with repeat insert disabled repeats are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// cbf.add(b"hello");
+    /// cbf.add(b"world");
+    /// assert_eq!(cbf.total_insertions(), 3);
+    /// ```
+    pub fn total_insertions(&self) -> u64 {
+        let sum: u64 = self.counting_vec.nonzero().map(|(_, count)| count as u64).sum();
+        sum / self.config.hashes as u64
+    }
+
+    /// Returns [true] if every counter is zero, e.g. once all added elements were removed again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{Deletable, FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// assert!(cbf.is_empty());
+    /// cbf.add(b"hello");
+    /// assert!(!cbf.is_empty());
+    /// cbf.remove(b"hello");
+    /// assert!(cbf.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.counting_vec.is_zero()
+    }
+
+    /// Returns the number of counters stuck at their largest value. A saturated counter no longer
+    /// counts insertions, and removals decrement it below the true count, so a growing number of
+    /// them means the filter is overloaded.
+    pub fn saturated_counters(&self) -> u64 {
+        self.counting_vec.nonzero().filter(|&(_, count)| count == COUNTER_MAX).count() as u64
+    }
+
+    /// Clears the filter if more than `threshold` of its counters are saturated, see
+    /// [CountingBloomFilter::saturated_counters], and returns whether it did. This trades all the
+    /// elements for bounded error in long running services where the filter slowly saturates.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// assert!(!cbf.reset_if_saturated(0.01));
+    /// assert!(cbf.contains(b"hello"));
+    /// ```
+    pub fn reset_if_saturated(&mut self, threshold: f64) -> bool {
+        let saturated = self.saturated_counters() as f64 / self.size_counters() as f64;
+        if saturated > threshold {
+            self.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return the membership of the counting bloom filter as the bytes of a bloom filter with the
+    /// same size, where bit `i` is set iff counter `i` is nonzero. Load it with
+    /// [BloomFilter::from_u8_array] and the same hashes; that uses the default
+    /// [FilterBuilder::hash_seeds], so the counting bloom filter must use them too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{BloomFilter, FilterBuilder, Hashes, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// let bloom = BloomFilter::from_u8_array(&cbf.to_bloom_bytes(), cbf.hashes());
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn to_bloom_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.config.size.div_ceil(8) as usize];
+        for (index, _) in self.counting_vec.nonzero() {
+            bytes[index / 8] |= 1 << (index % 8);
+        }
+        bytes
+    }
+
+    /// Return the counting bloom filter in a format independent of the pointer width of the
+    /// target. A 30 byte header records the hashes (`u32` little-endian), `enable_repeat_insert`
+    /// (one byte), the counter width in bits (one byte, always 4), the number of counters and the
+    /// two [FilterBuilder::hash_seeds] (each `u64` little-endian). The counters follow packed two
+    /// per byte, counter `i` in the low
+    /// nibble of byte `i / 2` when `i` is even and in the high nibble otherwise. Load it with
+    /// [CountingBloomFilter::from_portable_bytes].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{CountingBloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// let loaded = CountingBloomFilter::from_portable_bytes(&cbf.to_portable_bytes()).unwrap();
+    /// assert!(loaded.contains(b"hello"));
+    /// ```
+    pub fn to_portable_bytes(&self) -> Vec<u8> {
+        let size = self.config.size;
+        let mut bytes = Vec::with_capacity(PORTABLE_HEADER_LEN + size.div_ceil(2) as usize);
+        bytes.extend_from_slice(&self.config.hashes.to_le_bytes());
+        bytes.push(self.config.enable_repeat_insert as u8);
+        bytes.push(PORTABLE_COUNTER_BITS);
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend_from_slice(&self.config.hash_seeds.0.to_le_bytes());
+        bytes.extend_from_slice(&self.config.hash_seeds.1.to_le_bytes());
+        bytes.resize(PORTABLE_HEADER_LEN + size.div_ceil(2) as usize, 0);
+        for (index, count) in self.counting_vec.nonzero() {
+            bytes[PORTABLE_HEADER_LEN + index / 2] |= (count << (index % 2 * 4)) as u8;
+        }
+        bytes
+    }
+
+    /// Build a counting bloom filter from the format of [CountingBloomFilter::to_portable_bytes].
+    /// On 32 bit targets counters saturate at 7, so larger counters are clamped.
+    pub fn from_portable_bytes(bytes: &[u8]) -> Result<Self, FilterError> {
+        if bytes.len() < PORTABLE_HEADER_LEN {
+            return Err(FilterError::LengthMismatch { expected: PORTABLE_HEADER_LEN, found: bytes.len() });
+        }
+        let hashes = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let enable_repeat_insert = bytes[4] != 0;
+        let counter_bits = bytes[5];
+        let size = u64::from_le_bytes(bytes[6..14].try_into().unwrap());
+        let seed1 = u64::from_le_bytes(bytes[14..22].try_into().unwrap());
+        let seed2 = u64::from_le_bytes(bytes[22..30].try_into().unwrap());
+        if hashes == 0 { return Err(FilterError::ZeroHashes); }
+        if counter_bits != PORTABLE_COUNTER_BITS {
+            return Err(FilterError::UnsupportedCounterWidth(counter_bits));
+        }
+        if size == 0 { return Err(FilterError::ZeroSize); }
+        let counters_per_slot = (usize::BITS >> 2) as u64;
+        if size % counters_per_slot != 0 {
+            return Err(FilterError::SizeNotSlotAligned { size, counters_per_slot });
+        }
+        let expected = PORTABLE_HEADER_LEN + size.div_ceil(2) as usize;
+        if bytes.len() != expected {
+            return Err(FilterError::LengthMismatch { expected, found: bytes.len() });
+        }
+
+        let mut config = FilterBuilder::from_size_and_hashes(size, hashes);
+        config.enable_repeat_insert(enable_repeat_insert);
+        config.hash_seeds(seed1, seed2);
+        let mut counting_vec = CountingVec::new((size / counters_per_slot) as usize);
+        for (i, byte) in bytes[PORTABLE_HEADER_LEN..].iter().enumerate().filter(|(_, b)| **b != 0) {
+            counting_vec.set(2 * i, (byte & 0b1111) as usize);
+            counting_vec.set(2 * i + 1, (byte >> 4) as usize);
+        }
+        Ok(CountingBloomFilter { config, counting_vec, overlay: None })
+    }
+
+    /// Return every counter unpacked into its own byte, byte `i` holding the value of counter `i`,
+    /// for analysis outside of the filter. Rebuild the filter with
+    /// [CountingBloomFilter::from_counters_u8].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add(b"hello");
+    /// let counters = cbf.counters_as_u8();
+    /// assert_eq!(counters.len() as u64, cbf.size_counters());
+    /// assert_eq!(counters.iter().map(|c| *c as u32).sum::<u32>(), 7);
+    /// ```
+    pub fn counters_as_u8(&self) -> Vec<u8> {
+        let mut counters = vec![0u8; self.config.size as usize];
+        for (index, count) in self.counting_vec.nonzero() {
+            counters[index] = count as u8;
+        }
+        counters
+    }
+
+    /// Build a counting bloom filter from the counters returned by
+    /// [CountingBloomFilter::counters_as_u8], one counter per byte. Counters larger than a counter
+    /// holds are clamped, to 15 or to 7 on 32 bit targets. The number of counters must fill whole
+    /// native slots, as for [CountingBloomFilter::from_portable_bytes].
+    pub fn from_counters_u8(counters: &[u8], hashes: u32, enable_repeat_insert: bool)
+                            -> Result<Self, FilterError> {
+        let size = counters.len() as u64;
+        if hashes == 0 { return Err(FilterError::ZeroHashes); }
+        if size == 0 { return Err(FilterError::ZeroSize); }
+        let counters_per_slot = (usize::BITS >> 2) as u64;
+        if !size.is_multiple_of(counters_per_slot) {
+            return Err(FilterError::SizeNotSlotAligned { size, counters_per_slot });
+        }
+
+        let mut config = FilterBuilder::from_size_and_hashes(size, hashes);
+        config.enable_repeat_insert(enable_repeat_insert);
+        let mut counting_vec = CountingVec::new((size / counters_per_slot) as usize);
+        for (i, count) in counters.iter().enumerate().filter(|(_, c)| **c != 0) {
+            counting_vec.set(i, *count as usize);
+        }
+        Ok(CountingBloomFilter { config, counting_vec, overlay: None })
+    }
+
+    /// Build a counting bloom filter from a membership bitmap and a parallel array of counts, e.g.
+    /// when migrating from a system that stores them separately. `bits` is laid out like
+    /// [BloomFilter::to_vec], bit `i` being bit `i % 8` of byte `i / 8`, and `counts` holds
+    /// one count per bit. Counter `i` is `counts[i]` where bit `i` is set and zero elsewhere; a
+    /// set bit with a count of zero becomes 1 to keep the membership. Counts are clamped like in
+    /// [CountingBloomFilter::from_counters_u8], which also validates the geometry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{CountingBloomFilter, FilterBuilder, Membership};
+    ///
+    /// let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    /// cbf.add_n(b"hello", 3);
+    /// let loaded = CountingBloomFilter::from_parts(&cbf.to_bloom_bytes(), &cbf.counters_as_u8(),
+    ///                                              7, true).unwrap();
+    /// assert_eq!(loaded.estimate_count(b"hello"), 3);
+    /// ```
+    pub fn from_parts(bits: &[u8], counts: &[u8], hashes: u32, enable_repeat_insert: bool)
+                      -> Result<Self, FilterError> {
+        if counts.len() != bits.len() * 8 {
+            return Err(FilterError::LengthMismatch { expected: bits.len() * 8, found: counts.len() });
+        }
+        let counters: Vec<u8> = counts.iter().enumerate()
+            .map(|(i, count)| if bits[i / 8] >> (i % 8) & 1 == 1 { (*count).max(1) } else { 0 })
+            .collect();
+        CountingBloomFilter::from_counters_u8(&counters, hashes, enable_repeat_insert)
+    }
+}
+
+impl Membership for CountingBloomFilter {
+    fn add(&mut self, element: &[u8]) {
+        let probes = self.probes(element);
+
+        // contains and not enable repeat insert
+        if !self.config.enable_repeat_insert
+            && probes.clone().all(|index| self.counting_vec.get(index as usize) > 0) {
+            return;
+        }
+
+        // insert
+        for index in probes {
+            self.counting_vec.increment(index as usize);
+        }
+        if let Some(overlay) = &mut self.overlay {
+            overlay.record_add(element, 1);
+        }
+    }
+
+    #[inline]
+    fn contains(&self, element: &[u8]) -> bool {
+        self.probes(element).all(|index| self.counting_vec.get(index as usize) > 0)
+    }
+
+    fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
+        self.probes(element).collect()
+    }
+
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool {
+        for x in indices.iter() {
+            let index = *x;
+            if self.counting_vec.get(index as usize) == 0 { return false; }
+        }
+        true
+    }
+
+    fn clear(&mut self) {
+        self.counting_vec.clear();
+        if let Some(overlay) = &mut self.overlay {
+            overlay.clear();
+        }
+    }
+
+    fn as_deletable(&mut self) -> Option<&mut dyn Deletable> {
+        Some(self)
+    }
+}
+
+impl Deletable for CountingBloomFilter {
+    fn remove(&mut self, element: &[u8]) {
+        if self.removals(element, 1) == 0 { return; }
+        for index in self.probes(element) {
+            self.counting_vec.decrement(index as usize);
+        }
+    }
+}
+
+impl Hashes for CountingBloomFilter {
+    fn hashes(&self) -> u32 {
+        self.config.hashes
+    }
+}
+
+/// A Partitioned Bloom Filter is a variation of a classic Bloom Filter.
+///
+/// This filter works by partitioning the M-sized bit array into k slices of size `m = M/k` bits,
+/// `k = nb of hash functions` in the filter. Each hash function produces an index over `m` for its
+/// respective slice. Thus, each element is described by exactly `k` bits, meaning the distribution
+/// of false positives is uniform across all elements.
+///
+/// Be careful, as a Partitioned Bloom Filter have much higher collison risks that a classic
+/// Bloom Filter on small sets of data.
+///
+/// **Reference**: Chang, F., Feng, W. C., & Li, K. (2004, March). Approximate caches for packet
+/// classification. In INFOCOM 2004. Twenty-third AnnualJoint Conference of the IEEE Computer and
+/// Communications Societies (Vol. 4, pp. 2196-2207). IEEE.
+/// [Full text article](http://citeseerx.ist.psu.edu/viewdoc/download?doi=10.1.1.153.6902&rep=rep1&type=pdf)
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct PartitionedBloomFilter {}
+
+impl PartitionedBloomFilter {}
+
+/// A Scalable Bloom Filter is a variant of Bloom Filters that can adapt dynamically to the number
+/// of elements stored, while assuring a maximum false positive probability.
+///
+/// **Reference**: ALMEIDA, Paulo Sérgio, BAQUERO, Carlos, PREGUIÇA, Nuno, et al. Scalable bloom
+/// filters. Information Processing Letters, 2007, vol. 101, no 6, p. 255-261.
+/// [Full text article](https://citeseerx.ist.psu.edu/viewdoc/download?doi=10.1.1.725.390&rep=rep1&type=pdf)
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct ScalableBloomFilter {}
+
+impl ScalableBloomFilter {}
+
+/// An Invertible Bloom Filters (IBLT), also called Invertible Bloom Lookup Table, is a
+/// space-efficient and probabilistic data-structure for solving the set-difference problem
+/// efficiently without the use of logs or other prior context. It computes the set difference
+/// with communication proportional to the size of the difference between the sets being compared.
+/// They can simultaneously calculate D(A−B) and D(B−A) using O(d) space. This data structure
+/// encodes sets in a fashion that is similar in spirit to Tornado codes’ construction, in that it
+/// randomly combines elements using the XOR function.
+///
+/// **Reference**: Eppstein, D., Goodrich, M. T., Uyeda, F., & Varghese, G. (2011). What's the
+/// difference?: efficient set reconciliation without prior context. ACM SIGCOMM Computer
+/// Communication Review, 41(4), 218-229.
+/// [Full text article](http://www.sysnet.ucsd.edu/sysnet/miscpapers/EppGooUye-SIGCOMM-11.pdf)
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct InvertibleBloomFilter {}
+
+impl InvertibleBloomFilter {}
+
+#[derive(Clone)]
+#[derive(Debug)]
+pub(crate) struct GarbledBloomFilter {}
+
+impl GarbledBloomFilter {}
+
+
+#[test]
+fn bloom_test() {
+    let mut builder =
+        FilterBuilder::new(10_000_000, 0.01);
+    let mut bloom = builder.build_bloom_filter();
+    println!("{:?}", bloom.config);
+    bloom.add(b"hello");
+    println!("{:?}", &bloom.bit_set.storage[0..300]);
+    assert_eq!(bloom.contains(b"hello"), true);
+    assert_eq!(bloom.contains(b"world"), false);
+    assert_eq!(bloom.add_if_not_contains(b"hello2"), false);
+    assert_eq!(bloom.contains(b"hello2"), true);
+
+    let storage = &bloom.bit_set.storage[0..300];
+    println!("{:?}", storage);
+
+    #[cfg(target_pointer_width = "64")]{
+        let mut bloom2 = BloomFilter::from_u64_array(bloom.get_u64_array(), bloom.hashes());
+        assert_eq!(bloom2.compatible(&bloom), true);
+        assert_eq!(bloom2.contains(b"hello"), true);
+        assert_eq!(bloom2.contains(b"world"), false);
+    }
+
+    let mut bloom3 =
+        BloomFilter::from_u32_array(bloom.get_u32_array(), bloom.config.hashes);
+    assert_eq!(bloom3.compatible(&bloom), true);
+    assert_eq!(bloom3.contains(b"hello"), true);
+    assert_eq!(bloom3.contains(b"world"), false);
+
+    let u8_array = bloom.to_vec();
+    let mut bloom4 = BloomFilter::from_u8_array(&u8_array, bloom.config.hashes);
+    println!("{:?}", &bloom4.bit_set.storage[0..300]);
+    assert_eq!(bloom4.compatible(&bloom), true);
+    assert_eq!(bloom4.contains(b"hello"), true);
+    assert_eq!(bloom4.contains(b"world"), false);
+
+    let bloom5 = BloomFilter::from_u16_array(bloom.get_u16_array(), bloom.hashes());
+    assert_eq!(bloom5.compatible(&bloom), true);
+    assert_eq!(bloom5.contains(b"hello"), true);
+    assert_eq!(bloom5.contains(b"world"), false);
+
+    bloom4.add(b"hello world");
+
+    assert_eq!(bloom.intersect(&bloom4), true);
+    assert_eq!(bloom.contains(b"hello"), true);
+    assert_eq!(bloom.contains(b"hello world"), false);
+
+    bloom3.add(b"hello world");
+    bloom3.add(b"hello yankun");
+
+    assert_eq!(bloom3.union(&bloom4), true);
+    assert_eq!(bloom3.contains(b"hello"), true);
+    assert_eq!(bloom3.contains(b"hello world"), true);
+    assert_eq!(bloom3.contains(b"hello yankun"), true);
+}
+
+#[test]
+fn bloom_hash_indices_test() {
+    let mut builder =
+        FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.build_bloom_filter();
+    println!("{:?}", bloom.config);
+    bloom.add(b"hello");
+    assert_eq!(bloom.contains(b"hello"), true);
+    assert_eq!(bloom.contains(b"world"), false);
+
+    let indices = bloom.get_hash_indices(b"hello");
+    println!("{:?}", indices);
+    assert_eq!(bloom.contains_hash_indices(&indices), true);
+    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"world")), false);
+}
+
+#[test]
+fn bloom_hash_indices_u32_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    let mut mapper = builder.build_bloom_filter();
+    let mut reducer = builder.build_bloom_filter();
+    let mut dump: Vec<u8> = Vec::new();
+    for x in 0..1000u64 {
+        mapper.add(&x.to_le_bytes());
+        for index in mapper.get_hash_indices_u32(&x.to_le_bytes()) {
+            dump.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    let hashes = reducer.hashes() as usize;
+    let shipped: Vec<u32> = dump.chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    for indices in shipped.chunks_exact(hashes) {
+        reducer.add_hash_indices_u32(indices);
+    }
+
+    assert_eq!(reducer.get_u8_array(), mapper.get_u8_array());
+    for x in 0..1000u64 {
+        assert!(reducer.contains(&x.to_le_bytes()));
+        assert!(reducer.contains_hash_indices_u32(&reducer.get_hash_indices_u32(&x.to_le_bytes())));
+    }
+    assert_eq!(reducer.contains_hash_indices_u32(&reducer.get_hash_indices_u32(b"hello")),
+               reducer.contains(b"hello"));
+}
+
+#[test]
+fn bloom_contains_hash_indices_slice_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    bloom.add(b"hello");
+    cbf.add(b"hello");
+    let config = bloom.config();
+    for element in [b"hello".as_ref(), b"world"] {
+        let vec = bloom.get_hash_indices(element);
+        let expected = bloom.contains_hash_indices(&vec);
+        let small = crate::hashing::indices(element, config.size, config.hashes, 0);
+        assert_eq!(bloom.contains_hash_indices(&small), expected);
+        assert_eq!(bloom.contains_hash_indices(&vec[..2]), bloom.contains_hash_indices(&small[..2]));
+        let mut array = [0u64; 16];
+        let len = vec.len();
+        for (slot, index) in array.iter_mut().zip(vec.iter().copied()) {
+            *slot = index;
+        }
+        assert_eq!(bloom.contains_hash_indices(&array[..len]), expected);
+        assert_eq!(cbf.contains_hash_indices(&small), expected);
+    }
+}
+
+#[test]
+fn bloom_incremental_indices_test() {
+    let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let m = bloom.config.size;
+    let k = bloom.config.hashes as u64;
+    for x in 0..10_000u64 {
+        let element = x.to_le_bytes();
+        let (hash1, hash2) = crate::hashing::hash_pair(&element, 0);
+        let (hash1, hash2) = (hash1 % m, hash2 % m);
+        let expected: Vec<u64> = (0..k).map(|i| (hash1 + i * hash2) % m).collect();
+        assert_eq!(bloom.get_hash_indices(&element), expected);
+    }
+}
+
+#[test]
+fn bloom_power_of_two_size_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.power_of_two_size(true);
+    let mut masked = builder.build_bloom_filter();
+    let m = masked.config.size;
+    assert!(m.is_power_of_two());
+    for x in 0..10_000u64 {
+        let element = x.to_le_bytes();
+        bloom.add(&element);
+        masked.add(&element);
+        // the mask reduces hashes exactly as the modulo does.
+        let (hash1, hash2) = crate::hashing::hash_pair(&element, 0);
+        let (hash1, hash2) = (hash1 % m, hash2 % m);
+        let expected: Vec<u64> = (0..masked.config.hashes as u64)
+            .map(|i| (hash1 + i * hash2) % m).collect();
+        assert_eq!(masked.get_hash_indices(&element), expected);
+    }
+    for x in 0..10_000u64 {
+        assert!(bloom.contains(&x.to_le_bytes()));
+        assert!(masked.contains(&x.to_le_bytes()));
+    }
+    let errors = (10_000..110_000u64).filter(|x| masked.contains(&x.to_le_bytes())).count();
+    assert!(errors < 1_000, "{errors}");
+}
+
+#[test]
+fn bloom_estimate_union_cardinality_test() {
+    let builder = FilterBuilder::new(100_000, 0.01);
+    let mut a = builder.clone().build_bloom_filter();
+    let mut b = builder.clone().build_bloom_filter();
+    // |A| = 30_000, |B| = 40_000, |A ∩ B| = 10_000, so |A ∪ B| = 60_000.
+    for x in 0..30_000u64 {
+        a.add(&x.to_le_bytes());
+    }
+    for x in 20_000..60_000u64 {
+        b.add(&x.to_le_bytes());
+    }
+    let a_bytes = a.get_u8_array().to_vec();
+    let b_bytes = b.get_u8_array().to_vec();
+    let estimate = a.estimate_union_cardinality(&b).unwrap();
+    assert!((estimate - 60_000.0).abs() < 600.0, "{estimate}");
+    assert_eq!(a.get_u8_array(), &a_bytes[..]);
+    assert_eq!(b.get_u8_array(), &b_bytes[..]);
+
+    a.union(&b);
+    assert!((a.estimate_set_cardinality() - estimate).abs() < 1e-6);
+
+    let other = FilterBuilder::new(1_000, 0.01).build_bloom_filter();
+    assert_eq!(a.estimate_union_cardinality(&other), None);
+}
+
+#[test]
+fn bloom_canonical_byte_order_test() {
+    // a 128 bit filter as a 32 bit machine stores it: four u32 words, bit i in word i / 32.
+    let bits = [0u64, 1, 31, 32, 33, 63, 64, 70, 95, 127];
+    let mut words32 = [0u32; 4];
+    for &i in bits.iter() {
+        words32[(i / 32) as usize] |= 1 << (i % 32);
+    }
+    let bytes: Vec<u8> = words32.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let bloom = BloomFilter::from_u8_array(&bytes, 3);
+    assert_eq!(bloom.config.size, 128);
+    for i in 0..128u64 {
+        assert_eq!(bloom.bit_set.get(i as usize), bits.contains(&i), "bit {i}");
+    }
+    assert_eq!(bloom.to_vec(), bytes);
+    assert_eq!(BloomFilter::from_u32_array(&words32, 3).to_vec(), bytes);
+
+    // the same bits stored by a 64 bit machine regroup into the same bytes.
+    let mut words64 = [0u64; 2];
+    for &i in bits.iter() {
+        words64[(i / 64) as usize] |= 1 << (i % 64);
+    }
+    let bytes64: Vec<u8> = words64.iter().flat_map(|w| w.to_le_bytes()).collect();
+    assert_eq!(bytes64, bytes);
+    let bloom = BloomFilter::from_u64_array(&words64, 3);
+    assert_eq!(bloom.to_vec(), bytes64);
+    assert_eq!(BloomFilter::from_u8_array(&bloom.to_vec(), 3).get_u64_array(), &words64[..]);
+
+    // bytes not holding whole 64 bit words are rejected rather than padded.
+    assert_eq!(BloomFilter::try_from((&bytes[..12], 3)).unwrap_err(), FilterError::LengthNotWordAligned(12));
+    assert!(std::panic::catch_unwind(|| BloomFilter::from_u8_array(&bytes[..12], 3)).is_err());
+}
+
+#[test]
+fn bloom_clear_range_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(256, 1).build_bloom_filter();
+    bloom.as_bytes_mut().fill(0xff);
+    // spans a partial first word, a full interior word and a partial last word.
+    bloom.clear_range(60, 190);
+    for i in 0..256 {
+        assert_eq!(bloom.bit_set.get(i), !(60..190).contains(&i), "bit {i}");
+    }
+    // inside a single word.
+    bloom.clear_range(200, 203);
+    for i in 190..256 {
+        assert_eq!(bloom.bit_set.get(i), !(200..203).contains(&i), "bit {i}");
+    }
+    // empty range and full word edges.
+    bloom.clear_range(10, 10);
+    assert!(bloom.bit_set.get(10));
+    bloom.clear_range(0, 64);
+    assert_eq!(bloom.get_u64_array()[0], 0);
+    assert_eq!(bloom.get_u64_array()[3], !(0b111u64 << 8));
+    bloom.clear_range(0, 256);
+    assert!(bloom.get_u8_array().iter().all(|b| *b == 0));
+}
+
+#[test]
+fn bloom_bytes_conversion_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..10_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let bytes = Vec::<u8>::from(&bloom);
+    assert_eq!(bloom.to_vec(), bytes);
+    let restored = BloomFilter::try_from((&bytes[..], bloom.hashes())).unwrap();
+    assert_eq!(restored.config.size, bloom.config.size);
+    for x in 0..20_000u64 {
+        assert_eq!(restored.contains(&x.to_le_bytes()), bloom.contains(&x.to_le_bytes()));
+    }
+
+    assert_eq!(BloomFilter::try_from((&[][..], 7)).err(), Some(FilterError::ZeroSize));
+    assert_eq!(BloomFilter::try_from((&bytes[..], 0)).err(), Some(FilterError::ZeroHashes));
+}
+
+#[test]
+fn bloom_hash_width_test() {
+    // 20 elements in 4096 bits with 20 hashes target a false positive rate of about 1e-21, so
+    // every false positive comes from the probes of a query collapsing onto a few bits.
+    let target = crate::builder::optimal_p(20, 4096, 20);
+    let mut builder = FilterBuilder::from_size_and_hashes(4096, 20);
+    let mut bloom64 = builder.build_bloom_filter();
+    builder.hash_width(HashWidth::Bits128);
+    let mut bloom128 = builder.build_bloom_filter();
+    assert!(!bloom64.compatible(&bloom128));
+    for x in 0..20u64 {
+        bloom64.add(&x.to_le_bytes());
+        bloom128.add(&x.to_le_bytes());
+    }
+    for x in 0..20u64 {
+        assert!(bloom128.contains(&x.to_le_bytes()));
+        assert!(bloom128.add_if_not_contains(&x.to_le_bytes()));
+    }
+
+    let queries = 2_000_000u64;
+    let fpr = |bloom: &BloomFilter| {
+        (20..20 + queries).filter(|x| bloom.contains(&x.to_le_bytes())).count() as f64
+            / queries as f64
+    };
+    let (fpr64, fpr128) = (fpr(&bloom64), fpr(&bloom128));
+    assert!((fpr128 - target).abs() < (fpr64 - target).abs(), "{fpr64} {fpr128}");
+}
+
+#[test]
+fn bloom_len_test() {
+    let mut bloom = FilterBuilder::new(100_000, 0.01).build_bloom_filter();
+    assert_eq!(bloom.len(), 0);
+    assert_eq!(bloom.capacity(), 100_000);
+    for x in 0..50_000u64 {
+        bloom.add(&x.to_le_bytes());
+        // repeated inserts do not change the estimate.
+        bloom.add(&x.to_le_bytes());
+    }
+    let len = bloom.len();
+    assert!(len > 49_500 && len < 50_500, "{len}");
+}
+
+#[test]
+fn bloom_hamming_distance_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..1_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    assert_eq!(bloom.hamming_distance(&bloom), Some(0));
+
+    let snapshot = bloom.clone();
+    bloom.add(b"hello");
+    let distance = bloom.hamming_distance(&snapshot).unwrap();
+    let hashes = bloom.hashes() as u64;
+    // some of the bits of b"hello" may already have been set.
+    assert!(distance <= hashes && distance >= hashes - 2, "{distance}");
+    assert_eq!(snapshot.hamming_distance(&bloom), Some(distance));
+
+    let other = FilterBuilder::new(1_000, 0.01).build_bloom_filter();
+    assert_eq!(bloom.hamming_distance(&other), None);
+}
+
+#[test]
+fn bloom_subsample_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(1024, 1).build_bloom_filter();
+    // the first half of the filter is set, the second half is empty.
+    bloom.as_bytes_mut()[..64].fill(0xff);
+    let preview = bloom.subsample(64);
+    assert_eq!(preview, [vec![true; 8], vec![false; 8]].concat());
+
+    let preview = bloom.subsample(3);
+    assert_eq!(preview.len(), 342);
+    for (i, bit) in preview.into_iter().enumerate() {
+        assert_eq!(bit, i * 3 < 512, "{i}");
+    }
+    assert_eq!(bloom.subsample(1).len(), 1024);
+}
+
+#[test]
+fn bloom_from_iter_test() {
+    let keys: Vec<Vec<u8>> = (0..1000u64).map(|x| x.to_le_bytes().to_vec()).collect();
+    let bloom: BloomFilter = keys.clone().into_iter().collect();
+    for key in keys.iter() {
+        assert!(bloom.contains(key));
+    }
+    assert_eq!(bloom.config.expected_elements, 1000);
+
+    let filtered: BloomFilter = keys.into_iter().filter(|key| key[0] % 2 == 0).collect();
+    assert!(filtered.contains(&0u64.to_le_bytes()));
+    assert_eq!(filtered.config.expected_elements, 500);
+}
+
+#[test]
+fn bloom_clear_dirty_test() {
+    let mut builder = FilterBuilder::new(100_000_000, 0.01);
+    builder.enable_dirty_tracking(true);
+    let mut bloom = builder.build_bloom_filter();
+    for x in 0..10u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let touched = bloom.bit_set.storage.iter().filter(|w| **w != 0).count();
+    let written = bloom.bit_set.clear_dirty();
+    assert_eq!(written, touched);
+    assert!(written * 1000 < bloom.bit_set.storage.len());
+    assert!(bloom.bit_set.storage.iter().all(|w| *w == 0));
+    for x in 0..10u64 {
+        assert!(!bloom.contains(&x.to_le_bytes()));
+    }
+
+    // the dirty set is reset, so a second clear writes nothing.
+    assert_eq!(bloom.bit_set.clear_dirty(), 0);
+
+    // every add path records the words it writes.
+    builder.hash_width(HashWidth::Bits128);
+    let mut wide = builder.build_bloom_filter();
+    wide.add(b"hello");
+    assert!(!wide.add_if_not_contains(b"world"));
+    bloom.add_if_not_contains(b"hello");
+    bloom.add_u64(42);
+    for filter in [&mut bloom, &mut wide] {
+        let touched = filter.bit_set.storage.iter().filter(|w| **w != 0).count();
+        assert_eq!(filter.bit_set.clear_dirty(), touched);
+        assert!(filter.is_empty());
+    }
+
+    // without tracking the whole storage is zeroed.
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add(b"hello");
+    bloom.clear_dirty();
+    assert!(!bloom.contains(b"hello"));
+}
+
+#[test]
+fn bloom_as_bytes_mut_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let indices = bloom.get_hash_indices(b"hello");
+    let bytes = bloom.as_bytes_mut();
+    for index in indices.iter() {
+        let index = *index as usize;
+        #[cfg(target_pointer_width = "64")]
+            let byte = (index >> 6 << 3) + ((index & 63) >> 3);
+        #[cfg(target_pointer_width = "32")]
+            let byte = (index >> 5 << 2) + ((index & 31) >> 3);
+        let byte = if cfg!(target_endian = "little") { byte } else { byte ^ (std::mem::size_of::<usize>() - 1) };
+        bytes[byte] |= 1 << (index & 7);
+    }
+    assert!(bloom.contains(b"hello"));
+    assert!(!bloom.contains(b"world"));
+
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.enable_dirty_tracking(true);
+    let mut bloom = builder.build_bloom_filter();
+    bloom.as_bytes_mut().fill(0xff);
+    assert!(bloom.contains(b"world"));
+    bloom.clear_dirty();
+    assert!(!bloom.contains(b"world"));
+    bloom.add(b"hello");
+    assert!(bloom.bit_set.dirty.as_ref().is_some_and(|dirty| !dirty.is_empty()));
+}
+
+#[test]
+fn bloom_fold_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(1 << 20, 7).build_bloom_filter();
+    for x in 0..10_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let folded = bloom.fold(1).unwrap();
+    assert_eq!(folded.config.size, bloom.config.size / 2);
+    assert_eq!(folded.hashes(), bloom.hashes());
+    for x in 0..10_000u64 {
+        assert!(folded.contains(&x.to_le_bytes()));
+    }
+
+    let twice = bloom.fold(2).unwrap();
+    assert_eq!(twice.config.size, bloom.config.size / 4);
+    assert!(twice.contains(&42u64.to_le_bytes()));
+
+    assert!(bloom.fold(64).is_none());
+    assert!(bloom.fold(16).is_none());
+
+    let odd = FilterBuilder::from_size_and_hashes(64 * 3, 2).build_bloom_filter();
+    assert!(odd.fold(1).is_none());
+}
+
+#[test]
+fn bloom_fold_keeps_config_test() {
+    let mut seeded = FilterBuilder::from_size_and_hashes(1 << 16, 7);
+    seeded.hash_seeds(7, 99);
+    let mut wide = FilterBuilder::from_size_and_hashes(1 << 16, 7);
+    wide.hash_width(HashWidth::Bits128);
+    for mut builder in [seeded, wide] {
+        let mut bloom = builder.build_bloom_filter();
+        for x in 0..1000u64 {
+            bloom.add(&x.to_le_bytes());
+        }
+        let folded = bloom.fold(1).unwrap();
+        assert_eq!(folded.config.hash_seeds, bloom.config.hash_seeds);
+        assert_eq!(folded.config.hash_width, bloom.config.hash_width);
+        assert!((0..1000u64).all(|x| folded.contains(&x.to_le_bytes())));
+    }
+}
+
+#[test]
+fn rolling_bloom_test() {
+    let mut rolling = FilterBuilder::new(10_000, 0.01).build_rolling_bloom_filter(3);
+    assert_eq!(rolling.windows(), 3);
+    rolling.add(b"hello");
+    assert!(rolling.contains(b"hello"));
+    rolling.rotate();
+    rolling.add(b"world");
+    rolling.rotate();
+    assert!(rolling.contains(b"hello"));
+    assert!(rolling.contains(b"world"));
+    rolling.rotate();
+    assert!(!rolling.contains(b"hello"));
+    assert!(rolling.contains(b"world"));
+    rolling.rotate();
+    assert!(!rolling.contains(b"world"));
+}
+
+#[test]
+fn rolling_bloom_hash_width_test() {
+    for hash_width in [HashWidth::Bits64, HashWidth::Bits128, HashWidth::LegacyMurmur128] {
+        let mut builder = FilterBuilder::new(1000, 0.01);
+        builder.hash_width(hash_width);
+        builder.hash_seeds(7, 99);
+        let mut rolling = builder.build_rolling_bloom_filter(2);
+        for x in 0..1000u64 {
+            rolling.add(&x.to_le_bytes());
+        }
+        rolling.rotate();
+        for x in 1000..2000u64 {
+            rolling.add(&x.to_le_bytes());
+        }
+        for x in 0..4000u64 {
+            let element = x.to_le_bytes();
+            assert_eq!(rolling.contains(&element),
+                       rolling.filters.iter().any(|filter| filter.contains(&element)), "{hash_width:?} {x}");
+        }
+        assert!((0..2000u64).all(|x| rolling.contains(&x.to_le_bytes())));
+    }
+}
+
+#[test]
+fn bloom_contains_with_confidence_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    assert_eq!(bloom.fill_ratio(), 0.0);
+    for x in 0..10_000u64 {
+        bloom.add(&x.to_le_bytes());
     }
+    let error = bloom.contains_with_confidence(&42u64.to_le_bytes()).unwrap();
+    assert!(error > 0.0 && error < 0.02, "{error}");
+    assert!((bloom.fill_ratio() - 0.5).abs() < 0.05);
+    assert_eq!(bloom.contains_with_confidence(b"hello"), None);
+}
 
-    pub(crate) fn set_bit_vec(&mut self, bit_vec: BloomBitVec) {
-        assert_eq!(self.config.size, bit_vec.nbits as u64);
-        self.bit_set = bit_vec
+#[test]
+fn bloom_concat_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(1 << 16, 7).build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let words = bloom.bit_set.storage.len();
+    let shards: Vec<BloomFilter> = bloom.get_u64_array().chunks(words / 4)
+        .map(|chunk| BloomFilter::from_u64_array(chunk, 7))
+        .collect();
+    let concat = BloomFilter::concat(&shards).unwrap();
+    assert!(concat.compatible(&bloom));
+    assert_eq!(concat.bit_set.storage, bloom.bit_set.storage);
+    for x in 0..1000u64 {
+        assert!(concat.contains(&x.to_le_bytes()));
     }
 
-    /// Checks if two Bloom filters are compatible, i.e. have compatible parameters (hash function,
-    /// size, etc.)
-    fn compatible(&self, other: &BloomFilter) -> bool {
-        self.config.is_compatible_to(&other.config)
+    let other = FilterBuilder::from_size_and_hashes(1 << 10, 3).build_bloom_filter();
+    assert_eq!(BloomFilter::concat(&[bloom, other]).unwrap_err(),
+               FilterError::HashesMismatch { expected: 7, found: 3 });
+    assert_eq!(BloomFilter::concat(&[]).unwrap_err(), FilterError::NoFilters);
+}
+
+#[test]
+fn bloom_concat_keeps_config_test() {
+    let mut seeded = FilterBuilder::from_size_and_hashes(1 << 16, 7);
+    seeded.hash_seeds(7, 99);
+    let mut wide = FilterBuilder::from_size_and_hashes(1 << 16, 7);
+    wide.hash_width(HashWidth::Bits128);
+    for mut builder in [seeded, wide] {
+        let mut bloom = builder.build_bloom_filter();
+        for x in 0..1000u64 {
+            bloom.add(&x.to_le_bytes());
+        }
+        let words = bloom.bit_set.storage.len();
+        let shards: Vec<BloomFilter> = bloom.bit_set.storage.chunks(words / 4).map(|chunk| {
+            let mut shard = builder.clone();
+            shard.size = (chunk.len() * usize::BITS as usize) as u64;
+            let mut shard = BloomFilter::new(shard);
+            shard.bit_set.storage.copy_from_slice(chunk);
+            shard
+        }).collect();
+        let concat = BloomFilter::concat(&shards).unwrap();
+        assert_eq!(concat.config(), bloom.config());
+        assert!((0..1000u64).all(|x| concat.contains(&x.to_le_bytes())));
+
+        let default = BloomFilter::from_u8_array(&shards[0].to_vec(), 7);
+        assert_eq!(BloomFilter::concat(&[shards[0].clone(), default]).unwrap_err(),
+                   FilterError::HashConfigMismatch);
     }
 }
 
-/// A Counting Bloom filter works in a similar manner as a regular Bloom filter; however, it is
-/// able to keep track of insertions and deletions. In a counting Bloom filter, each entry in the
-/// Bloom filter is a small counter associated with a basic Bloom filter bit.
-///
-/// **Reference**: F. Bonomi, M. Mitzenmacher, R. Panigrahy, S. Singh, and G. Varghese, “An Improved
-/// Construction for Counting Bloom Filters,” in 14th Annual European Symposium on
-/// Algorithms, LNCS 4168, 2006
-#[derive(Clone)]
-#[derive(Debug)]
-pub struct CountingBloomFilter {
-    config: FilterBuilder,
-    counting_vec: CountingVec,
+#[test]
+fn bloom_canonical_encoding_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add(&87i64.to_le_bytes());
+    bloom.add("hello".as_bytes());
+    assert!(bloom.contains_int(87));
+    assert!(bloom.contains_str("hello"));
+    // the bytes Python produces for `(87).to_bytes(8, "little", signed=True)`.
+    assert!(bloom.contains(&[87, 0, 0, 0, 0, 0, 0, 0]));
+    assert!(!bloom.contains(&87i32.to_le_bytes()));
+
+    let mut counting = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    counting.add(&(-1i64).to_le_bytes());
+    assert!(counting.contains_int(-1));
 }
 
-macro_rules! get_array {
-    ($name:ident, $native:ty, $len:expr) => {
-        impl CountingBloomFilter {
-            pub fn $name(&self) -> &[$native] {
-                let ptr = self.counting_vec.storage.as_ptr() as *const $native;
-                #[cfg(target_pointer_width = "64")]
-                    let arr = slice_from_raw_parts(ptr, self.counting_vec.storage.len() * $len);
-                #[cfg(target_pointer_width = "32")]
-                    if cfg!(target_pointer_width= "32") {
-                        if self.counting_vec.storage.len() % 2 != 0 {
-                            panic!("CountingVec with len {} can't export as u64 array!", self.counting_vec.storage.len())
-                        }
-                    }
-                #[cfg(target_pointer_width = "32")]
-                    let arr = slice_from_raw_parts(ptr, self.counting_vec.storage.len() * $len / 2);
-                unsafe { &*arr }
+#[test]
+fn bloom_union_bytes_test() {
+    let builder = FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.clone().build_bloom_filter();
+    let mut other = builder.clone().build_bloom_filter();
+    for x in 0..500u64 {
+        bloom.add(&x.to_le_bytes());
+        other.add(&(x + 1_000).to_le_bytes());
+    }
+    let mut expected = bloom.clone();
+    assert!(expected.union(&other));
+    bloom.union_bytes(&other.to_vec(), other.hashes()).unwrap();
+    assert_eq!(bloom.bit_set.storage, expected.bit_set.storage);
+
+    assert_eq!(bloom.union_bytes(&other.to_vec(), other.hashes() + 1).unwrap_err(),
+               FilterError::HashesMismatch { expected: other.hashes(), found: other.hashes() + 1 });
+    let bytes = &other.to_vec()[8..];
+    assert_eq!(bloom.union_bytes(bytes, other.hashes()).unwrap_err(),
+               FilterError::SizeMismatch { expected: other.config.size, found: other.config.size - 64 });
+}
+
+#[test]
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+fn bloom_new_lazy_test() {
+    /// Resident kB of the mapping containing `ptr`, read from `/proc/self/smaps`.
+    fn resident_kb(ptr: *const u8) -> u64 {
+        let smaps = fs::read_to_string("/proc/self/smaps").unwrap();
+        let addr = ptr as usize;
+        let mut inside = false;
+        for line in smaps.lines() {
+            let range = line.split_whitespace().next().unwrap_or("");
+            if let Some((start, end)) = range.split_once('-') {
+                if let (Ok(start), Ok(end)) = (usize::from_str_radix(start, 16),
+                                               usize::from_str_radix(end, 16)) {
+                    inside = start <= addr && addr < end;
+                    continue;
+                }
+            }
+            if inside && line.starts_with("Rss:") {
+                return line.split_whitespace().nth(1).unwrap().parse().unwrap();
             }
         }
-    };
+        panic!("mapping not found")
+    }
+
+    // 8 Gbit = 1 GiB of bits, in pages of the base size: a transparent huge page would make a
+    // single probe resident 2 MB.
+    let mut bloom = BloomFilter::new_lazy(FilterBuilder::from_size_and_hashes(1 << 33, 7)).unwrap();
+    if let crate::vec::Storage::Mmap(map) = &bloom.bit_set.storage {
+        map.advise(memmap2::Advice::NoHugePage).unwrap();
+    }
+    let ptr = bloom.get_u8_array().as_ptr();
+    assert!(resident_kb(ptr) < 1024);
+    assert!(!bloom.contains(b"hello"));
+
+    for x in 0..100u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    for x in 0..100u64 {
+        assert!(bloom.contains(&x.to_le_bytes()));
+    }
+    // at most one page per probe, well under the mapping even with 64 kB pages.
+    let resident = resident_kb(ptr);
+    assert!(resident > 0 && resident < 64 * 1024, "{resident}");
 }
 
-get_array!(get_u8_array, u8, 8);
-get_array!(get_u16_array, u16, 4);
-get_array!(get_u32_array, u32, 2);
-get_array!(get_u64_array, u64, 1);
+#[test]
+#[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+fn bloom_mmap_flush_test() {
+    let path = std::env::temp_dir().join(format!("fastbloom_mmap_flush_{}.bloom", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut bloom = BloomFilter::create_mmap(path, FilterBuilder::new(10_000, 0.01)).unwrap();
+    let (size, hashes) = (bloom.config.size, bloom.hashes());
+    for x in 0..1_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    bloom.flush().unwrap();
+    drop(bloom);
+
+    assert_eq!(fs::metadata(path).unwrap().len(), size / 8);
+    let mut bloom = BloomFilter::open_mmap(path, hashes).unwrap();
+    assert_eq!(bloom.config.size, size);
+    for x in 0..1_000u64 {
+        assert!(bloom.contains(&x.to_le_bytes()));
+    }
+    bloom.add(b"hello");
+    bloom.flush().unwrap();
+    drop(bloom);
+
+    // the file is the plain byte vector read by from_file.
+    let bloom = BloomFilter::from_file(path, hashes);
+    assert!(bloom.contains(b"hello"));
+    assert!(bloom.contains(&0u64.to_le_bytes()));
+    // heap filters have nothing to flush.
+    assert!(bloom.flush().is_ok());
+    fs::remove_file(path).unwrap();
+}
 
-impl CountingBloomFilter {
-    pub fn new(mut config: FilterBuilder) -> Self {
-        config.complete();
-        #[cfg(target_pointer_width = "64")]
-            let counting_vec = CountingVec::new((config.size >> 4) as usize);
-        #[cfg(target_pointer_width = "32")]
-            let counting_vec = CountingVec::new((config.size >> 3) as usize);
-        CountingBloomFilter { config, counting_vec }
+#[test] 
+fn bloom_large() {
+    let mut builder =
+        FilterBuilder::new(1_000_000_000, 0.0001);
+    let mut bloom = builder.build_bloom_filter();
+
+    bloom.add(b"hello");
+    assert_eq!(bloom.contains(b"hello"), true);
+
+    let bloom = BloomFilter::from_u8_array(&bloom.to_vec(), bloom.hashes());
+
+    assert_eq!(bloom.contains(b"hello"), true);
+
+}
+
+#[test]
+fn bloom_save_and_load_file_hashes() {
+    {
+        let mut builder = FilterBuilder::new(1_000_000_000, 0.0001);
+        let mut bloom = builder.build_bloom_filter();
+
+        bloom.add(b"hello");
+        assert_eq!(bloom.contains(b"hello"), true);
+        bloom.save_to_file_with_hashes("hello.bloom");
+    }
+    
+
+    let bloom2 = BloomFilter::from_file_with_hashes("hello.bloom");
+    fs::remove_file("hello.bloom").unwrap();
+
+    assert_eq!(bloom2.contains(b"hello"), true);
+    assert_eq!(bloom2.contains(b"world"), false);
+
+}
+
+#[test]
+fn bloom_save_and_load_file() {
+    let mut hashes = 0;
+    {
+        let mut builder = FilterBuilder::new(1_000_000_000, 0.0001);
+        let mut bloom = builder.build_bloom_filter();
+
+        bloom.add(b"hello");
+        assert_eq!(bloom.contains(b"hello"), true);
+
+        hashes = bloom.hashes();
+        
+        bloom.save_to_file("no_hashes.bloom");
+    }
+
+    let bloom2 = BloomFilter::from_file("no_hashes.bloom", hashes);
+    fs::remove_file("no_hashes.bloom").unwrap();
+
+    assert_eq!(bloom2.contains(b"hello"), true);
+    assert_eq!(bloom2.contains(b"world"), false);
+    
+}
+
+
+#[test]
+fn counting_bloom_test() {
+    let mut builder =
+        FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.build_counting_bloom_filter();
+
+    bloom.add(b"hello");
+
+    assert_eq!(bloom.contains(b"hello"), true);
+
+    bloom.remove(b"hello");
+    assert_eq!(bloom.contains(b"hello"), false);
+}
+
+#[test]
+fn counting_bloom_repeat_test() {
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    // enable_repeat_insert is true
+    builder.enable_repeat_insert(true);
+    let mut cbf = builder.build_counting_bloom_filter();
+    cbf.add(b"hello"); // modify underlying vector counter.
+    cbf.add(b"hello"); // modify underlying vector counter.
+    assert_eq!(cbf.contains(b"hello"), true);
+    cbf.remove(b"hello");
+    assert_eq!(cbf.contains(b"hello"), true);
+    cbf.remove(b"hello");
+    assert_eq!(cbf.contains(b"hello"), false);
+
+    // enable_repeat_insert is false
+    builder.enable_repeat_insert(false);
+    let mut cbf = builder.build_counting_bloom_filter();
+    cbf.add(b"hello"); // modify underlying vector counter.
+    cbf.add(b"hello"); // not modify underlying vector counter because b"hello" has been added.
+    assert_eq!(cbf.contains(b"hello"), true);
+    cbf.remove(b"hello");
+    assert_eq!(cbf.contains(b"hello"), false);
+}
+
+#[test]
+fn counting_bloom_from_test() {
+    let mut builder = FilterBuilder::new(10_000_000, 0.01);
+    let mut cbf = builder.build_counting_bloom_filter();
+
+    cbf.add(b"hello");
+    cbf.add(b"hello");
+
+    let mut cbf_copy = CountingBloomFilter::from_u8_array(cbf.get_u8_array(), builder.hashes, true);
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), false);
+
+    let mut cbf_copy = CountingBloomFilter::from_u16_array(cbf.get_u16_array(), builder.hashes, true);
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), false);
+
+    let mut cbf_copy = CountingBloomFilter::from_u32_array(cbf.get_u32_array(), builder.hashes, true);
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), true);
+    cbf_copy.remove(b"hello");
+    assert_eq!(cbf_copy.contains(b"hello"), false);
+
+    #[cfg(target_pointer_width = "64")]{
+        let mut cbf_copy = CountingBloomFilter::from_u64_array(cbf.get_u64_array(), builder.hashes, true);
+        assert_eq!(cbf_copy.contains(b"hello"), true);
+        cbf_copy.remove(b"hello");
+        assert_eq!(cbf_copy.contains(b"hello"), true);
+        cbf_copy.remove(b"hello");
+        assert_eq!(cbf_copy.contains(b"hello"), false);
+    }
+}
+
+#[test]
+fn counting_bloom_hash_indices_test() {
+    let mut builder =
+        FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.build_counting_bloom_filter();
+
+    bloom.add(b"hello");
+
+    assert_eq!(bloom.contains(b"hello"), true);
+    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"hello")), true);
+    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"world")), false);
+
+
+    bloom.remove(b"hello");
+    assert_eq!(bloom.contains(b"hello"), false);
+    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"hello")), false);
+}
+
+#[test]
+fn counting_bloom_nonzero_counters_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    assert_eq!(bloom.nonzero_counters().count(), 0);
+
+    let mut expected = std::collections::BTreeSet::new();
+    for key in [b"hello".as_ref(), b"world", b"yankun"] {
+        bloom.add(key);
+        expected.extend(bloom.get_hash_indices(key));
+    }
+    bloom.add(b"hello");
+
+    let counters: Vec<(u64, usize)> = bloom.nonzero_counters().collect();
+    let indices: std::collections::BTreeSet<u64> = counters.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indices, expected);
+    for (index, count) in counters {
+        assert_eq!(count, bloom.counter_at(index));
+    }
+}
+
+#[test]
+fn counting_bloom_total_insertions_test() {
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    builder.enable_repeat_insert(true);
+    let mut bloom = builder.build_counting_bloom_filter();
+    assert_eq!(bloom.total_insertions(), 0);
+    for _ in 0..3 {
+        for x in 0..1_000u64 {
+            bloom.add(&x.to_le_bytes());
+        }
     }
+    let total = bloom.total_insertions();
+    assert!(total > 2_970 && total <= 3_000, "{total}");
+    for x in 0..1_000u64 {
+        bloom.remove(&x.to_le_bytes());
+    }
+    let total = bloom.total_insertions();
+    assert!(total > 1_980 && total <= 2_000, "{total}");
+}
+
+#[test]
+fn counting_bloom_estimate_count() {
+    let mut builder =
+        FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.build_counting_bloom_filter();
+
+    bloom.add(b"hello");
+    bloom.add(b"world");
+
+    assert_eq!(bloom.estimate_count(b"hello"), 1);
+    let indices = bloom.get_hash_indices(b"hello");
 
-    pub(crate) fn set_counting_vec(&mut self, counting_vec: CountingVec) {
-        assert_eq!(self.config.size, counting_vec.counters as u64);
-        self.counting_vec = counting_vec
+    for index in indices {
+        assert_eq!(bloom.counter_at(index), 1)
     }
 
-    /// Checks if two Counting Bloom filters are compatible, i.e. have compatible parameters (hash
-    /// function, size, etc.)
-    fn compatible(&self, other: &BloomFilter) -> bool {
-        self.config.is_compatible_to(&other.config)
+    assert_eq!(bloom.estimate_count(b"world"), 1);
+    for index in bloom.get_hash_indices(b"world") {
+        assert!(bloom.counter_at(index) <= 2);
     }
+}
 
-    /// Returns the configuration/builder of the Bloom filter.
-    /// # Examples
-    ///
-    /// ```rust
-    /// use fastbloom_rs::{BloomFilter, FilterBuilder};
-    ///
-    /// let bloom = FilterBuilder::new(100_000_000, 0.01).build_bloom_filter();
-    /// let builder = bloom.config();
-    /// ```
-    ///
-    pub fn config(&self) -> FilterBuilder {
-        self.config.clone()
+#[test]
+#[cfg(feature = "serde")]
+fn bloom_hash_seeds_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_seeds(42, 4242);
+    let mut bloom = builder.build_bloom_filter();
+    let default = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
     }
+    assert_ne!(bloom.get_hash_indices(b"hello"), default.get_hash_indices(b"hello"));
+    assert!(!bloom.config().is_compatible_to(&default.config()));
+
+    let json = serde_json::to_string(&bloom).unwrap();
+    let loaded: BloomFilter = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.config().hash_seeds, (42, 4242));
+    for x in 0..1000u64 {
+        assert!(loaded.contains(&x.to_le_bytes()));
+    }
+    assert_eq!(loaded.get_hash_indices(b"hello"), bloom.get_hash_indices(b"hello"));
+
+    let mut cbf = builder.build_counting_bloom_filter();
+    cbf.add(b"hello");
+    assert!(cbf.contains(b"hello"));
+    assert_eq!(cbf.get_hash_indices(b"hello"), bloom.get_hash_indices(b"hello"));
 }
 
-macro_rules! from_array {
-    ($name:ident, $native:ty, $num:expr) => {
-        impl CountingBloomFilter {
-            pub fn $name(array: &[$native], hashes: u32, enable_repeat_insert:bool) -> Self {
-                let mut config =
-                    FilterBuilder::from_size_and_hashes((array.len() * $num) as u64, hashes);
-                config.enable_repeat_insert(enable_repeat_insert);
-                config.complete();
-                #[cfg(target_pointer_width = "64")]
-                    let mut counting_vec = CountingVec::new((config.size >> 4) as usize);
-                #[cfg(target_pointer_width = "32")]
-                    let mut counting_vec = CountingVec::new((config.size >> 3) as usize);
+#[test]
+fn counting_bloom_portable_bytes_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    for x in 0..1000u64 {
+        for _ in 0..x % 5 {
+            cbf.add(&x.to_le_bytes());
+        }
+    }
+    let bytes = cbf.to_portable_bytes();
+    assert_eq!(bytes.len(), 30 + cbf.config().size as usize / 2);
+
+    let loaded = CountingBloomFilter::from_portable_bytes(&bytes).unwrap();
+    assert_eq!(loaded.hashes(), cbf.hashes());
+    assert_eq!(loaded.config().size, cbf.config().size);
+    assert!(loaded.config().enable_repeat_insert);
+    for x in 0..2000u64 {
+        assert_eq!(loaded.estimate_count(&x.to_le_bytes()), cbf.estimate_count(&x.to_le_bytes()));
+    }
+    assert_eq!(loaded.to_portable_bytes(), bytes);
 
-                let ptr = array.as_ptr() as *const usize;
-                #[cfg(target_pointer_width = "64")]
-                    let usize_array = slice_from_raw_parts(ptr, (config.size >> 4) as usize);
-                #[cfg(target_pointer_width = "32")]
-                    let usize_array = slice_from_raw_parts(ptr, (config.size >> 3) as usize);
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.enable_repeat_insert(false);
+    let loaded = CountingBloomFilter::from_portable_bytes(
+        &builder.build_counting_bloom_filter().to_portable_bytes()).unwrap();
+    assert!(!loaded.config().enable_repeat_insert);
+
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_seeds(7, 99);
+    let mut seeded = builder.build_counting_bloom_filter();
+    for x in 0..1000u64 {
+        seeded.add(&x.to_le_bytes());
+    }
+    let loaded = CountingBloomFilter::from_portable_bytes(&seeded.to_portable_bytes()).unwrap();
+    assert_eq!(loaded.config().hash_seeds, (7, 99));
+    assert!((0..1000u64).all(|x| loaded.contains(&x.to_le_bytes())));
+    for x in 0..2000u64 {
+        assert_eq!(loaded.estimate_count(&x.to_le_bytes()), seeded.estimate_count(&x.to_le_bytes()));
+    }
 
-                counting_vec.storage.copy_from_slice(unsafe { &*usize_array });
+    assert_eq!(CountingBloomFilter::from_portable_bytes(&bytes[..10]).unwrap_err(),
+               FilterError::LengthMismatch { expected: 30, found: 10 });
+    assert_eq!(CountingBloomFilter::from_portable_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+               FilterError::LengthMismatch { expected: bytes.len(), found: bytes.len() - 1 });
+    let mut invalid = bytes.clone();
+    invalid[5] = 8;
+    assert_eq!(CountingBloomFilter::from_portable_bytes(&invalid).unwrap_err(),
+               FilterError::UnsupportedCounterWidth(8));
+}
 
-                CountingBloomFilter { config, counting_vec }
-            }
-        }
-    };
+#[test]
+fn bloom_filter_present_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.001).build_bloom_filter();
+    let elements: Vec<[u8; 8]> = (0..2000u64).map(|x| x.to_le_bytes()).collect();
+    for element in elements.iter().step_by(3) {
+        bloom.add(element);
+    }
+    let (present, hits) = bloom.filter_present(&elements);
+    let expected: Vec<usize> = (0..elements.len()).filter(|i| bloom.contains(&elements[*i])).collect();
+    assert_eq!(present, expected);
+    assert_eq!(hits, present.len());
+    assert!((0..elements.len()).step_by(3).all(|i| present.contains(&i)));
+
+    let empty: &[&[u8]] = &[];
+    assert_eq!(bloom.filter_present(empty), (vec![], 0));
 }
 
-from_array!(from_u8_array, u8, 2);
-from_array!(from_u16_array, u16, 4);
-from_array!(from_u32_array, u32, 8);
-from_array!(from_u64_array, u64, 16);
+#[test]
+#[cfg(all(feature = "getrandom", feature = "serde"))]
+fn bloom_randomize_seed_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.randomize_seed();
+    let mut bloom = builder.build_bloom_filter();
+    let mut other_builder = FilterBuilder::new(10_000, 0.01);
+    other_builder.randomize_seed();
+    let other = other_builder.build_bloom_filter();
+    assert_ne!(builder.seed(), other_builder.seed());
+    assert_eq!(builder.hash_seeds, (builder.seed(), builder.seed().wrapping_add(32)));
+    assert_ne!(bloom.get_hash_indices(b"hello"), other.get_hash_indices(b"hello"));
 
-impl CountingBloomFilter {
-    /// Get the estimate count for element in this counting bloom filter.
-    /// See: https://github.com/yankun1992/fastbloom/issues/3
-    pub fn estimate_count(&self, element: &[u8]) -> usize {
-        let m = self.config.size;
-        let hash1 = xxh3_64_with_seed(element, 0) % m;
-        let hash2 = xxh3_64_with_seed(element, 32) % m;
+    bloom.add(b"hello");
+    let loaded: BloomFilter = serde_json::from_str(&serde_json::to_string(&bloom).unwrap()).unwrap();
+    assert_eq!(loaded.config().seed(), builder.seed());
+    assert!(loaded.contains(b"hello"));
+    assert_eq!(loaded.get_hash_indices(b"hello"), bloom.get_hash_indices(b"hello"));
+}
 
-        let mut res = self.counting_vec.get(hash1 as usize);
-        if res == 0 { return 0; }
+#[test]
+fn bloom_with_test() {
+    let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter()
+        .with(b"a")
+        .with(b"b")
+        .with_all(vec![b"c".to_vec(), b"d".to_vec()])
+        .with(b"e");
+    for element in [b"a", b"b", b"c", b"d", b"e"] {
+        assert!(bloom.contains(element));
+    }
+    assert!(!bloom.contains(b"f"));
+}
 
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            let count = self.counting_vec.get(mo);
-            if count == 0 { return 0; } else { res = min(count, res) }
-        }
+#[test]
+fn bloom_add_str_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add_str("héllo");
+    assert!(bloom.contains_str("héllo"));
+    assert!(bloom.contains("héllo".as_bytes()));
+    assert!(!bloom.contains_str("hello"));
+
+    let mut bytes = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bytes.add("héllo".as_bytes());
+    assert_eq!(bytes.get_u8_array(), bloom.get_u8_array());
+}
 
-        res
+#[test]
+fn bloom_export_import_test() {
+    let mut sparse = FilterBuilder::new(100_000, 0.01).build_bloom_filter();
+    for x in 0..1000u64 {
+        sparse.add(&x.to_le_bytes());
+    }
+    let mut dense = FilterBuilder::new(1000, 0.01).build_bloom_filter();
+    for x in 0..1000u64 {
+        dense.add(&x.to_le_bytes());
     }
 
-    /// Get the underlying counter at index.
-    pub fn counter_at(&self, index: u64) -> usize {
-        self.counting_vec.get(index as usize)
+    for bloom in [&sparse, &dense] {
+        for format in [ExportFormat::Raw, ExportFormat::SparseIndices, ExportFormat::Rle] {
+            let bytes = bloom.export(format);
+            let imported = BloomFilter::import(&bytes, bloom.hashes()).unwrap();
+            assert_eq!(imported.get_u8_array(), bloom.get_u8_array(), "{format:?}");
+            assert_eq!(imported.hashes(), bloom.hashes());
+        }
     }
+    assert!(sparse.export(ExportFormat::SparseIndices).len() < sparse.export(ExportFormat::Raw).len());
+    assert_eq!(dense.export(ExportFormat::Raw).len(), dense.get_u8_array().len() + 18);
+    assert!((0..1000u64).all(|x| BloomFilter::import(&dense.export(ExportFormat::Raw), dense.hashes())
+        .unwrap().contains(&x.to_le_bytes())));
+
+    let import = |tag: u8, body: &[u8]| {
+        let mut bytes = vec![tag, 0];
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&32u64.to_le_bytes());
+        bytes.extend_from_slice(body);
+        BloomFilter::import(&bytes, 4)
+    };
+    assert_eq!(BloomFilter::import(&[], 4).unwrap_err(), FilterError::InvalidEncoding("missing format byte"));
+    assert_eq!(BloomFilter::import(&[7, 0], 4).unwrap_err(), FilterError::UnknownExportFormat(7));
+    assert_eq!(BloomFilter::import(&[1, 0], 4).unwrap_err(),
+               FilterError::InvalidEncoding("missing hash width and seeds"));
+    assert_eq!(import(1, &[8, 64]).unwrap_err(), FilterError::InvalidEncoding("bit index out of range"));
+    assert_eq!(import(2, &[8, 3]).unwrap_err(), FilterError::InvalidEncoding("runs shorter than the filter"));
+    assert_eq!(import(1, &[0x80]).unwrap_err(), FilterError::InvalidEncoding("truncated varint"));
 }
 
-impl Membership for CountingBloomFilter {
-    fn add(&mut self, element: &[u8]) {
-        let m = self.config.size;
-        // let hash1 = (murmur3_x64_128(element, 0) % m) as u64;
-        // let hash2 = (murmur3_x64_128(element, 32) % m) as u64;
-        let hash1 = xxh3_64_with_seed(element, 0) % m;
-        let hash2 = xxh3_64_with_seed(element, 32) % m;
+#[test]
+fn bloom_import_untrusted_test() {
+    let import = |tag: u8, body: &[u8]| {
+        let mut bytes = vec![tag, 0];
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(body);
+        BloomFilter::import(&bytes, 4)
+    };
+    // a length whose bit count overflows, and one of 2^60 bytes, in a few bytes of input.
+    let mut overflow = Vec::new();
+    write_varint(&mut overflow, u64::MAX / 4);
+    let mut huge = Vec::new();
+    write_varint(&mut huge, 1 << 60);
+    for tag in [1, 2] {
+        assert_eq!(import(tag, &overflow).unwrap_err(), FilterError::InvalidEncoding("length overflows"));
+    }
+    assert_eq!(import(1, &huge).unwrap_err(), FilterError::InvalidEncoding("filter too large to allocate"));
+    let mut runs = huge.clone();
+    write_varint(&mut runs, 1 << 63);
+    assert_eq!(import(2, &runs).unwrap_err(), FilterError::InvalidEncoding("filter too large to allocate"));
+
+    // a nearly empty GiB filter is legitimate and allocated zeroed rather than written.
+    if cfg!(target_pointer_width = "64") {
+        let mut sparse = Vec::new();
+        write_varint(&mut sparse, 1 << 30);
+        write_varint(&mut sparse, 12345);
+        let imported = import(1, &sparse).unwrap();
+        assert_eq!(imported.size_bytes(), 1 << 30);
+        assert!(imported.bit_set.get(12345));
+    }
 
-        let mut res = self.counting_vec.get(hash1 as usize) > 0;
-        // let m = self.config.size;
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            res = res && (self.counting_vec.get(mo) > 0);
+    // every truncation and single byte corruption of valid exports errors or decodes, never panics.
+    let mut bloom = FilterBuilder::new(100, 0.01).build_bloom_filter();
+    for x in 0..50u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    for format in [ExportFormat::Raw, ExportFormat::SparseIndices, ExportFormat::Rle] {
+        let bytes = bloom.export(format);
+        for end in 0..bytes.len() {
+            let _ = BloomFilter::import(&bytes[..end], 4);
         }
-
-        // contains and not enable repeat insert
-        if res && !self.config.enable_repeat_insert {
-            return;
+        for i in 0..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+                let _ = BloomFilter::import(&corrupt, 4);
+            }
         }
-
-        // insert
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            self.counting_vec.increment(mo);
-        };
-        self.counting_vec.increment(hash1 as usize);
     }
+}
 
-    #[inline]
-    fn contains(&self, element: &[u8]) -> bool {
-        let m = self.config.size;
-        // let hash1 = (murmur3_x64_128(element, 0) % m) as u64;
-        // let hash2 = (murmur3_x64_128(element, 32) % m) as u64;
-        let hash1 = xxh3_64_with_seed(element, 0) % m;
-        let hash2 = xxh3_64_with_seed(element, 32) % m;
-
-        let mut res = self.counting_vec.get(hash1 as usize) > 0;
-        if !res { return false; }
-        // let m = self.config.size;
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            res = res && (self.counting_vec.get(mo) > 0);
-            if !res { return false; }
+#[test]
+fn bloom_export_hash_config_test() {
+    let mut seeded = FilterBuilder::new(10_000, 0.01);
+    seeded.hash_seeds(7, 99);
+    let mut wide = FilterBuilder::new(10_000, 0.01);
+    wide.hash_width(HashWidth::Bits128);
+    for mut builder in [seeded, wide] {
+        let mut bloom = builder.build_bloom_filter();
+        for x in 0..1000u64 {
+            bloom.add(&x.to_le_bytes());
+        }
+        for format in [ExportFormat::Raw, ExportFormat::SparseIndices, ExportFormat::Rle] {
+            let imported = BloomFilter::import(&bloom.export(format), bloom.hashes()).unwrap();
+            assert_eq!(imported.config.hash_width, bloom.config.hash_width);
+            assert_eq!(imported.config.hash_seeds, bloom.config.hash_seeds);
+            assert!((0..1000u64).all(|x| imported.contains(&x.to_le_bytes())));
         }
-        res
     }
+}
 
-    fn get_hash_indices(&self, element: &[u8]) -> Vec<u64> {
-        let m = self.config.size;
-        let mut res = Vec::<u64>::with_capacity(self.config.size as usize);
-        // let hash1 = (murmur3_x64_128(element, 0) % m) as u64;
-        // let hash2 = (murmur3_x64_128(element, 32) % m) as u64;
-        let hash1 = xxh3_64_with_seed(element, 0) % m;
-        let hash2 = xxh3_64_with_seed(element, 32) % m;
-        res.push(hash1);
-        // let m = self.config.size;
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            res.push(mo as u64);
+#[test]
+fn counting_bloom_min_count_hash_indices_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    for x in 0..1000u64 {
+        for _ in 0..x % 4 {
+            cbf.add(&x.to_le_bytes());
         }
-        res
     }
+    for x in 0..2000u64 {
+        let element = x.to_le_bytes();
+        assert_eq!(cbf.min_count_hash_indices(&cbf.get_hash_indices(&element)), cbf.estimate_count(&element));
+    }
+    assert_eq!(cbf.min_count_hash_indices(&[]), 0);
+}
 
-    fn contains_hash_indices(&self, indices: &Vec<u64>) -> bool {
-        for x in indices.iter() {
-            let index = *x;
-            if self.counting_vec.get(index as usize) == 0 { return false; }
+#[test]
+fn bloom_union_bytes_many_test() {
+    let builder = FilterBuilder::new(100_000, 0.01);
+    let shards: Vec<(Vec<u8>, u32)> = (0..100u64).map(|shard| {
+        let mut bloom = builder.clone().build_bloom_filter();
+        for x in shard * 100..(shard + 1) * 100 {
+            bloom.add(&x.to_le_bytes());
         }
-        true
+        (Vec::from(&bloom), bloom.hashes())
+    }).collect();
+
+    let mut acc = builder.clone().build_bloom_filter();
+    assert_eq!(acc.union_bytes_many(shards.clone()), Ok(100));
+    assert!((0..10_000u64).all(|x| acc.contains(&x.to_le_bytes())));
+
+    let mut acc = builder.clone().build_bloom_filter();
+    let mut invalid = shards[..3].to_vec();
+    invalid[1].1 += 1;
+    assert_eq!(acc.union_bytes_many(invalid.clone()),
+               Err(FilterError::HashesMismatch { expected: acc.hashes(), found: acc.hashes() + 1 }));
+    assert!((0..100u64).all(|x| acc.contains(&x.to_le_bytes())));
+    assert!(!(200..300u64).all(|x| acc.contains(&x.to_le_bytes())));
+}
+
+#[test]
+fn bloom_check_invariants_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add(b"hello");
+    assert_eq!(bloom.check_invariants(), Ok(()));
+    assert_eq!(BloomFilter::from_u8_array(&[0xff; 8], 2).check_invariants(), Ok(()));
+
+    let mismatched = BloomFilter::from_parts(
+        FilterBuilder::from_size_and_hashes(256, 4),
+        BloomBitVec::new(2),
+    );
+    assert!(mismatched.check_invariants().is_err());
+
+    let no_hashes = BloomFilter::from_parts(
+        FilterBuilder::from_size_and_hashes(128, 0),
+        BloomBitVec::new(128 / usize::BITS as usize),
+    );
+    assert!(no_hashes.check_invariants().is_err());
+
+    // whole bytes are not enough, the size must be whole 64 bit words.
+    let mut bit_set = BloomBitVec::new(1);
+    bit_set.nbits = 24;
+    let partial = BloomFilter::from_parts(FilterBuilder::from_size_and_hashes(24, 2), bit_set);
+    assert_eq!(partial.check_invariants(),
+               Err("size must be a positive multiple of 64 bits, found 24".to_string()));
+}
+
+#[test]
+fn size_bytes_test() {
+    for (n, p) in [(1, 0.5), (1000, 0.01), (10_000, 0.001), (123_457, 1e-6)] {
+        let bloom = FilterBuilder::new(n, p).build_bloom_filter();
+        assert_eq!(bloom.size_bytes(), bloom.get_u8_array().len() as u64);
+        assert_eq!(bloom.size_bits(), bloom.config().size);
+
+        let cbf = FilterBuilder::new(n, p).build_counting_bloom_filter();
+        assert_eq!(cbf.size_bytes(), cbf.get_u8_array().len() as u64);
+        assert_eq!(cbf.size_counters(), cbf.config().size);
     }
+    assert_eq!(BloomFilter::from_u8_array(&[0; 24], 2).size_bytes(), 24);
+}
 
-    fn clear(&mut self) {
-        self.counting_vec.clear()
+#[test]
+fn bloom_union_resample_test() {
+    let mut small = FilterBuilder::from_size_and_hashes(1 << 14, 5).build_bloom_filter();
+    let mut large = FilterBuilder::from_size_and_hashes(1 << 15, 5).build_bloom_filter();
+    for x in 0..1000u64 {
+        small.add(&x.to_le_bytes());
+        large.add(&(x + 1000).to_le_bytes());
     }
+
+    let mut tiled = large.clone();
+    assert!(tiled.union_resample(&small));
+    assert!((0..2000u64).all(|x| tiled.contains(&x.to_le_bytes())));
+    assert_eq!(tiled.config().size, 1 << 15);
+
+    let mut folded = small.clone();
+    assert!(folded.union_resample(&large));
+    assert_eq!(folded.get_u8_array(), {
+        let mut expected = large.fold(1).unwrap();
+        expected.union(&small);
+        expected
+    }.get_u8_array());
+
+    // a size tiled a number of times that is not a power of two.
+    let mut word = FilterBuilder::from_size_and_hashes(64, 5).build_bloom_filter();
+    for x in 0..5u64 {
+        word.add(&x.to_le_bytes());
+    }
+    let mut tiled = FilterBuilder::from_size_and_hashes(192, 5).build_bloom_filter();
+    assert!(tiled.union_resample(&word));
+    assert!((0..5u64).all(|x| tiled.contains(&x.to_le_bytes())));
+
+    let other = FilterBuilder::from_size_and_hashes(3 << 13, 5).build_bloom_filter();
+    assert!(!large.union_resample(&other));
+    let other = FilterBuilder::from_size_and_hashes(1 << 14, 4).build_bloom_filter();
+    assert!(!large.union_resample(&other));
 }
 
-impl Deletable for CountingBloomFilter {
-    fn remove(&mut self, element: &[u8]) {
-        let m = self.config.size;
-        // let hash1 = (murmur3_x64_128(element, 0) % m) as u64;
-        // let hash2 = (murmur3_x64_128(element, 32) % m) as u64;
-        let hash1 = xxh3_64_with_seed(element, 0) % m;
-        let hash2 = xxh3_64_with_seed(element, 32) % m;
+#[test]
+fn counting_bloom_add_n_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    let mut repeated = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    cbf.add_n(b"hello", 5);
+    for _ in 0..5 {
+        repeated.add(b"hello");
+    }
+    assert_eq!(cbf.estimate_count(b"hello"), 5);
+    assert_eq!(cbf.get_u8_array(), repeated.get_u8_array());
 
-        let mut res = self.counting_vec.get(hash1 as usize) > 0;
-        // let m = self.config.size;
-        for i in 1..self.config.hashes as u64 {
-            let mo = ((hash1 + i * hash2) % m) as usize;
-            res = res && (self.counting_vec.get(mo) > 0);
-        }
+    cbf.add_n(b"world", 100);
+    repeated.add_n(b"world", 0);
+    assert_eq!(repeated.estimate_count(b"world"), 0);
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(cbf.estimate_count(b"world"), 15);
+
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.enable_repeat_insert(false);
+    let mut cbf = builder.build_counting_bloom_filter();
+    cbf.add_n(b"hello", 5);
+    assert_eq!(cbf.estimate_count(b"hello"), 1);
+}
+
+#[test]
+fn bloom_u8_array_with_header_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_seeds(7, 77);
+    let mut bloom = builder.build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let bytes = bloom.to_u8_array_with_header();
+    assert_eq!(bytes.len(), 25 + bloom.get_u8_array().len());
+
+    let loaded = BloomFilter::from_u8_array_with_header(&bytes).unwrap();
+    assert_eq!(loaded.hashes(), bloom.hashes());
+    assert_eq!(loaded.config().hash_seeds, (7, 77));
+    assert_eq!(loaded.get_u8_array(), bloom.get_u8_array());
+    assert!((0..1000u64).all(|x| loaded.contains(&x.to_le_bytes())));
+
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_width(HashWidth::Bits128);
+    let mut wide = builder.build_bloom_filter();
+    wide.add(b"hello");
+    let loaded = BloomFilter::from_u8_array_with_header(&wide.to_u8_array_with_header()).unwrap();
+    assert_eq!(loaded.config().hash_width, HashWidth::Bits128);
+    assert!(loaded.contains(b"hello"));
+
+    assert_eq!(BloomFilter::from_u8_array_with_header(bloom.get_u8_array()).unwrap_err(),
+               FilterError::InvalidEncoding("missing header"));
+    assert_eq!(BloomFilter::from_u8_array_with_header(&bytes[..25]).unwrap_err(), FilterError::ZeroSize);
+}
 
-        // contains
-        if res {
-            for i in 1..self.config.hashes as u64 {
-                let mo = ((hash1 + i * hash2) % m) as usize;
-                self.counting_vec.decrement(mo);
-            };
-            self.counting_vec.decrement(hash1 as usize);
+#[test]
+fn bloom_estimate_set_cardinality_linear_counting_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(1 << 16, 4).build_bloom_filter();
+    assert_eq!(bloom.estimate_set_cardinality_linear_counting(), 0.0);
+    let mut inserted = 0u64;
+    // fill ratios from about 6% up to about 70%.
+    for n in [1_000u64, 5_000, 10_000, 20_000] {
+        while inserted < n {
+            bloom.add(&inserted.to_le_bytes());
+            inserted += 1;
         }
+        let linear = bloom.estimate_set_cardinality_linear_counting();
+        let swamidass_baldi = bloom.estimate_set_cardinality();
+        assert!((linear - n as f64).abs() / (n as f64) < 0.05, "{n} {linear}");
+        assert!((linear - swamidass_baldi).abs() / swamidass_baldi < 0.001, "{linear} {swamidass_baldi}");
     }
+    bloom.as_bytes_mut().fill(0xff);
+    assert_eq!(bloom.estimate_set_cardinality_linear_counting(), f64::INFINITY);
 }
 
-impl Hashes for CountingBloomFilter {
-    fn hashes(&self) -> u32 {
-        self.config.hashes
+#[test]
+fn add_all_prefixes_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add_all_prefixes(b"hello");
+    for prefix in ["h", "he", "hel", "hell", "hello"] {
+        assert!(bloom.contains_str(prefix));
     }
+    assert!(!bloom.contains_str("hello!"));
+    assert!(!bloom.contains(b""));
+
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    cbf.add_all_prefixes(b"hello");
+    cbf.add_all_prefixes(b"help");
+    assert_eq!(cbf.estimate_count(b"hel"), 2);
+    cbf.remove_all_prefixes(b"hello");
+    assert!(cbf.contains(b"help"));
+    assert_eq!(cbf.estimate_count(b"hel"), 1);
+    assert!(!cbf.contains(b"hello"));
 }
 
-/// A Partitioned Bloom Filter is a variation of a classic Bloom Filter.
-///
-/// This filter works by partitioning the M-sized bit array into k slices of size `m = M/k` bits,
-/// `k = nb of hash functions` in the filter. Each hash function produces an index over `m` for its
-/// respective slice. Thus, each element is described by exactly `k` bits, meaning the distribution
-/// of false positives is uniform across all elements.
-///
-/// Be careful, as a Partitioned Bloom Filter have much higher collison risks that a classic
-/// Bloom Filter on small sets of data.
-///
-/// **Reference**: Chang, F., Feng, W. C., & Li, K. (2004, March). Approximate caches for packet
-/// classification. In INFOCOM 2004. Twenty-third AnnualJoint Conference of the IEEE Computer and
-/// Communications Societies (Vol. 4, pp. 2196-2207). IEEE.
-/// [Full text article](http://citeseerx.ist.psu.edu/viewdoc/download?doi=10.1.1.153.6902&rep=rep1&type=pdf)
-#[derive(Clone)]
-#[derive(Debug)]
-pub(crate) struct PartitionedBloomFilter {}
+#[test]
+fn bloom_bit_accessors_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let last = bloom.config().size - 1;
+    for index in [0, 63, 64, last] {
+        assert!(!bloom.get_bit(index));
+        bloom.set_bit(index);
+        assert!(bloom.get_bit(index));
+    }
+    assert_eq!(bloom.get_u64_array().iter().map(|w| w.count_ones()).sum::<u32>(), 4);
+    bloom.clear_bit(63);
+    assert!(!bloom.get_bit(63));
+    assert!(bloom.get_bit(64));
 
-impl PartitionedBloomFilter {}
+    bloom.clear();
+    bloom.add(b"hello");
+    for index in bloom.get_hash_indices(b"hello") {
+        assert!(bloom.get_bit(index));
+    }
+    bloom.clear_bit(bloom.get_hash_indices(b"hello")[0]);
+    assert!(!bloom.contains(b"hello"));
+}
 
-/// A Scalable Bloom Filter is a variant of Bloom Filters that can adapt dynamically to the number
-/// of elements stored, while assuring a maximum false positive probability.
-///
-/// **Reference**: ALMEIDA, Paulo Sérgio, BAQUERO, Carlos, PREGUIÇA, Nuno, et al. Scalable bloom
-/// filters. Information Processing Letters, 2007, vol. 101, no 6, p. 255-261.
-/// [Full text article](https://citeseerx.ist.psu.edu/viewdoc/download?doi=10.1.1.725.390&rep=rep1&type=pdf)
-#[derive(Clone)]
-#[derive(Debug)]
-pub(crate) struct ScalableBloomFilter {}
+#[test]
+#[should_panic(expected = "index must smaller than size!")]
+fn bloom_set_bit_out_of_range_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let size = bloom.config().size;
+    bloom.set_bit(size);
+}
 
-impl ScalableBloomFilter {}
+#[test]
+fn counting_to_bloom_bytes_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    for i in 0..1000u32 {
+        cbf.add(&i.to_le_bytes());
+    }
+    cbf.remove(&0u32.to_le_bytes());
+    let bloom = BloomFilter::from_u8_array(&cbf.to_bloom_bytes(), cbf.hashes());
+    assert_eq!(bloom.config().size, cbf.config().size);
+    for i in 0..20_000u32 {
+        assert_eq!(bloom.contains(&i.to_le_bytes()), cbf.contains(&i.to_le_bytes()));
+    }
+}
 
-/// An Invertible Bloom Filters (IBLT), also called Invertible Bloom Lookup Table, is a
-/// space-efficient and probabilistic data-structure for solving the set-difference problem
-/// efficiently without the use of logs or other prior context. It computes the set difference
-/// with communication proportional to the size of the difference between the sets being compared.
-/// They can simultaneously calculate D(A−B) and D(B−A) using O(d) space. This data structure
-/// encodes sets in a fashion that is similar in spirit to Tornado codes’ construction, in that it
-/// randomly combines elements using the XOR function.
-///
-/// **Reference**: Eppstein, D., Goodrich, M. T., Uyeda, F., & Varghese, G. (2011). What's the
-/// difference?: efficient set reconciliation without prior context. ACM SIGCOMM Computer
-/// Communication Review, 41(4), 218-229.
-/// [Full text article](http://www.sysnet.ucsd.edu/sysnet/miscpapers/EppGooUye-SIGCOMM-11.pdf)
-#[derive(Clone)]
-#[derive(Debug)]
-pub(crate) struct InvertibleBloomFilter {}
+#[test]
+#[cfg(feature = "metrics")]
+fn bloom_op_stats_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    assert_eq!(bloom.op_stats(), OpStats::default());
+    for i in 0..100u32 {
+        bloom.add(&i.to_le_bytes());
+    }
+    for i in 0..250u32 {
+        bloom.contains(&i.to_le_bytes());
+    }
+    bloom.add_str("hello");
+    assert!(bloom.contains_str("hello"));
+    let stats = bloom.op_stats();
+    assert_eq!(stats.adds, 101);
+    assert_eq!(stats.contains, 251);
 
-impl InvertibleBloomFilter {}
+    let copy = bloom.clone();
+    bloom.add(b"world");
+    assert_eq!(copy.op_stats().adds, 101);
+    assert_eq!(bloom.op_stats().adds, 102);
+}
 
-#[derive(Clone)]
-#[derive(Debug)]
-pub(crate) struct GarbledBloomFilter {}
+#[test]
+#[should_panic(expected = "bytes must hold whole 64 bit words, found 12 bytes")]
+fn from_u32_array_odd_length_test() {
+    BloomFilter::from_u32_array(&[0x8000_0001u32, 0, 0x10], 3);
+}
 
-impl GarbledBloomFilter {}
+#[test]
+#[cfg(target_pointer_width = "64")]
+#[should_panic(expected = "array must fill whole usize slots!")]
+fn counting_from_u32_array_odd_length_test() {
+    CountingBloomFilter::from_u32_array(&[0u32; 3], 3, true);
+}
 
+#[test]
+fn send_sync_test() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BloomFilter>();
+    assert_send_sync::<CountingBloomFilter>();
+    assert_send_sync::<RollingBloomFilter>();
+    assert_send_sync::<CachedBloomFilter>();
+    assert_send_sync::<SmallMembership>();
+    assert_send_sync::<FrozenBloomFilter>();
+    assert_send_sync::<FilterBuilder>();
+}
 
 #[test]
-fn bloom_test() {
-    let mut builder =
-        FilterBuilder::new(10_000_000, 0.01);
-    let mut bloom = builder.build_bloom_filter();
-    println!("{:?}", bloom.config);
-    bloom.add(b"hello");
-    println!("{:?}", &bloom.bit_set.storage[0..300]);
-    assert_eq!(bloom.contains(b"hello"), true);
-    assert_eq!(bloom.contains(b"world"), false);
-    assert_eq!(bloom.add_if_not_contains(b"hello2"), false);
-    assert_eq!(bloom.contains(b"hello2"), true);
+fn bloom_suggested_hashes_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(102_400, 30).build_bloom_filter();
+    assert_eq!(bloom.suggested_hashes(), 30);
+    for x in 0..5000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    // about 102_400 * ln 2 / 5000 = 15 hashes suit 5000 elements
+    let suggested = bloom.suggested_hashes();
+    assert!(suggested < 30);
+    assert!((14..=16).contains(&suggested), "{suggested}");
+
+    let mut tuned = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..10_000u64 {
+        tuned.add(&x.to_le_bytes());
+    }
+    assert!(tuned.suggested_hashes().abs_diff(tuned.hashes()) <= 1);
+}
 
-    let storage = &bloom.bit_set.storage[0..300];
-    println!("{:?}", storage);
+#[test]
+fn counting_remove_n_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    cbf.add_n(b"key", 10);
+    cbf.remove_n(b"key", 3);
+    assert_eq!(cbf.estimate_count(b"key"), 7);
+    cbf.remove_n(b"key", 20);
+    assert_eq!(cbf.estimate_count(b"key"), 0);
+    assert!(!cbf.contains(b"key"));
+
+    cbf.add(b"other");
+    let before = cbf.get_u8_array().to_vec();
+    cbf.remove_n(b"absent", 5);
+    assert_eq!(cbf.get_u8_array(), &before[..]);
+}
 
-    #[cfg(target_pointer_width = "64")]{
-        let mut bloom2 = BloomFilter::from_u64_array(bloom.get_u64_array(), bloom.hashes());
-        assert_eq!(bloom2.compatible(&bloom), true);
-        assert_eq!(bloom2.contains(b"hello"), true);
-        assert_eq!(bloom2.contains(b"world"), false);
+#[test]
+fn bloom_present_only_in_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.001);
+    let mut a = builder.clone().build_bloom_filter();
+    let mut b = builder.build_bloom_filter();
+    let candidates: Vec<[u8; 8]> = (0..100u64).map(|x| x.to_le_bytes()).collect();
+    for (i, candidate) in candidates.iter().enumerate() {
+        match i % 3 {
+            0 => a.add(candidate),
+            1 => b.add(candidate),
+            _ => {
+                a.add(candidate);
+                b.add(candidate);
+            }
+        }
     }
+    let expected: Vec<usize> = (0..100).filter(|i| i % 3 == 0).collect();
+    assert_eq!(a.present_only_in(&b, &candidates), expected);
+    let expected: Vec<usize> = (0..100).filter(|i| i % 3 == 1).collect();
+    assert_eq!(b.present_only_in(&a, &candidates), expected);
+    assert!(a.present_only_in(&a, &candidates).is_empty());
+}
 
-    let mut bloom3 =
-        BloomFilter::from_u32_array(bloom.get_u32_array(), bloom.config.hashes);
-    assert_eq!(bloom3.compatible(&bloom), true);
-    assert_eq!(bloom3.contains(b"hello"), true);
-    assert_eq!(bloom3.contains(b"world"), false);
-
-    let u8_array = bloom.get_u8_array();
-    let mut bloom4 = BloomFilter::from_u8_array(u8_array, bloom.config.hashes);
-    println!("{:?}", &bloom4.bit_set.storage[0..300]);
-    assert_eq!(bloom4.compatible(&bloom), true);
-    assert_eq!(bloom4.contains(b"hello"), true);
-    assert_eq!(bloom4.contains(b"world"), false);
+#[test]
+#[cfg(feature = "arrow")]
+fn bloom_to_arrow_buffer_test() {
+    use arrow_buffer::ScalarBuffer;
 
-    let bloom5 = BloomFilter::from_u16_array(bloom.get_u16_array(), bloom.hashes());
-    assert_eq!(bloom5.compatible(&bloom), true);
-    assert_eq!(bloom5.contains(b"hello"), true);
-    assert_eq!(bloom5.contains(b"world"), false);
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let buffer = bloom.to_arrow_buffer();
+    let len = buffer.len();
+    // a `UInt8Array` is a `ScalarBuffer<u8>` over the buffer plus an optional null bitmap.
+    let values = ScalarBuffer::<u8>::new(buffer, 0, len);
+    assert_eq!(&values[..], &bloom.to_vec()[..]);
+    bloom.clear();
+    assert!(values.iter().any(|b| *b != 0));
+}
 
-    bloom4.add(b"hello world");
+#[test]
+fn counting_counters_u8_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    for i in 0..500u32 {
+        cbf.add(&i.to_le_bytes());
+    }
+    cbf.add_n(b"hot", 20);
+    let counters = cbf.counters_as_u8();
+    assert_eq!(counters.len() as u64, cbf.config().size);
+    for (i, count) in counters.iter().enumerate() {
+        assert_eq!(*count as usize, cbf.counter_at(i as u64));
+    }
 
-    assert_eq!(bloom.intersect(&bloom4), true);
-    assert_eq!(bloom.contains(b"hello"), true);
-    assert_eq!(bloom.contains(b"hello world"), false);
+    let rebuilt = CountingBloomFilter::from_counters_u8(&counters, cbf.hashes(), true).unwrap();
+    assert_eq!(rebuilt.get_u8_array(), cbf.get_u8_array());
+    assert_eq!(rebuilt.counters_as_u8(), counters);
+    assert_eq!(rebuilt.estimate_count(b"hot"), cbf.estimate_count(b"hot"));
 
-    bloom3.add(b"hello world");
-    bloom3.add(b"hello yankun");
+    assert_eq!(CountingBloomFilter::from_counters_u8(&counters, 0, true).unwrap_err(),
+               FilterError::ZeroHashes);
+    assert!(matches!(CountingBloomFilter::from_counters_u8(&counters[1..], 3, true),
+                     Err(FilterError::SizeNotSlotAligned { .. })));
+}
 
-    assert_eq!(bloom3.union(&bloom4), true);
-    assert_eq!(bloom3.contains(b"hello"), true);
-    assert_eq!(bloom3.contains(b"hello world"), true);
-    assert_eq!(bloom3.contains(b"hello yankun"), true);
+#[test]
+fn bloom_should_stop_inserting_test() {
+    let mut bloom = FilterBuilder::new(1_000_000, 0.01).build_bloom_filter();
+    assert!(!bloom.should_stop_inserting(0.01));
+    let mut inserted = 0u64;
+    while !bloom.should_stop_inserting(0.01) {
+        bloom.add(&inserted.to_le_bytes());
+        inserted += 1;
+    }
+    // the designed capacity reaches the target, overshooting by at most one refresh interval.
+    assert!((950_000..=1_000_000 + FPR_REFRESH_INSERTS).contains(&inserted), "{inserted}");
+    let trials = 100_000u64;
+    let false_positives = (0..trials)
+        .filter(|x| bloom.contains(&(u64::MAX - x).to_le_bytes()))
+        .count();
+    let measured = false_positives as f64 / trials as f64;
+    assert!((0.008..0.013).contains(&measured), "{measured}");
+
+    bloom.clear();
+    assert!(!bloom.should_stop_inserting(0.01));
 }
 
 #[test]
-fn bloom_hash_indices_test() {
-    let mut builder =
-        FilterBuilder::new(10_000, 0.01);
+fn uniform_probes_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
     let mut bloom = builder.build_bloom_filter();
-    println!("{:?}", bloom.config);
-    bloom.add(b"hello");
-    assert_eq!(bloom.contains(b"hello"), true);
-    assert_eq!(bloom.contains(b"world"), false);
+    let mut cbf = builder.build_counting_bloom_filter();
+    let m = bloom.config.size;
+    let k = bloom.config.hashes as u64;
+    for x in 0..1000u64 {
+        let element = x.to_le_bytes();
+        let (hash1, hash2) = crate::hashing::hash_pair(&element, 0);
+        let (hash1, hash2) = (hash1 % m, (hash2 % m).max(1));
+        // probe i is hash1 + i * hash2 for every i, the first one included.
+        let expected: Vec<u64> = (0..k).map(|i| (hash1 + i * hash2) % m).collect();
+        assert_eq!(bloom.get_hash_indices(&element), expected);
+        assert_eq!(cbf.get_hash_indices(&element), expected);
+    }
 
-    let indices = bloom.get_hash_indices(b"hello");
-    println!("{:?}", indices);
-    assert_eq!(bloom.contains_hash_indices(&indices), true);
-    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"world")), false);
+    // every probe is counted, removed and checked the same way.
+    let element = b"hello";
+    let indices = cbf.get_hash_indices(element);
+    cbf.add(element);
+    bloom.add(element);
+    for index in indices.iter() {
+        let duplicates = indices.iter().filter(|i| *i == index).count();
+        assert_eq!(cbf.counter_at(*index), duplicates);
+        assert!(bloom.get_bit(*index));
+    }
+    for index in indices.iter() {
+        let mut missing = cbf.clone();
+        missing.counting_vec.set(*index as usize, 0);
+        assert!(!missing.contains(element));
+        assert_eq!(missing.estimate_count(element), 0);
+    }
+    cbf.remove(element);
+    assert!(indices.iter().all(|index| cbf.counter_at(*index) == 0));
 }
 
-#[test] 
-fn bloom_large() {
-    let mut builder =
-        FilterBuilder::new(1_000_000_000, 0.0001);
-    let mut bloom = builder.build_bloom_filter();
+#[test]
+fn bloom_with_bits_set_test() {
+    let bloom = BloomFilter::with_bits_set(FilterBuilder::new(10_000, 0.01), &[3, 100, 5000]);
+    for index in 0..bloom.config().size {
+        assert_eq!(bloom.get_bit(index), [3, 100, 5000].contains(&index), "bit {index}");
+    }
+    assert_eq!(bloom.get_u64_array().iter().map(|w| w.count_ones()).sum::<u32>(), 3);
+    assert!(BloomFilter::with_bits_set(FilterBuilder::new(10_000, 0.01), &[]).get_u8_array()
+        .iter().all(|b| *b == 0));
+}
 
-    bloom.add(b"hello");
-    assert_eq!(bloom.contains(b"hello"), true);
+#[test]
+fn counting_union_bytes_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    let mut a = builder.build_counting_bloom_filter();
+    let mut b = builder.build_counting_bloom_filter();
+    a.add_n(b"both", 3);
+    b.add_n(b"both", 4);
+    a.add_n(b"a", 2);
+    b.add_n(b"b", 5);
+    a.add_n(b"full", 12);
+    b.add_n(b"full", 12);
+
+    let mut total = builder.build_counting_bloom_filter();
+    total.union_bytes(a.get_u8_array(), a.hashes()).unwrap();
+    total.union_bytes(b.get_u8_array(), b.hashes()).unwrap();
+    assert_eq!(total.estimate_count(b"both"), 7);
+    assert_eq!(total.estimate_count(b"a"), 2);
+    assert_eq!(total.estimate_count(b"b"), 5);
+    let max = if cfg!(target_pointer_width = "64") { 15 } else { 7 };
+    assert_eq!(total.estimate_count(b"full"), max);
+    for index in 0..total.config().size {
+        assert_eq!(total.counter_at(index), min(a.counter_at(index) + b.counter_at(index), max));
+    }
 
-    let bloom = BloomFilter::from_u8_array(bloom.get_u8_array(), bloom.hashes());
+    let before = total.get_u8_array().to_vec();
+    assert_eq!(total.union_bytes(a.get_u8_array(), a.hashes() + 1),
+               Err(FilterError::HashesMismatch { expected: a.hashes(), found: a.hashes() + 1 }));
+    assert!(matches!(total.union_bytes(&a.get_u8_array()[8..], a.hashes()),
+                     Err(FilterError::SizeMismatch { .. })));
+    assert_eq!(total.get_u8_array(), &before[..]);
+}
 
-    assert_eq!(bloom.contains(b"hello"), true);
+#[test]
+fn bloom_contains_checked_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    for x in 0..2000u64 {
+        assert_eq!(bloom.contains_checked(&x.to_le_bytes()), bloom.contains(&x.to_le_bytes()));
+    }
 
+    // the size claims four times the bits the storage holds, so `contains` would index past it.
+    let mut storage = BloomBitVec::new(2);
+    for index in 0..2 * usize::BITS as usize {
+        storage.set(index);
+    }
+    let corrupt = BloomFilter::from_parts(
+        FilterBuilder::from_size_and_hashes(8 * usize::BITS as u64, 4), storage);
+    assert!(corrupt.check_invariants().is_err());
+    for x in 0..1000u64 {
+        assert!(!corrupt.contains_checked(&x.to_le_bytes()));
+    }
 }
 
 #[test]
-fn bloom_save_and_load_file_hashes() {
-    {
-        let mut builder = FilterBuilder::new(1_000_000_000, 0.0001);
-        let mut bloom = builder.build_bloom_filter();
+fn cached_bloom_filter_test() {
+    let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let mut cached = CachedBloomFilter::new(bloom, 2);
+    assert!(!cached.contains(b"a"));
+    assert!(!cached.contains(b"a"));
+    assert!(!cached.contains(b"a"));
+    assert_eq!(cached.cache_hits(), 2);
+
+    // "a" is the most recently used, so caching "c" evicts "b".
+    assert!(!cached.contains(b"b"));
+    assert!(!cached.contains(b"a"));
+    assert!(!cached.contains(b"c"));
+    assert_eq!(cached.cache_hits(), 3);
+    assert!(!cached.contains(b"a"));
+    assert!(!cached.contains(b"b"));
+    assert_eq!(cached.cache_hits(), 4);
+
+    cached.add(b"a");
+    assert!(cached.contains(b"a"));
+    assert!(!cached.contains(b"b"));
+    assert_eq!(cached.cache_hits(), 4);
+    assert!(cached.clone().contains(b"a"));
+    assert!(cached.into_inner().contains(b"a"));
+}
 
-        bloom.add(b"hello");
-        assert_eq!(bloom.contains(b"hello"), true);
-        bloom.save_to_file_with_hashes("hello.bloom");
+#[test]
+fn bloom_to_vec_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let before = bloom.to_vec();
+    assert_eq!(bloom.get_u8_array(), before);
+    bloom.add(b"hello");
+    let after = bloom.to_vec();
+    assert_eq!(bloom.get_u8_array(), after);
+    assert_ne!(after, before);
+    assert!(before.iter().all(|byte| *byte == 0));
+    assert!(BloomFilter::from_u8_array(&after, bloom.hashes()).contains(b"hello"));
+
+    bloom.clear();
+    assert_eq!(bloom.to_vec(), before);
+    assert!(after.iter().any(|byte| *byte != 0));
+}
+
+#[test]
+fn bloom_hash_pairs_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_seeds(7, 39);
+    let mut bloom = builder.build_bloom_filter();
+    let mut paired = builder.build_bloom_filter();
+    let pair = |element: &[u8]| (xxh3_64_with_seed(element, 7), xxh3_64_with_seed(element, 39));
+    let pairs: Vec<(u64, u64)> = (0..1000u64).map(|x| pair(&x.to_le_bytes())).collect();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
     }
-    
+    paired.add_hash_pairs(&pairs);
+    assert_eq!(paired.get_u8_array(), bloom.get_u8_array());
 
-    let bloom2 = BloomFilter::from_file_with_hashes("hello.bloom");
-    fs::remove_file("hello.bloom").unwrap();
+    let queries: Vec<(u64, u64)> = (0..2000u64).map(|x| pair(&x.to_le_bytes())).collect();
+    let expected: Vec<bool> = (0..2000u64).map(|x| bloom.contains(&x.to_le_bytes())).collect();
+    assert_eq!(paired.contains_hash_pairs(&queries), expected);
+    assert!(paired.contains_hash_pairs(&[]).is_empty());
+}
 
-    assert_eq!(bloom2.contains(b"hello"), true);
-    assert_eq!(bloom2.contains(b"world"), false);
+#[test]
+fn counting_is_empty_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    assert!(cbf.is_empty());
+    cbf.add(b"hello");
+    cbf.add(b"hello");
+    assert!(!cbf.is_empty());
+    cbf.remove(b"hello");
+    assert!(!cbf.is_empty());
+    cbf.remove(b"hello");
+    assert!(cbf.is_empty());
 
+    cbf.add_n(b"world", 3);
+    cbf.clear();
+    assert!(cbf.is_empty());
 }
 
 #[test]
-fn bloom_save_and_load_file() {
-    let mut hashes = 0;
-    {
-        let mut builder = FilterBuilder::new(1_000_000_000, 0.0001);
-        let mut bloom = builder.build_bloom_filter();
+fn bloom_empty_like_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.hash_seeds(3, 35);
+    builder.hash_width(HashWidth::Bits128);
+    let mut bloom = builder.build_bloom_filter();
+    bloom.add(b"hello");
+    let loaded = BloomFilter::from_u8_array(&[0xff; 16], 3);
+    for filter in [&bloom, &loaded] {
+        let mut acc = BloomFilter::empty_like(filter);
+        assert!(acc.compatible(filter));
+        assert!(acc.is_empty());
+        assert_eq!(acc.config, filter.config);
+        assert!(acc.get_u8_array().iter().all(|byte| *byte == 0));
+        assert_eq!(acc.get_u8_array().len(), filter.get_u8_array().len());
+        assert!(acc.union(filter));
+        assert_eq!(acc.get_u8_array(), filter.get_u8_array());
+    }
+}
 
-        bloom.add(b"hello");
-        assert_eq!(bloom.contains(b"hello"), true);
+#[test]
+fn bloom_probe_histogram_test() {
+    let mut bloom = FilterBuilder::new(100_000, 0.01).build_bloom_filter();
+    let mut state = 42u64;
+    for _ in 0..20_000 {
+        bloom.add(&splitmix64(&mut state).to_le_bytes());
+    }
+    let buckets = 64;
+    let histogram = bloom.probe_histogram(buckets);
+    let total: u64 = histogram.iter().sum();
+    assert_eq!(total, bloom.config.size - bloom.bit_set.count_zeros() as u64);
+
+    // uniform keys: chi-square with 63 degrees of freedom, whose 99.9% quantile is about 104.
+    let expected = total as f64 / buckets as f64;
+    let chi_square: f64 = histogram.iter()
+        .map(|&observed| (observed as f64 - expected).powi(2) / expected)
+        .sum();
+    assert!(chi_square < 104.0, "{chi_square}");
+
+    // a filter whose keys all land in the first half is visibly skewed.
+    let mut skewed = BloomFilter::empty_like(&bloom);
+    for index in 0..bloom.config.size / 2 {
+        if bloom.bit_set.get(index as usize) {
+            skewed.bit_set.set(index as usize);
+        }
+    }
+    let histogram = skewed.probe_histogram(buckets);
+    assert!(histogram[buckets / 2..].iter().all(|count| *count == 0));
+    assert_eq!(bloom.probe_histogram(1), vec![total]);
+    assert_eq!(bloom.probe_histogram(7).iter().sum::<u64>(), total);
+}
 
-        hashes = bloom.hashes();
-        
-        bloom.save_to_file("no_hashes.bloom");
+#[test]
+fn counting_subtract_test() {
+    let builder = FilterBuilder::new(10_000, 0.01);
+    let mut a = builder.clone().build_counting_bloom_filter();
+    let mut b = builder.clone().build_counting_bloom_filter();
+    a.add_n(b"key", 5);
+    a.add_n(b"only in a", 2);
+    b.add_n(b"key", 2);
+    b.add_n(b"only in b", 4);
+    assert!(a.subtract(&b));
+    assert_eq!(a.estimate_count(b"key"), 3);
+    assert_eq!(a.estimate_count(b"only in a"), 2);
+    assert_eq!(a.estimate_count(b"only in b"), 0);
+
+    // subtracting more than was added saturates at zero.
+    assert!(a.subtract(&b));
+    assert!(a.subtract(&b));
+    assert_eq!(a.estimate_count(b"key"), 0);
+    assert_eq!(a.estimate_count(b"only in a"), 2);
+
+    let other = FilterBuilder::new(1_000, 0.01).build_counting_bloom_filter();
+    let before = a.get_u8_array().to_vec();
+    assert!(!a.subtract(&other));
+    assert_eq!(a.get_u8_array(), &before[..]);
+}
+
+#[test]
+fn frozen_bloom_filter_test() {
+    for hash_width in [HashWidth::Bits64, HashWidth::Bits128] {
+        let mut builder = FilterBuilder::new(10_000, 0.01);
+        builder.hash_width(hash_width);
+        let mut bloom = builder.build_bloom_filter();
+        for x in 0..10_000u64 {
+            bloom.add(&x.to_le_bytes());
+        }
+        let frozen = bloom.clone().freeze();
+        assert_eq!(frozen.lines.as_ptr() as usize % 64, 0);
+        assert_eq!(frozen.config(), bloom.config());
+        assert_eq!(frozen.hashes(), bloom.hashes());
+        for x in 0..20_000u64 {
+            let element = x.to_le_bytes();
+            assert_eq!(frozen.contains(&element), bloom.contains(&element));
+            let indices = frozen.get_hash_indices(&element);
+            assert_eq!(frozen.contains_hash_indices(&indices), bloom.contains_hash_indices(&indices));
+        }
+        assert!(!frozen.contains_hash_indices(&[bloom.config.size]));
     }
 
-    let bloom2 = BloomFilter::from_file("no_hashes.bloom", hashes);
-    fs::remove_file("no_hashes.bloom").unwrap();
+    for m in [1u64, 2, 3, 1 << 20, 958_506, u64::MAX / 3, u64::MAX] {
+        let magic = (u128::MAX / m as u128).wrapping_add(1);
+        for hash in [0, 1, m - 1, m, m.wrapping_add(1), 0x9e37_79b9_7f4a_7c15, u64::MAX] {
+            assert_eq!(fast_mod(hash, m, magic), hash % m);
+        }
+    }
 
-    assert_eq!(bloom2.contains(b"hello"), true);
-    assert_eq!(bloom2.contains(b"world"), false);
-    
+    // a filter whose size is not a whole number of cache lines.
+    let mut bloom = BloomFilter::from_u8_array(&[0; 104], 3);
+    bloom.add(b"hello");
+    let frozen = bloom.freeze();
+    assert!(frozen.contains(b"hello"));
 }
 
+#[test]
+fn empty_element_test() {
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    assert!(!bloom.contains(b""));
+    bloom.add(b"");
+    assert!(bloom.contains(b""));
+    assert!(bloom.contains_str(""));
+    let mut indices = bloom.get_hash_indices(b"");
+    indices.sort_unstable();
+    indices.dedup();
+    assert_eq!(bloom.bit_set.count_ones_in(0, bloom.config.size as usize), indices.len() as u64);
+    assert!((0..10_000u64).all(|x| !bloom.contains(&x.to_le_bytes())));
+    assert!(!bloom.contains(b"\0"));
+
+    let mut counting = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    counting.add_str("");
+    assert!(counting.contains(b""));
+    assert_eq!(counting.estimate_count(b""), 1);
+    counting.remove(b"");
+    assert!(!counting.contains(b""));
+    assert!(counting.is_empty());
+}
 
 #[test]
-fn counting_bloom_test() {
-    let mut builder =
-        FilterBuilder::new(10_000, 0.01);
-    let mut bloom = builder.build_counting_bloom_filter();
+fn bloom_u64_test() {
+    for hash_width in [HashWidth::Bits64, HashWidth::Bits128] {
+        let mut builder = FilterBuilder::new(10_000, 0.01);
+        builder.hash_width(hash_width).hash_seeds(7, 39);
+        let mut bloom = builder.build_bloom_filter();
+        let mut expected = builder.build_bloom_filter();
+        for x in (0..10_000u64).map(|x| x * 3) {
+            bloom.add_u64(x);
+            expected.add(&x.to_le_bytes());
+        }
+        assert_eq!(bloom.bit_set.storage, expected.bit_set.storage);
+        for x in 0..30_000u64 {
+            assert_eq!(bloom.contains_u64(x), bloom.contains(&x.to_le_bytes()));
+        }
+    }
+}
 
-    bloom.add(b"hello");
+#[test]
+fn bloom_legacy_test() {
+    use fastmurmur3::murmur3_x64_128;
+
+    // the bits older versions of the crate set, `murmur3(element, 0|32) % m` by double hashing.
+    let (m, k) = (1u64 << 16, 7u64);
+    let mut bit_set = BloomBitVec::new((m / usize::BITS as u64) as usize);
+    for x in 0..1_000u64 {
+        let element = x.to_le_bytes();
+        let hash1 = (murmur3_x64_128(&element, 0) % m as u128) as u64;
+        let hash2 = (murmur3_x64_128(&element, 32) % m as u128) as u64;
+        for i in 0..k {
+            bit_set.set(((hash1 + i * hash2) % m) as usize);
+        }
+    }
+    let bytes = Vec::<u8>::from(&BloomFilter::from_parts(
+        FilterBuilder::from_size_and_hashes(m, k as u32), bit_set));
+
+    let legacy = BloomFilter::from_u8_array_legacy(&bytes, k as u32);
+    assert!((0..1_000u64).all(|x| legacy.contains(&x.to_le_bytes())));
+    assert!((1_000..2_000u64).filter(|x| legacy.contains(&x.to_le_bytes())).count() < 20);
+    // the current scheme misses the members.
+    let current = BloomFilter::from_u8_array(&bytes, k as u32);
+    assert!((0..1_000u64).filter(|x| current.contains(&x.to_le_bytes())).count() < 20);
+
+    let loaded = BloomFilter::from_u8_array_with_header(&legacy.to_u8_array_with_header()).unwrap();
+    assert_eq!(loaded.config().hash_width, HashWidth::LegacyMurmur128);
+    assert!((0..1_000u64).all(|x| loaded.contains(&x.to_le_bytes())));
+
+    let migrated = legacy.migrate_to_current((0..1_000u64).map(u64::to_le_bytes));
+    assert_eq!(migrated.config().hash_width, HashWidth::Bits64);
+    assert_eq!(migrated.config().size, m);
+    assert!((0..1_000u64).all(|x| migrated.contains(&x.to_le_bytes())));
+}
 
-    assert_eq!(bloom.contains(b"hello"), true);
+#[test]
+fn small_membership_test() {
+    let mut builder = FilterBuilder::new(1000, 0.01);
+    let mut set = builder.build_adaptive(100);
+    let mut bloom = builder.build_bloom_filter();
+    for x in 0..100u64 {
+        set.add(&x.to_le_bytes());
+        bloom.add(&x.to_le_bytes());
+    }
+    assert!(set.is_exact());
+    assert!(set.bloom_filter().is_none());
+    for x in 0..100u64 {
+        assert!(set.contains(&x.to_le_bytes()));
+        assert!(set.contains_hash_indices(&set.get_hash_indices(&x.to_le_bytes())));
+    }
+    // exact below the threshold: no false positives.
+    assert!((100..100_000u64).all(|x| !set.contains(&x.to_le_bytes())));
+
+    set.add(&100u64.to_le_bytes());
+    bloom.add(&100u64.to_le_bytes());
+    assert!(!set.is_exact());
+    assert_eq!(set.bloom_filter().unwrap().get_u8_array(), bloom.get_u8_array());
+    for x in 101..1000u64 {
+        set.add(&x.to_le_bytes());
+    }
+    assert!((0..1000u64).all(|x| set.contains(&x.to_le_bytes())));
+    assert_eq!(set.hashes(), bloom.hashes());
 
-    bloom.remove(b"hello");
-    assert_eq!(bloom.contains(b"hello"), false);
+    set.clear();
+    assert!(set.is_exact());
+    assert!(!set.contains(&0u64.to_le_bytes()));
 }
 
 #[test]
-fn counting_bloom_repeat_test() {
-    let mut builder = FilterBuilder::new(100_000, 0.01);
-    // enable_repeat_insert is true
-    builder.enable_repeat_insert(true);
-    let mut cbf = builder.build_counting_bloom_filter();
-    cbf.add(b"hello"); // modify underlying vector counter.
-    cbf.add(b"hello"); // modify underlying vector counter.
-    assert_eq!(cbf.contains(b"hello"), true);
-    cbf.remove(b"hello");
-    assert_eq!(cbf.contains(b"hello"), true);
-    cbf.remove(b"hello");
-    assert_eq!(cbf.contains(b"hello"), false);
-
-    // enable_repeat_insert is false
-    builder.enable_repeat_insert(false);
-    let mut cbf = builder.build_counting_bloom_filter();
-    cbf.add(b"hello"); // modify underlying vector counter.
-    cbf.add(b"hello"); // not modify underlying vector counter because b"hello" has been added.
-    assert_eq!(cbf.contains(b"hello"), true);
-    cbf.remove(b"hello");
-    assert_eq!(cbf.contains(b"hello"), false);
+fn bloom_word_bit_indices_test() {
+    for hash_width in [HashWidth::Bits64, HashWidth::Bits128] {
+        let mut builder = FilterBuilder::new(100_000, 0.01);
+        builder.hash_width(hash_width);
+        let bloom = builder.build_bloom_filter();
+        for x in 0..100u64 {
+            let element = x.to_le_bytes();
+            let pairs = bloom.get_word_bit_indices(&element);
+            assert!(pairs.iter().all(|&(_, bit)| bit < usize::BITS));
+            let indices: Vec<u64> = pairs.iter()
+                .map(|&(word, bit)| word * usize::BITS as u64 + bit as u64)
+                .collect();
+            assert_eq!(indices, bloom.get_hash_indices(&element));
+        }
+    }
 }
 
 #[test]
-fn counting_bloom_from_test() {
-    let mut builder = FilterBuilder::new(10_000_000, 0.01);
-    let mut cbf = builder.build_counting_bloom_filter();
+fn counting_reset_if_saturated_test() {
+    let mut cbf = FilterBuilder::from_size_and_hashes(1024, 4).build_counting_bloom_filter();
+    cbf.add_n(b"hot", COUNTER_MAX);
+    assert_eq!(cbf.saturated_counters(), 4);
+    assert!(!cbf.reset_if_saturated(0.5));
+    for x in 0..2000u64 {
+        cbf.add_n(&x.to_le_bytes(), COUNTER_MAX);
+    }
+    assert!(cbf.saturated_counters() as f64 / cbf.size_counters() as f64 > 0.5);
+    assert!(cbf.reset_if_saturated(0.5));
+    assert_eq!(cbf.saturated_counters(), 0);
+    assert_eq!(cbf.nonzero_counters().count(), 0);
+    assert!(!cbf.reset_if_saturated(0.5));
+}
 
-    cbf.add(b"hello");
-    cbf.add(b"hello");
+#[test]
+fn bloom_duplicate_tracking_test() {
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.enable_duplicate_tracking(true);
+    let mut bloom = builder.build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    for x in 0..100u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    for x in 0..100u64 {
+        assert!(bloom.seen_multiple(&x.to_le_bytes()));
+    }
+    let once = (100..1000u64).filter(|x| bloom.seen_multiple(&x.to_le_bytes())).count();
+    assert!(once < 50, "{once}");
 
-    let mut cbf_copy = CountingBloomFilter::from_u8_array(cbf.get_u8_array(), builder.hashes, true);
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), false);
+    bloom.clear();
+    assert!(!bloom.seen_multiple(&0u64.to_le_bytes()));
 
-    let mut cbf_copy = CountingBloomFilter::from_u16_array(cbf.get_u16_array(), builder.hashes, true);
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), false);
+    let mut plain = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    plain.add(b"hello");
+    plain.add(b"hello");
+    assert!(!plain.seen_multiple(b"hello"));
+}
 
-    let mut cbf_copy = CountingBloomFilter::from_u32_array(cbf.get_u32_array(), builder.hashes, true);
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), true);
-    cbf_copy.remove(b"hello");
-    assert_eq!(cbf_copy.contains(b"hello"), false);
+#[test]
+fn counting_exact_overlay_test() {
+    let mut builder = FilterBuilder::from_size_and_hashes(256, 3);
+    let keys: Vec<[u8; 8]> = (0..40u64).map(u64::to_le_bytes).collect();
+    let mut plain = builder.clone().build_counting_bloom_filter();
+    let mut tracked = builder.clone().build_counting_bloom_filter().with_exact_overlay(64);
+    for key in &keys {
+        plain.add(key);
+        tracked.add(key);
+    }
+    // a false positive sharing counters with the inserted keys.
+    let ghosts: Vec<[u8; 8]> = (1000..100_000u64).map(u64::to_le_bytes)
+        .filter(|ghost| plain.contains(ghost))
+        .take(20)
+        .collect();
+    assert!(!ghosts.is_empty());
+    for ghost in &ghosts {
+        plain.remove(ghost);
+        tracked.remove(ghost);
+    }
+    assert!(keys.iter().any(|key| !plain.contains(key)));
+    for key in &keys {
+        assert!(tracked.contains(key));
+    }
 
-    #[cfg(target_pointer_width = "64")]{
-        let mut cbf_copy = CountingBloomFilter::from_u64_array(cbf.get_u64_array(), builder.hashes, true);
-        assert_eq!(cbf_copy.contains(b"hello"), true);
-        cbf_copy.remove(b"hello");
-        assert_eq!(cbf_copy.contains(b"hello"), true);
-        cbf_copy.remove(b"hello");
-        assert_eq!(cbf_copy.contains(b"hello"), false);
+    // removals beyond the exact count leave the counters alone.
+    tracked.add(&keys[0]);
+    tracked.remove_n(&keys[0], 5);
+    tracked.remove(&keys[0]);
+    for key in &keys[1..] {
+        assert!(tracked.contains(key));
     }
+
+    // once the overlay is full, untracked elements fall back to the presence check.
+    let mut full = builder.build_counting_bloom_filter().with_exact_overlay(1);
+    full.add(b"tracked");
+    full.add(b"untracked");
+    full.remove(b"untracked");
+    assert!(!full.contains(b"untracked"));
+    full.clear();
+    full.add(b"untracked");
+    full.remove(b"never added");
+    assert!(full.contains(b"untracked"));
 }
 
 #[test]
-fn counting_bloom_hash_indices_test() {
-    let mut builder =
-        FilterBuilder::new(10_000, 0.01);
-    let mut bloom = builder.build_counting_bloom_filter();
-
-    bloom.add(b"hello");
+fn bloom_union_ignore_hashes_test() {
+    let mut bloom = FilterBuilder::from_size_and_hashes(1 << 16, 7).build_bloom_filter();
+    let mut fewer = FilterBuilder::from_size_and_hashes(1 << 16, 4).build_bloom_filter();
+    let mut more = FilterBuilder::from_size_and_hashes(1 << 16, 9).build_bloom_filter();
+    for x in 0..1000u64 {
+        bloom.add(&x.to_le_bytes());
+        fewer.add(&(x + 1000).to_le_bytes());
+        more.add(&(x + 2000).to_le_bytes());
+    }
+    assert!(!bloom.union(&fewer));
+    assert!(bloom.union_ignore_hashes(&fewer));
+    assert!(bloom.union_ignore_hashes(&more));
+    assert_eq!(bloom.hashes(), 7);
+    for x in 0..1000u64 {
+        assert!(bloom.contains(&x.to_le_bytes()));
+        assert!(bloom.contains(&(x + 2000).to_le_bytes()));
+    }
 
-    assert_eq!(bloom.contains(b"hello"), true);
-    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"hello")), true);
-    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"world")), false);
+    let other_size = FilterBuilder::from_size_and_hashes(1 << 17, 7).build_bloom_filter();
+    let before = bloom.get_u8_array().to_vec();
+    assert!(!bloom.union_ignore_hashes(&other_size));
+    assert_eq!(bloom.get_u8_array(), &before[..]);
+}
 
+#[test]
+fn bloom_sample_fpr_test() {
+    let mut bloom = FilterBuilder::new(100_000, 0.01).build_bloom_filter();
+    assert_eq!(bloom.sample_fpr(1000, 1), 0.0);
+    for x in 0..100_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let analytical = bloom.current_false_positive_probability();
+    let sampled = bloom.sample_fpr(200_000, 1);
+    assert!(sampled > analytical / 1.5 && sampled < analytical * 1.5, "{sampled} {analytical}");
+    assert_eq!(bloom.sample_fpr(1000, 2), bloom.sample_fpr(1000, 2));
+}
 
-    bloom.remove(b"hello");
-    assert_eq!(bloom.contains(b"hello"), false);
-    assert_eq!(bloom.contains_hash_indices(&bloom.get_hash_indices(b"hello")), false);
+#[test]
+fn counting_from_parts_test() {
+    let builder = FilterBuilder::from_size_and_hashes(1024, 3);
+    let probes: Vec<u64> = probes(b"hello", 1024, 3, builder.hash_seeds).collect();
+    let mut bits = vec![0u8; 128];
+    let mut counts = vec![0u8; 1024];
+    for (n, index) in probes.iter().enumerate() {
+        bits[*index as usize / 8] |= 1 << (index % 8);
+        counts[*index as usize] = 4 + n as u8;
+    }
+    // counts of unset bits are ignored.
+    counts[(probes[0] as usize + 1) % 1024] = 9;
+    let cbf = CountingBloomFilter::from_parts(&bits, &counts, 3, true).unwrap();
+    assert!(cbf.contains(b"hello"));
+    assert_eq!(cbf.estimate_count(b"hello"), 4);
+    assert_eq!(cbf.nonzero_counters().count(), 3);
+    assert_eq!(cbf.to_bloom_bytes(), bits);
+
+    assert_eq!(CountingBloomFilter::from_parts(&bits, &counts[1..], 3, true).unwrap_err(),
+               FilterError::LengthMismatch { expected: 1024, found: 1023 });
+    assert_eq!(CountingBloomFilter::from_parts(&bits[..1], &counts[..8], 3, true).unwrap_err(),
+               FilterError::SizeNotSlotAligned { size: 8, counters_per_slot: (usize::BITS >> 2) as u64 });
 }
 
 #[test]
-fn counting_bloom_estimate_count() {
-    let mut builder =
-        FilterBuilder::new(10_000, 0.01);
-    let mut bloom = builder.build_counting_bloom_filter();
+fn bloom_unchecked_test() {
+    for hash_width in [HashWidth::Bits64, HashWidth::Bits128] {
+        let mut builder = FilterBuilder::new(10_000, 0.01);
+        builder.hash_width(hash_width);
+        let mut checked = builder.clone().build_bloom_filter();
+        let mut unchecked = builder.build_bloom_filter();
+        assert!(unchecked.check_invariants().is_ok());
+        for x in 0..5000u64 {
+            checked.add(&x.to_le_bytes());
+            unsafe { unchecked.add_unchecked(&x.to_le_bytes()) };
+        }
+        assert_eq!(checked.get_u8_array(), unchecked.get_u8_array());
+        for x in 0..20_000u64 {
+            let element = x.to_le_bytes();
+            assert_eq!(unsafe { unchecked.contains_unchecked(&element) }, checked.contains(&element));
+        }
+    }
+}
 
-    bloom.add(b"hello");
-    bloom.add(b"world");
+#[test]
+fn as_deletable_test() {
+    let builder = FilterBuilder::new(10_000, 0.01);
+    let mut filters: Vec<Box<dyn Membership>> = vec![
+        Box::new(builder.clone().build_counting_bloom_filter()),
+        Box::new(builder.clone().build_bloom_filter()),
+        Box::new(builder.clone().build_rolling_bloom_filter(2)),
+        Box::new(CachedBloomFilter::new(builder.clone().build_bloom_filter(), 16)),
+    ];
+    for filter in filters.iter_mut() {
+        filter.add(b"hello");
+        if let Some(deletable) = filter.as_deletable() {
+            deletable.remove(b"hello");
+        }
+    }
+    let present: Vec<bool> = filters.iter().map(|filter| filter.contains(b"hello")).collect();
+    assert_eq!(present, [false, true, true, true]);
+}
 
-    assert_eq!(bloom.estimate_count(b"hello"), 1);
-    let indices = bloom.get_hash_indices(b"hello");
+#[test]
+fn bloom_get_u64_array_test() {
+    assert_eq!(pack_u32_pairs(&[1, 2, 3]), [1 | 2 << 32, 3]);
+    assert_eq!(pack_u32_pairs(&[]), Vec::<u64>::new());
 
-    for index in indices {
-        assert_eq!(bloom.counter_at(index), 1)
-    }
+    let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    bloom.add(b"hello");
+    let words = bloom.to_u64_vec();
+    assert_eq!(words.len() as u64, bloom.size_bits().div_ceil(64));
+    assert_eq!(pack_u32_pairs(bloom.get_u32_array()), words);
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(bloom.get_u64_array(), &words[..]);
+    assert!(BloomFilter::from_u64_array(&words, bloom.hashes()).contains(b"hello"));
+}
 
-    assert_eq!(bloom.estimate_count(b"world"), 1);
-    for index in bloom.get_hash_indices(b"world") {
-        assert!(bloom.counter_at(index) <= 2);
+#[test]
+fn counting_estimate_count_all_test() {
+    let mut cbf = FilterBuilder::new(10_000, 0.01).build_counting_bloom_filter();
+    for x in 0..100u64 {
+        cbf.add_n(&x.to_le_bytes(), (x % 5) as usize);
     }
+    let elements: Vec<[u8; 8]> = (0..200u64).map(u64::to_le_bytes).collect();
+    let counts = cbf.estimate_count_all(&elements);
+    let expected: Vec<usize> = elements.iter().map(|element| cbf.estimate_count(element)).collect();
+    assert_eq!(counts, expected);
+    assert_eq!(counts[..5], [0, 1, 2, 3, 4]);
+    assert!(cbf.estimate_count_all(Vec::<Vec<u8>>::new()).is_empty());
 }
@@ -1,5 +1,9 @@
-use crate::bloom::{BloomFilter, CountingBloomFilter};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::bloom::{BloomFilter, CountingBloomFilter, RollingBloomFilter, SmallMembership};
 use crate::Membership;
+use crate::error::ConfigIssue;
 
 /// Builder for Bloom Filters.
 #[derive(Clone)]
@@ -12,9 +16,59 @@ pub struct FilterBuilder {
     pub hashes: u32,
     /// Usage for CountingBloomFilter.
     pub enable_repeat_insert: bool,
+    /// Usage for BloomFilter, see [FilterBuilder::enable_dirty_tracking].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub enable_dirty_tracking: bool,
+    /// Usage for BloomFilter, see [FilterBuilder::enable_duplicate_tracking].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub enable_duplicate_tracking: bool,
+    /// Round the inferred size up to a power of two, see [FilterBuilder::power_of_two_size].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub power_of_two_size: bool,
+    /// Round the inferred size up to a multiple of this many bits, see
+    /// [FilterBuilder::align_size_to]. Zero keeps the word alignment only.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub align_size_to: u64,
+    /// Usage for BloomFilter, see [FilterBuilder::hash_width].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hash_width: HashWidth,
+    /// Seeds of the two xxh3 hashes, see [FilterBuilder::hash_seeds].
+    #[cfg_attr(feature = "serde", serde(default = "default_hash_seeds"))]
+    pub hash_seeds: (u64, u64),
     pub(crate) done: bool,
 }
 
+/// The xxh3 seeds used unless set with [FilterBuilder::hash_seeds].
+pub(crate) const DEFAULT_HASH_SEEDS: (u64, u64) = (0, 32);
+
+#[cfg(feature = "serde")]
+fn default_hash_seeds() -> (u64, u64) {
+    DEFAULT_HASH_SEEDS
+}
+
+/// Width of the hash the indices of an element are derived from, see [FilterBuilder::hash_width].
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashWidth {
+    /// Two 64 bit xxh3 hashes, reduced mod size and combined by double hashing.
+    #[default]
+    Bits64,
+    /// One 128 bit murmur3 hash whose halves are combined in 128 bit arithmetic before the
+    /// result is reduced mod size. Slower, but less biased for very low false positive rates.
+    Bits128,
+    /// The indices of filters persisted by older versions of the crate, which hashed with two
+    /// murmur3 hashes reduced mod size instead of xxh3, see [crate::BloomFilter::from_u8_array_legacy].
+    /// Only meant for reading such filters: the xxh3 hashes of [HashWidth::Bits64] are faster.
+    LegacyMurmur128,
+}
+
+/// The largest number of hashes a filter may use. The optimal number of hashes for a false
+/// positive probability `p` is about `log2(1 / p)`, so this covers any `p` down to `2^-128`.
+pub const MAX_HASHES: u32 = 128;
+
 #[cfg(target_pointer_width = "32")]
 pub(crate) const SUFFIX: usize = 0b0001_1111;
 #[cfg(target_pointer_width = "64")]
@@ -38,10 +92,50 @@ fn optimal_m(n: u64, p: f64) -> u64 {
     m
 }
 
+/// Calculates the size `m` of the bloom filter in bits needed to reach the false positive rate
+/// `p` for `n` expected elements when the number of hashes `k` is fixed, by inverting
+/// `p = (1 - e^(-kn/m))^k`.
+#[inline]
+fn fixed_k_m(n: u64, p: f64, k: u32) -> u64 {
+    let fact = -(k as f64) * n as f64;
+    let div = (1.0 - p.powf(1.0 / k as f64)).ln();
+    let m: f64 = fact / div;
+    let mut m = m.ceil() as u64;
+    if (m & SUFFIX as u64) != 0 {
+        m = (m & MASK) + SUFFIX as u64 + 1;
+    };
+    m
+}
+
+/// Calculates the size `m` of a one-hash filter in bits for which linear counting of `n` elements
+/// has a standard error of at most `error` relative to `n`. The standard error is
+/// `sqrt(m * (e^t - t - 1)) / n` with load `t = n / m` (Whang et al., "A linear-time probabilistic
+/// counting algorithm for database applications"), which falls as `m` grows, so the smallest such
+/// `m` is found by doubling and bisection.
+fn linear_counting_m(n: u64, error: f64) -> u64 {
+    let std_error = |m: u64| {
+        let t = n as f64 / m as f64;
+        (m as f64 * (t.exp() - t - 1.0)).sqrt() / n as f64
+    };
+    let mut high = SUFFIX as u64 + 1;
+    while std_error(high) > error {
+        high *= 2;
+    }
+    let mut low = high / 2;
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if std_error(mid) > error { low = mid } else { high = mid }
+    }
+    if (high & SUFFIX as u64) != 0 {
+        high = (high & MASK) + SUFFIX as u64 + 1;
+    };
+    high
+}
+
 /// Calculates the optimal `hashes` (number of hash function) given `n` (expected number of
 /// elements in bloom filter) and `m` (size of bloom filter in bits).
 #[inline]
-fn optimal_k(n: u64, m: u64) -> u32 {
+pub(crate) fn optimal_k(n: u64, m: u64) -> u32 {
     let k: f64 = (m as f64 * 2f64.ln()) / n as f64;
     k.ceil() as u32
 }
@@ -60,7 +154,7 @@ fn optimal_n(k: u32, m: u64) -> u64 {
 /// `m` The size of the bloom filter in bits.
 /// `n` number of elements inserted in the filter.
 #[inline]
-fn optimal_p(k: u32, m: u64, n: u64) -> f64 {
+pub(crate) fn optimal_p(k: u32, m: u64, n: u64) -> f64 {
     let nk = -(k as f64);
     (1.0 - (nk * n as f64 / m as f64).exp()).powi(k as i32)
 }
@@ -85,6 +179,12 @@ impl FilterBuilder {
             size: 0,
             hashes: 0,
             enable_repeat_insert: true,
+            enable_dirty_tracking: false,
+            enable_duplicate_tracking: false,
+            power_of_two_size: false,
+            align_size_to: 0,
+            hash_width: HashWidth::Bits64,
+            hash_seeds: DEFAULT_HASH_SEEDS,
             done: false,
         }
     }
@@ -101,10 +201,143 @@ impl FilterBuilder {
             size,
             hashes,
             enable_repeat_insert: true,
+            enable_dirty_tracking: false,
+            enable_duplicate_tracking: false,
+            power_of_two_size: false,
+            align_size_to: 0,
+            hash_width: HashWidth::Bits64,
+            hash_seeds: DEFAULT_HASH_SEEDS,
             done: true,
         }
     }
 
+    /// Constructs a new Bloom Filter Builder by specifying the expected size of the filter, the
+    /// tolerable false positive probability and a fixed number of hashes. The size of the Bloom
+    /// filter in bits is derived from the false positive formula solved for a fixed `hashes`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let builder = FilterBuilder::from_fpr_and_hashes(100_000, 0.01, 4);
+    /// assert_eq!(builder.hashes, 4);
+    /// ```
+    pub fn from_fpr_and_hashes(expected_elements: u64, false_positive_probability: f64,
+                               hashes: u32) -> Self {
+        assert!(expected_elements > 0, "expected_elements must larger than 0!");
+        assert!(false_positive_probability < 1.0 && false_positive_probability > 0.0,
+                "false_positive_probability must between (0.0, 1.0)!");
+        assert!(hashes > 0, "hashes must larger than 0!");
+        let size = fixed_k_m(expected_elements, false_positive_probability, hashes);
+        assert!(size > 0 && optimal_p(hashes, size, expected_elements) <= false_positive_probability,
+                "{hashes} hashes can not reach false_positive_probability {false_positive_probability}!");
+        FilterBuilder {
+            expected_elements,
+            false_positive_probability,
+            size,
+            hashes,
+            enable_repeat_insert: true,
+            enable_dirty_tracking: false,
+            enable_duplicate_tracking: false,
+            power_of_two_size: false,
+            align_size_to: 0,
+            hash_width: HashWidth::Bits64,
+            hash_seeds: DEFAULT_HASH_SEEDS,
+            done: true,
+        }
+    }
+
+    /// Constructs a new Bloom Filter Builder for estimating the number of distinct elements with
+    /// [BloomFilter::estimate_set_cardinality] rather than for membership. Linear counting is most
+    /// accurate with a single hash, so the filter gets `hashes = 1` and is sized so that the
+    /// estimate for `expected_elements` has a standard error of `relative_error / 2`, i.e. it is
+    /// within `relative_error` of the true count about 95% of the time. This needs far fewer bits
+    /// than [FilterBuilder::new], at the cost of a high false positive probability.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let builder = FilterBuilder::for_cardinality(100_000, 0.01);
+    /// assert_eq!(builder.hashes, 1);
+    /// assert!(builder.size < FilterBuilder::new(100_000, 0.01).build_bloom_filter().config().size);
+    /// ```
+    pub fn for_cardinality(expected_elements: u64, relative_error: f64) -> Self {
+        assert!(expected_elements > 0, "expected_elements must larger than 0!");
+        assert!(relative_error < 1.0 && relative_error > 0.0,
+                "relative_error must between (0.0, 1.0)!");
+        let size = linear_counting_m(expected_elements, relative_error / 2.0);
+        FilterBuilder {
+            expected_elements,
+            false_positive_probability: optimal_p(1, size, expected_elements),
+            size,
+            hashes: 1,
+            enable_repeat_insert: true,
+            enable_dirty_tracking: false,
+            enable_duplicate_tracking: false,
+            power_of_two_size: false,
+            align_size_to: 0,
+            hash_width: HashWidth::Bits64,
+            hash_seeds: DEFAULT_HASH_SEEDS,
+            done: true,
+        }
+    }
+
+    /// Constructs a new Bloom Filter Builder from a sample of the data when the number of elements
+    /// is unknown. `sample_fraction` is the share of the data the sample was drawn from, and the
+    /// expected size of the filter is estimated as `distinct_in_sample / sample_fraction`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let sample: Vec<String> = (0..1000).map(|x| (x % 500).to_string()).collect();
+    /// let builder = FilterBuilder::from_sample(&sample, 0.1, 0.01);
+    /// assert_eq!(builder.expected_elements, 5000);
+    /// ```
+    pub fn from_sample<T: AsRef<[u8]>>(sample: &[T], sample_fraction: f64,
+                                       false_positive_probability: f64) -> Self {
+        assert!(sample_fraction > 0.0 && sample_fraction <= 1.0,
+                "sample_fraction must between (0.0, 1.0]!");
+        let distinct: HashSet<&[u8]> = sample.iter().map(|element| element.as_ref()).collect();
+        let expected_elements = (distinct.len() as f64 / sample_fraction).ceil() as u64;
+        FilterBuilder::new(expected_elements.max(1), false_positive_probability)
+    }
+
+    /// Builds a Bloom filter holding `elements`, sized for exactly as many elements at
+    /// `false_positive_probability`. This takes two passes over `elements`: the first one counts
+    /// them and the second one adds them, so a lazy iterator is cloned and run twice. Pass a slice
+    /// or `&Vec` to make the first pass cheap. Every item counts, so duplicates size the filter
+    /// larger than needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::validate].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let words = vec!["hello", "world"];
+    /// let bloom = FilterBuilder::optimally_build_from(0.01, &words);
+    /// assert_eq!(bloom.config().expected_elements, 2);
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn optimally_build_from<I>(false_positive_probability: f64, elements: I) -> BloomFilter
+        where I: IntoIterator + Clone, I::Item: AsRef<[u8]> {
+        let expected_elements = elements.clone().into_iter().count() as u64;
+        let mut bloom = FilterBuilder::new(expected_elements.max(1), false_positive_probability)
+            .build_bloom_filter();
+        for element in elements {
+            bloom.add(element.as_ref());
+        }
+        bloom
+    }
+
     /// set the expected size of the filter.
     fn expected_elements(&mut self, expected_elements: u64) {
         assert!(expected_elements > 0, "expected_elements must larger than 0!");
@@ -136,17 +369,184 @@ impl FilterBuilder {
     /// cbf.remove(b"hello");
     /// assert_eq!(cbf.contains(b"hello"), false);
     ///
-    /// // enable_repeat_insert is false
-    /// builder.enable_repeat_insert(false);
-    /// let mut cbf = builder.build_counting_bloom_filter();
+    /// // enable_repeat_insert is false, setters return the builder for chaining.
+    /// let mut cbf = builder.enable_repeat_insert(false).build_counting_bloom_filter();
     /// cbf.add(b"hello"); // modify underlying vector counter.
     /// cbf.add(b"hello"); // not modify underlying vector counter because b"hello" has been added.
     /// assert_eq!(cbf.contains(b"hello"), true);
     /// cbf.remove(b"hello");
     /// assert_eq!(cbf.contains(b"hello"), false);
     /// ```
-    pub fn enable_repeat_insert(&mut self, enable: bool) {
+    pub fn enable_repeat_insert(&mut self, enable: bool) -> &mut Self {
         self.enable_repeat_insert = enable;
+        self
+    }
+
+    /// Use for BloomFilter. Remember which words of the underlying bit vector have been written
+    /// so that [BloomFilter::clear_dirty] only zeroes those words instead of the whole vector.
+    /// This is useful for huge, short-lived filters that only ever receive a few elements.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000_000, 0.01);
+    /// builder.enable_dirty_tracking(true);
+    /// let mut bloom = builder.build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// bloom.clear_dirty(); // only zeroes the words touched by b"hello".
+    /// assert_eq!(bloom.contains(b"hello"), false);
+    /// ```
+    pub fn enable_dirty_tracking(&mut self, enable: bool) -> &mut Self {
+        self.enable_dirty_tracking = enable;
+        self
+    }
+
+    /// Use for BloomFilter. Allocate a second bit vector of the same size in which `add` sets the
+    /// bits of elements that were already present, so that [BloomFilter::seen_multiple] tells
+    /// whether an element was added more than once. This doubles the memory but is cheaper than a
+    /// [CountingBloomFilter] when only "seen twice" matters. Like membership it is subject to false
+    /// positives: an element whose bits were all set by others is reported as seen twice on its
+    /// first insert.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 0.01);
+    /// builder.enable_duplicate_tracking(true);
+    /// let mut bloom = builder.build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(!bloom.seen_multiple(b"hello"));
+    /// bloom.add(b"hello");
+    /// assert!(bloom.seen_multiple(b"hello"));
+    /// ```
+    pub fn enable_duplicate_tracking(&mut self, enable: bool) -> &mut Self {
+        self.enable_duplicate_tracking = enable;
+        self
+    }
+
+    /// Round the size inferred from `expected_elements` and `false_positive_probability` up to the
+    /// next power of two, so that hashes are reduced into the bit vector with a mask instead of a
+    /// modulo. The number of hashes is still chosen for the unrounded size, so the false positive
+    /// probability can only get better, at the cost of up to twice the memory (on average about
+    /// 1.5 times). Sizes given explicitly via [FilterBuilder::from_size_and_hashes] are kept as is;
+    /// pass a power of two there to get the same effect.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 0.01);
+    /// builder.power_of_two_size(true);
+    /// let bloom = builder.build_bloom_filter();
+    /// assert!(bloom.config().size.is_power_of_two());
+    /// ```
+    pub fn power_of_two_size(&mut self, enable: bool) -> &mut Self {
+        self.power_of_two_size = enable;
+        self
+    }
+
+    /// Round the size inferred from `expected_elements` and `false_positive_probability` up to a
+    /// multiple of `bits`, e.g. `32768` for a filter filling whole 4096 byte pages of a memory map.
+    /// This is applied after [FilterBuilder::power_of_two_size] and, like it, keeps the number of
+    /// hashes chosen for the unrounded size and leaves sizes given explicitly via
+    /// [FilterBuilder::from_size_and_hashes] as is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is zero or not a multiple of the word size.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 0.01);
+    /// builder.align_size_to(32768);
+    /// let bloom = builder.build_bloom_filter();
+    /// assert_eq!(bloom.config().size % 32768, 0);
+    /// ```
+    pub fn align_size_to(&mut self, bits: u64) -> &mut Self {
+        assert!(bits > 0 && bits & SUFFIX as u64 == 0, "bits must be a multiple of the word size!");
+        self.align_size_to = bits;
+        self
+    }
+
+    /// Use for BloomFilter. Choose the width of the hash the indices of an element are derived
+    /// from. The default [HashWidth::Bits64] reduces two 64 bit hashes mod size before combining
+    /// them, so the probes of an element collapse when the second hash is a multiple of the
+    /// size. This is negligible for usual false positive probabilities but dominates for very low
+    /// ones such as `1e-12`, where [HashWidth::Bits128] keeps the empirical rate near the target
+    /// at the cost of slower hashing. Filters using different widths are not compatible.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, HashWidth, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 1e-12);
+    /// builder.hash_width(HashWidth::Bits128);
+    /// let mut bloom = builder.build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn hash_width(&mut self, width: HashWidth) -> &mut Self {
+        self.hash_width = width;
+        self
+    }
+
+    /// Set the seeds of the two xxh3 hashes the indices of an element are derived from, `(0, 32)`
+    /// by default. Filters built with the same seeds and sizes by another xxh3 implementation can
+    /// then be queried here and vice versa. The seeds apply to [HashWidth::Bits64] and counting
+    /// filters and are kept when the filter is serialized with serde or
+    /// [BloomFilter::to_u8_array_with_header], but not in the headerless byte and file formats.
+    /// Filters using different seeds are not compatible.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 0.01);
+    /// builder.hash_seeds(42, 4242);
+    /// let mut bloom = builder.build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    pub fn hash_seeds(&mut self, s1: u64, s2: u64) -> &mut Self {
+        self.hash_seeds = (s1, s2);
+        self
+    }
+
+    /// Draw a random seed with `getrandom` and use `seed` and `seed + 32` as
+    /// [FilterBuilder::hash_seeds], like [crate::hashing::hash_pair] does. Filters built this way
+    /// hash independently, so they do not share false positives. The seed is kept by
+    /// [FilterBuilder::seed] and serialized with the filter, which is needed to reload it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system fails to provide random bytes.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut builder = FilterBuilder::new(100_000, 0.01);
+    /// builder.randomize_seed();
+    /// let mut bloom = builder.build_bloom_filter();
+    /// bloom.add(b"hello");
+    /// assert!(bloom.contains(b"hello"));
+    /// ```
+    #[cfg(feature = "getrandom")]
+    pub fn randomize_seed(&mut self) -> &mut Self {
+        let mut bytes = [0u8; 8];
+        getrandom::getrandom(&mut bytes).expect("failed to draw a random seed!");
+        let seed = u64::from_le_bytes(bytes);
+        self.hash_seeds(seed, seed.wrapping_add(32))
+    }
+
+    /// Returns the seed of the first hash, `0` unless set by [FilterBuilder::randomize_seed] or
+    /// [FilterBuilder::hash_seeds].
+    pub fn seed(&self) -> u64 {
+        self.hash_seeds.0
     }
 
     /// set  the size of the bloom filter in bits.
@@ -164,29 +564,311 @@ impl FilterBuilder {
             if self.size == 0 {
                 self.size = optimal_m(self.expected_elements, self.false_positive_probability);
                 self.hashes = optimal_k(self.expected_elements, self.size);
+                if self.power_of_two_size {
+                    self.size = self.size.next_power_of_two();
+                }
+                if self.align_size_to > 0 {
+                    self.size = self.size.next_multiple_of(self.align_size_to);
+                }
             }
             self.done = true;
         }
     }
 
+    /// Returns a configuration at least as capable as both `self` and `other`: a filter built from
+    /// it holds the larger expected elements at no more than the smaller false positive
+    /// probability, and is never smaller than either configuration's size. The size and hashes
+    /// are inferred for that load, so the hashes are optimal for it rather than the larger of the
+    /// two, since extra hashes do not make a filter safer. All other options are taken from
+    /// `self`, including [FilterBuilder::power_of_two_size] and [FilterBuilder::align_size_to].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let merged = FilterBuilder::new(1_000_000, 0.01)
+    ///     .merge_conservative(&FilterBuilder::new(100_000, 0.001));
+    /// assert_eq!(merged.expected_elements, 1_000_000);
+    /// assert_eq!(merged.false_positive_probability, 0.001);
+    /// ```
+    pub fn merge_conservative(&self, other: &FilterBuilder) -> FilterBuilder {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        a.complete();
+        b.complete();
+        let mut merged = self.clone();
+        merged.expected_elements = a.expected_elements.max(b.expected_elements);
+        merged.false_positive_probability =
+            a.false_positive_probability.min(b.false_positive_probability);
+        merged.size = 0;
+        merged.hashes = 0;
+        merged.done = false;
+        merged.complete();
+        merged.size = merged.size.max(a.size).max(b.size);
+        merged
+    }
+
+    /// Checks all parameters without building a filter and returns every problem found, rather
+    /// than only the first one. When size and hashes still have to be inferred, they are checked
+    /// as they would be inferred from the expected elements and false positive probability.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{ConfigIssue, FilterBuilder};
+    ///
+    /// assert!(FilterBuilder::new(100_000, 0.01).validate().is_ok());
+    /// let issues = FilterBuilder::new(0, 2.0).validate().unwrap_err();
+    /// assert_eq!(issues, vec![ConfigIssue::ZeroExpectedElements,
+    ///                         ConfigIssue::FalsePositiveProbabilityOutOfRange(2.0)]);
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<ConfigIssue>> {
+        let mut issues = Vec::new();
+        let mut config = self.clone();
+        if !config.done && config.size == 0 {
+            if config.expected_elements == 0 {
+                issues.push(ConfigIssue::ZeroExpectedElements);
+            }
+            let p = config.false_positive_probability;
+            if !(p > 0.0 && p < 1.0) {
+                issues.push(ConfigIssue::FalsePositiveProbabilityOutOfRange(p));
+            }
+            if !issues.is_empty() {
+                return Err(issues);
+            }
+        }
+        config.complete();
+        if config.size == 0 {
+            issues.push(ConfigIssue::ZeroSize);
+        } else if config.size & SUFFIX as u64 != 0 {
+            issues.push(ConfigIssue::SizeNotWordAligned(config.size));
+        }
+        if config.hashes == 0 {
+            issues.push(ConfigIssue::ZeroHashes);
+        } else if config.hashes > MAX_HASHES {
+            issues.push(ConfigIssue::TooManyHashes { hashes: config.hashes, max: MAX_HASHES });
+        }
+        if issues.is_empty() { Ok(()) } else { Err(issues) }
+    }
+
+    /// Panics with the first issue reported by [FilterBuilder::validate], if any.
+    fn assert_valid(&self) {
+        if let Err(issues) = self.validate() {
+            panic!("{}", issues[0]);
+        }
+    }
+
+    /// Returns the number of bytes a Bloom filter built from this builder allocates, one bit per
+    /// slot of the size.
+    pub fn bloom_memory_bytes(&self) -> u64 {
+        self.final_size() / usize::BITS as u64 * (usize::BITS / 8) as u64
+    }
+
+    /// Returns the number of bytes a Counting Bloom filter built from this builder allocates. Each
+    /// slot of the size is a 4 bit counter, 4x the memory of [FilterBuilder::bloom_memory_bytes]:
+    /// size a counting filter for 100M elements by this rather than by the bit count.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let builder = FilterBuilder::new(100_000_000, 0.01);
+    /// assert_eq!(builder.counting_memory_bytes(), 4 * builder.bloom_memory_bytes());
+    /// ```
+    pub fn counting_memory_bytes(&self) -> u64 {
+        self.final_size() / (usize::BITS / 4) as u64 * (usize::BITS / 8) as u64
+    }
+
+    /// Returns the size in bits of the filters built from this builder, i.e. the optimal size
+    /// rounded up to whole words (and to [FilterBuilder::power_of_two_size] or
+    /// [FilterBuilder::align_size_to] if set), without building a filter or completing the
+    /// builder itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut builder = FilterBuilder::new(100_000_000, 0.01);
+    /// let size = builder.final_size();
+    /// assert_eq!(size % 64, 0);
+    /// assert_eq!(builder.build_bloom_filter().config().size, size);
+    /// ```
+    pub fn final_size(&self) -> u64 {
+        self.completed().size
+    }
+
+    /// Returns the number of hashes of the filters built from this builder, without building a
+    /// filter or completing the builder itself.
+    pub fn final_hashes(&self) -> u32 {
+        self.completed().hashes
+    }
+
+    fn completed(&self) -> FilterBuilder {
+        let mut config = self.clone();
+        config.complete();
+        config
+    }
+
     /// Constructs a Bloom filter using the specified parameters and computing missing parameters
     /// if possible (e.g. the optimal Bloom filter bit size).
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::validate], see
+    /// [FilterBuilder::try_build_bloom_filter] for a fallible version.
     pub fn build_bloom_filter(&mut self) -> BloomFilter {
+        self.try_build_bloom_filter().unwrap_or_else(|issues| panic!("{}", issues[0]))
+    }
+
+    /// Constructs a Bloom filter like [FilterBuilder::build_bloom_filter], but returns every issue
+    /// reported by [FilterBuilder::validate] instead of panicking on an invalid configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// assert!(FilterBuilder::new(100_000, 0.01).try_build_bloom_filter().is_ok());
+    /// assert!(FilterBuilder::new(0, 2.0).try_build_bloom_filter().is_err());
+    /// ```
+    pub fn try_build_bloom_filter(&mut self) -> Result<BloomFilter, Vec<ConfigIssue>> {
+        self.validate()?;
         self.complete();
-        BloomFilter::new(self.clone())
+        Ok(BloomFilter::new(self.clone()))
     }
 
     /// Constructs a Counting Bloom filter using the specified parameters and computing missing parameters
     /// if possible (e.g. the optimal Bloom filter bit size).
+    ///
+    /// The size is counted in slots as for a Bloom filter, but each slot is a 4 bit counter, so
+    /// the filter takes 4x the memory of a Bloom filter from the same builder, see
+    /// [FilterBuilder::counting_memory_bytes].
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::try_build_counting_bloom_filter],
+    /// its fallible version.
     pub fn build_counting_bloom_filter(&mut self) -> CountingBloomFilter {
+        self.try_build_counting_bloom_filter().unwrap_or_else(|issues| panic!("{}", issues[0]))
+    }
+
+    /// Constructs a Counting Bloom filter like [FilterBuilder::build_counting_bloom_filter], but
+    /// returns every issue reported by [FilterBuilder::validate] instead of panicking on an invalid
+    /// configuration. Counting Bloom filters always hash with [HashWidth::Bits64], so any other
+    /// [FilterBuilder::hash_width] is reported as [ConfigIssue::UnsupportedHashWidth].
+    pub fn try_build_counting_bloom_filter(&mut self) -> Result<CountingBloomFilter, Vec<ConfigIssue>> {
+        let mut issues = self.validate().err().unwrap_or_default();
+        if self.hash_width != HashWidth::Bits64 {
+            issues.push(ConfigIssue::UnsupportedHashWidth(self.hash_width));
+        }
+        if !issues.is_empty() {
+            return Err(issues);
+        }
         self.complete();
-        CountingBloomFilter::new(self.clone())
+        Ok(CountingBloomFilter::new(self.clone()))
+    }
+
+    /// Constructs the filter suited to the use case, for users unsure which one they need: a
+    /// [CountingBloomFilter] if elements have to be removed, a [BloomFilter] otherwise. Reach the
+    /// removal of the returned filter through [Membership::as_deletable].
+    ///
+    /// Scalable and blocked filters are not implemented yet, so `_unknown_size` does not change
+    /// the choice: the filter is sized for `expected_elements` and its false positive
+    /// probability rises once more elements are added.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::validate].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::FilterBuilder;
+    ///
+    /// let mut filter = FilterBuilder::new(100_000, 0.01).build_auto(true, false);
+    /// filter.add(b"hello");
+    /// filter.as_deletable().unwrap().remove(b"hello");
+    /// assert!(!filter.contains(b"hello"));
+    /// ```
+    pub fn build_auto(&mut self, needs_removal: bool, _unknown_size: bool) -> Box<dyn Membership> {
+        if needs_removal {
+            Box::new(self.build_counting_bloom_filter())
+        } else {
+            Box::new(self.build_bloom_filter())
+        }
+    }
+
+    /// Constructs a [SmallMembership], which stores up to `small_threshold` elements exactly and
+    /// then switches to a Bloom filter using the specified parameters, for sets that are often
+    /// small but may grow large.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::validate].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut set = FilterBuilder::new(100_000, 0.01).build_adaptive(64);
+    /// set.add(b"hello");
+    /// assert!(set.is_exact());
+    /// assert!(set.contains(b"hello"));
+    /// ```
+    pub fn build_adaptive(&mut self, small_threshold: usize) -> SmallMembership {
+        self.assert_valid();
+        self.complete();
+        SmallMembership::new(self.clone(), small_threshold)
+    }
+
+    /// Constructs a Rolling Bloom filter of `windows` Bloom filters using the specified parameters
+    /// and computing missing parameters if possible (e.g. the optimal Bloom filter bit size).
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first issue reported by [FilterBuilder::validate].
+    pub fn build_rolling_bloom_filter(&mut self, windows: usize) -> RollingBloomFilter {
+        self.assert_valid();
+        self.complete();
+        RollingBloomFilter::new(self.clone(), windows)
     }
 
     /// Checks whether a configuration is compatible to another configuration based on the size of
     /// the Bloom filter and its hash functions.
     pub(crate) fn is_compatible_to(&self, other: &FilterBuilder) -> bool {
-        self.size == other.size && self.hashes == other.hashes
+        self.size == other.size && self.hashes == other.hashes && self.hash_width == other.hash_width
+            && self.hash_seeds == other.hash_seeds
+    }
+}
+
+/// The fields that make two builders equal, see the [PartialEq] impl of [FilterBuilder].
+type BuilderKey = (u64, u64, u64, u32, bool, bool, bool, bool, u64, HashWidth, (u64, u64));
+
+impl FilterBuilder {
+    fn key(&self) -> BuilderKey {
+        (self.expected_elements, self.false_positive_probability.to_bits(), self.size, self.hashes,
+         self.enable_repeat_insert, self.enable_dirty_tracking, self.enable_duplicate_tracking,
+         self.power_of_two_size, self.align_size_to, self.hash_width, self.hash_seeds)
+    }
+}
+
+/// Builders are equal when all their options are, comparing the false positive probability by
+/// its bit pattern so that equality is consistent with [Hash]. A builder whose size and hashes
+/// are still to be inferred has a zero size, so it differs from the same builder after a build.
+impl PartialEq for FilterBuilder {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for FilterBuilder {}
+
+impl Hash for FilterBuilder {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
     }
 }
 
@@ -208,4 +890,272 @@ fn builder_test() {
     bloom.add(b"helloworld");
     assert_eq!(bloom.contains(b"helloworld"), true);
     assert_eq!(bloom.contains(b"helloworld!"), false);
-}
\ No newline at end of file
+}
+
+#[test]
+fn power_of_two_size_test() {
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    builder.power_of_two_size(true);
+    builder.complete();
+    let optimal = optimal_m(100_000, 0.01);
+    assert!(builder.size.is_power_of_two());
+    assert!(builder.size >= optimal && builder.size < optimal * 2);
+    assert_eq!(builder.hashes, optimal_k(100_000, optimal));
+}
+
+#[test]
+fn align_size_to_test() {
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    builder.align_size_to(32768);
+    builder.complete();
+    let optimal = optimal_m(100_000, 0.01);
+    assert_eq!(builder.size % 32768, 0);
+    assert!(builder.size >= optimal && builder.size < optimal + 32768);
+    assert_eq!(builder.hashes, optimal_k(100_000, optimal));
+
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    builder.power_of_two_size(true);
+    builder.align_size_to(3 * 32768);
+    builder.complete();
+    assert_eq!(builder.size % (3 * 32768), 0);
+    assert!(builder.size >= optimal.next_power_of_two());
+}
+
+#[test]
+#[should_panic(expected = "bits must be a multiple of the word size!")]
+fn align_size_to_unaligned_test() {
+    FilterBuilder::new(100_000, 0.01).align_size_to(100);
+}
+
+#[test]
+fn validate_test() {
+    assert_eq!(FilterBuilder::new(100_000, 0.01).validate(), Ok(()));
+    assert_eq!(FilterBuilder::from_size_and_hashes(1 << 20, 7).validate(), Ok(()));
+
+    assert_eq!(FilterBuilder::new(0, 0.0).validate(),
+               Err(vec![ConfigIssue::ZeroExpectedElements,
+                        ConfigIssue::FalsePositiveProbabilityOutOfRange(0.0)]));
+    assert_eq!(FilterBuilder::new(100_000, f64::NAN).validate().unwrap_err().len(), 1);
+    assert_eq!(FilterBuilder::new(100, 1e-300).validate(),
+               Err(vec![ConfigIssue::TooManyHashes { hashes: 997, max: MAX_HASHES }]));
+
+    let builder = FilterBuilder::from_size_and_hashes(100, 0);
+    assert_eq!(builder.validate(),
+               Err(vec![ConfigIssue::SizeNotWordAligned(100), ConfigIssue::ZeroHashes]));
+    let builder = FilterBuilder::from_size_and_hashes(0, 200);
+    assert_eq!(builder.validate(),
+               Err(vec![ConfigIssue::ZeroSize,
+                        ConfigIssue::TooManyHashes { hashes: 200, max: MAX_HASHES }]));
+}
+
+#[test]
+#[should_panic(expected = "hashes must larger than 0")]
+fn build_invalid_test() {
+    FilterBuilder::from_size_and_hashes(1 << 10, 0).build_bloom_filter();
+}
+
+#[test]
+fn try_build_test() {
+    assert_eq!(FilterBuilder::new(0, 2.0).try_build_bloom_filter().unwrap_err(),
+               vec![ConfigIssue::ZeroExpectedElements,
+                    ConfigIssue::FalsePositiveProbabilityOutOfRange(2.0)]);
+    assert_eq!(FilterBuilder::from_size_and_hashes(1 << 10, 0).try_build_counting_bloom_filter()
+                   .unwrap_err(), vec![ConfigIssue::ZeroHashes]);
+
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    let bloom = builder.try_build_bloom_filter().unwrap();
+    assert_eq!(bloom.config().size, builder.size);
+    assert!(builder.try_build_counting_bloom_filter().is_ok());
+}
+
+#[test]
+fn from_fpr_and_hashes_test() {
+    let builder = FilterBuilder::from_fpr_and_hashes(100_000, 0.01, 4);
+    assert_eq!(builder.hashes, 4);
+    assert_eq!(builder.size % 64, 0);
+    let p = optimal_p(builder.hashes, builder.size, builder.expected_elements);
+    assert!(p <= 0.01 && p > 0.0099, "{p}");
+    // a non-optimal k needs more bits than the optimal one.
+    assert!(builder.size > optimal_m(100_000, 0.01));
+
+    let mut bloom = FilterBuilder::from_fpr_and_hashes(10_000, 0.01, 3).build_bloom_filter();
+    for x in 0..10_000u64 {
+        bloom.add(&x.to_le_bytes());
+    }
+    let errors = (10_000..110_000u64).filter(|x| bloom.contains(&x.to_le_bytes())).count();
+    assert!(errors < 1_300, "{errors}");
+}
+
+#[test]
+fn builder_hash_map_key_test() {
+    use std::collections::HashMap;
+
+    let mut cache = HashMap::new();
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    let mut bloom = builder.build_bloom_filter();
+    bloom.add(b"hello");
+    cache.insert(builder.clone(), bloom);
+
+    let mut equal = FilterBuilder::new(10_000, 0.01);
+    equal.complete();
+    assert_eq!(equal, builder);
+    assert!(cache.get(&equal).unwrap().contains(b"hello"));
+
+    let mut seeded = equal.clone();
+    seeded.hash_seeds(1, 2);
+    assert_ne!(seeded, builder);
+    assert!(!cache.contains_key(&seeded));
+    assert_ne!(FilterBuilder::new(10_000, 0.02), FilterBuilder::new(10_000, 0.01));
+    assert_eq!(FilterBuilder::new(10_000, f64::NAN), FilterBuilder::new(10_000, f64::NAN));
+}
+
+#[test]
+fn merge_conservative_test() {
+    let small = FilterBuilder::new(1_000_000, 0.01);
+    let large = FilterBuilder::new(10_000_000, 0.001);
+    for merged in [small.merge_conservative(&large), large.merge_conservative(&small)] {
+        assert_eq!(merged.expected_elements, 10_000_000);
+        assert_eq!(merged.false_positive_probability, 0.001);
+        assert_eq!(merged.size, optimal_m(10_000_000, 0.001));
+        assert_eq!(merged.hashes, optimal_k(10_000_000, merged.size));
+    }
+
+    // neither dominates: the merged load needs more bits than both.
+    let merged = FilterBuilder::new(10_000_000, 0.01)
+        .merge_conservative(&FilterBuilder::new(1_000_000, 0.0001));
+    assert_eq!((merged.expected_elements, merged.false_positive_probability), (10_000_000, 0.0001));
+    assert_eq!((merged.size, merged.hashes),
+               (optimal_m(10_000_000, 0.0001), optimal_k(10_000_000, merged.size)));
+
+    // a builder from size and hashes takes part with the load it was inferred to be optimal for.
+    let sized = FilterBuilder::from_size_and_hashes(1 << 24, 7);
+    let merged = small.merge_conservative(&sized);
+    assert!(merged.size >= 1 << 24);
+    assert!(merged.false_positive_probability <= sized.false_positive_probability);
+    assert!(merged.validate().is_ok());
+}
+
+#[test]
+fn for_cardinality_test() {
+    for (n, error) in [(1000, 0.05), (100_000, 0.01), (1_000_000, 0.005)] {
+        let builder = FilterBuilder::for_cardinality(n, error);
+        assert_eq!(builder.hashes, 1);
+        assert_eq!(builder.size & SUFFIX as u64, 0);
+        let mut bloom = BloomFilter::new(builder);
+        for x in 0..n {
+            bloom.add(&x.to_le_bytes());
+        }
+        let estimate = bloom.estimate_set_cardinality();
+        assert!((estimate - n as f64).abs() <= error * n as f64, "{n} {error} {estimate}");
+    }
+}
+
+#[test]
+fn build_auto_test() {
+    let mut builder = FilterBuilder::new(100_000, 0.01);
+    let mut removable = builder.build_auto(true, false);
+    removable.add(b"hello");
+    assert!(removable.contains(b"hello"));
+    removable.as_deletable().expect("a filter supporting removal").remove(b"hello");
+    assert!(!removable.contains(b"hello"));
+
+    for unknown_size in [false, true] {
+        let mut filter = builder.build_auto(false, unknown_size);
+        filter.add(b"hello");
+        assert!(filter.contains(b"hello"));
+        assert!(filter.as_deletable().is_none());
+    }
+}
+
+#[test]
+fn from_sample_test() {
+    let sample: Vec<[u8; 8]> = (0..20_000u64).map(|x| (x % 2_000).to_le_bytes()).collect();
+    let mut builder = FilterBuilder::from_sample(&sample, 0.01, 0.01);
+    let estimated = builder.expected_elements;
+    assert!((180_000..=220_000).contains(&estimated), "{estimated}");
+    let bloom = builder.build_bloom_filter();
+    assert_eq!(bloom.config().expected_elements, estimated);
+    assert_eq!(bloom.config().false_positive_probability, 0.01);
+
+    assert_eq!(FilterBuilder::from_sample(&Vec::<Vec<u8>>::new(), 0.5, 0.01).expected_elements, 1);
+}
+
+#[test]
+fn memory_bytes_test() {
+    for mut builder in [FilterBuilder::new(100_000, 0.01),
+                        FilterBuilder::from_size_and_hashes(1 << 20, 3),
+                        FilterBuilder::new(1_000, 0.0001)] {
+        let bloom_bytes = builder.bloom_memory_bytes();
+        let counting_bytes = builder.counting_memory_bytes();
+        assert_eq!(builder.build_bloom_filter().get_u8_array().len() as u64, bloom_bytes);
+        let cbf = builder.build_counting_bloom_filter();
+        assert_eq!(cbf.get_u8_array().len() as u64, counting_bytes);
+        assert_eq!(counting_bytes, 4 * bloom_bytes);
+    }
+}
+
+#[test]
+fn counting_hash_width_test() {
+    let mut builder = FilterBuilder::new(1_000, 0.01);
+    builder.hash_width(HashWidth::Bits128);
+    assert_eq!(builder.try_build_counting_bloom_filter().unwrap_err(),
+               vec![ConfigIssue::UnsupportedHashWidth(HashWidth::Bits128)]);
+    assert!(builder.try_build_bloom_filter().is_ok());
+    builder.hash_width(HashWidth::Bits64);
+    assert!(builder.try_build_counting_bloom_filter().is_ok());
+}
+
+#[test]
+fn final_size_test() {
+    let mut power_of_two = FilterBuilder::new(100_000, 0.01);
+    power_of_two.power_of_two_size(true);
+    let mut aligned = FilterBuilder::new(100_000, 0.01);
+    aligned.align_size_to(4096);
+    for mut builder in [FilterBuilder::new(100_000, 0.01),
+                        FilterBuilder::new(1, 0.5),
+                        FilterBuilder::from_size_and_hashes(1 << 20, 3),
+                        power_of_two,
+                        aligned] {
+        let before = builder.clone();
+        let (size, hashes) = (builder.final_size(), builder.final_hashes());
+        assert_eq!(builder, before);
+        let config = builder.build_bloom_filter().config();
+        assert_eq!(config.size, size);
+        assert_eq!(config.hashes, hashes);
+    }
+}
+
+#[test]
+fn chained_setters_test() {
+    use crate::Deletable;
+
+    let mut cbf = FilterBuilder::new(10_000, 0.01)
+        .enable_repeat_insert(false)
+        .hash_seeds(7, 39)
+        .power_of_two_size(true)
+        .build_counting_bloom_filter();
+    assert_eq!(cbf.config().hash_seeds, (7, 39));
+    assert!(cbf.config().size.is_power_of_two());
+    assert!(!cbf.config().enable_repeat_insert);
+    cbf.add(b"hello");
+    cbf.add(b"hello");
+    cbf.remove(b"hello");
+    assert!(!cbf.contains(b"hello"));
+
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    builder.enable_repeat_insert(false);
+    assert_eq!(&builder, FilterBuilder::new(10_000, 0.01).enable_repeat_insert(false));
+}
+
+#[test]
+fn optimally_build_from_test() {
+    let elements: Vec<String> = (0..1000).map(|x| x.to_string()).collect();
+    let bloom = FilterBuilder::optimally_build_from(0.01, &elements);
+    let config = bloom.config();
+    assert_eq!(config.expected_elements, 1000);
+    assert_eq!(config, FilterBuilder::new(1000, 0.01).build_bloom_filter().config());
+    assert!(elements.iter().all(|x| bloom.contains(x.as_bytes())));
+
+    let empty = FilterBuilder::optimally_build_from(0.01, Vec::<Vec<u8>>::new());
+    assert_eq!(empty.config().expected_elements, 1);
+}
@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter};
+
+/// Errors returned by fallible filter operations.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum FilterError {
+    /// The operation needs at least one filter.
+    NoFilters,
+    /// The filters do not use the same number of hashes.
+    HashesMismatch { expected: u32, found: u32 },
+    /// The filters do not have the same size in bits.
+    SizeMismatch { expected: u64, found: u64 },
+    /// A filter needs at least one bit.
+    ZeroSize,
+    /// A filter needs at least one hash.
+    ZeroHashes,
+    /// The bytes do not have the length their header implies.
+    LengthMismatch { expected: usize, found: usize },
+    /// The counters are not stored with a supported width in bits.
+    UnsupportedCounterWidth(u8),
+    /// The number of counters does not fill whole native slots.
+    SizeNotSlotAligned { size: u64, counters_per_slot: u64 },
+    /// The first byte of an export does not name a known [crate::ExportFormat].
+    UnknownExportFormat(u8),
+    /// The bytes are not a valid encoding, with the reason.
+    InvalidEncoding(&'static str),
+    /// The filters do not use the same hash width and seeds.
+    HashConfigMismatch,
+    /// The length of the bytes is not a multiple of 8, so they do not hold whole 64 bit words.
+    LengthNotWordAligned(usize),
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterError::NoFilters => write!(f, "at least one filter is required"),
+            FilterError::HashesMismatch { expected, found } =>
+                write!(f, "filters must use the same hashes, expected {expected} but found {found}"),
+            FilterError::SizeMismatch { expected, found } =>
+                write!(f, "filters must have the same size, expected {expected} bits but found {found}"),
+            FilterError::ZeroSize => write!(f, "size must larger than 0"),
+            FilterError::ZeroHashes => write!(f, "hashes must larger than 0"),
+            FilterError::LengthMismatch { expected, found } =>
+                write!(f, "bytes must be {expected} long but found {found}"),
+            FilterError::UnsupportedCounterWidth(width) =>
+                write!(f, "counters must be 4 bits wide, found {width}"),
+            FilterError::SizeNotSlotAligned { size, counters_per_slot } =>
+                write!(f, "size must be a multiple of {counters_per_slot} counters, found {size}"),
+            FilterError::UnknownExportFormat(tag) => write!(f, "unknown export format {tag}"),
+            FilterError::InvalidEncoding(reason) => write!(f, "invalid encoding: {reason}"),
+            FilterError::HashConfigMismatch =>
+                write!(f, "filters must use the same hash width and seeds"),
+            FilterError::LengthNotWordAligned(len) =>
+                write!(f, "bytes must hold whole 64 bit words, found {len} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// A problem with the parameters of a [crate::FilterBuilder], see
+/// [crate::FilterBuilder::validate].
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum ConfigIssue {
+    /// The expected number of elements is zero.
+    ZeroExpectedElements,
+    /// The false positive probability is not in `(0.0, 1.0)`.
+    FalsePositiveProbabilityOutOfRange(f64),
+    /// The size in bits is zero.
+    ZeroSize,
+    /// The size in bits is not a multiple of the word size.
+    SizeNotWordAligned(u64),
+    /// The number of hashes is zero.
+    ZeroHashes,
+    /// The number of hashes exceeds [crate::MAX_HASHES].
+    TooManyHashes { hashes: u32, max: u32 },
+    /// The filter only supports [crate::HashWidth::Bits64], e.g. a
+    /// [crate::CountingBloomFilter].
+    UnsupportedHashWidth(crate::HashWidth),
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigIssue::ZeroExpectedElements => write!(f, "expected_elements must larger than 0"),
+            ConfigIssue::FalsePositiveProbabilityOutOfRange(p) =>
+                write!(f, "false_positive_probability must between (0.0, 1.0), found {p}"),
+            ConfigIssue::ZeroSize => write!(f, "size must larger than 0"),
+            ConfigIssue::SizeNotWordAligned(size) =>
+                write!(f, "size must be a multiple of the word size, found {size} bits"),
+            ConfigIssue::ZeroHashes => write!(f, "hashes must larger than 0"),
+            ConfigIssue::TooManyHashes { hashes, max } =>
+                write!(f, "hashes must not larger than {max}, found {hashes}"),
+            ConfigIssue::UnsupportedHashWidth(width) =>
+                write!(f, "hash_width must be Bits64 for this filter, found {width:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigIssue {}
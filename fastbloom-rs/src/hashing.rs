@@ -0,0 +1,233 @@
+use fastmurmur3::murmur3_x64_128;
+use smallvec::SmallVec;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+use crate::HashWidth;
+
+/// Returns the two xxh3 hashes of `element` that the bit indices are derived from, seeded with
+/// `seed` and `seed + 32`. The filters use `seed = 0` unless configured otherwise with
+/// [crate::FilterBuilder::hash_seeds].
+#[inline]
+pub fn hash_pair(element: &[u8], seed: u64) -> (u64, u64) {
+    (xxh3_64_with_seed(element, seed), xxh3_64_with_seed(element, seed.wrapping_add(32)))
+}
+
+/// Returns `xxh3_64_with_seed(&value.to_le_bytes(), seed)` by xxh3's path for 4 to 8 byte inputs
+/// specialized to exactly 8 bytes, skipping the dispatch on the length of a slice.
+#[inline(always)]
+pub(crate) fn xxh3_64_u64(value: u64, seed: u64) -> u64 {
+    // bytes 8..16 and 16..24 of the default xxh3 secret, read as little-endian u64.
+    const FLIP: u64 = 0x1cad_21f7_2c81_017c ^ 0xdb97_9083_e96d_d4de;
+    const PRIME: u64 = 0x9fb2_1c65_1e98_df25;
+    let seed = seed ^ (((seed as u32).swap_bytes() as u64) << 32);
+    // the first 4 bytes in the high half and the last 4 in the low half.
+    let keyed = value.rotate_left(32) ^ FLIP.wrapping_sub(seed);
+    let mut hash = keyed ^ keyed.rotate_left(49) ^ keyed.rotate_left(24);
+    hash = hash.wrapping_mul(PRIME);
+    hash ^= (hash >> 35).wrapping_add(8);
+    hash = hash.wrapping_mul(PRIME);
+    hash ^ (hash >> 28)
+}
+
+/// Returns the `hashes` bit indices of `element` in a filter of `size` bits, the same indices
+/// [crate::Membership::get_hash_indices] returns for a [crate::BloomFilter] with the default
+/// [crate::HashWidth::Bits64] when `seed = 0`. This allows computing indices without a filter
+/// instance, e.g. to ship them to the node owning the filter.
+///
+/// # Examples
+///
+/// ```rust
+/// use fastbloom_rs::{FilterBuilder, Membership};
+/// use fastbloom_rs::hashing::indices;
+///
+/// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+/// bloom.add(b"hello");
+/// let config = bloom.config();
+/// let indices = indices(b"hello", config.size, config.hashes, 0);
+/// assert!(bloom.contains_hash_indices(&indices));
+/// ```
+pub fn indices(element: &[u8], size: u64, hashes: u32, seed: u64) -> SmallVec<[u64; 8]> {
+    seeded_indices(element, size, hashes, (seed, seed.wrapping_add(32)))
+}
+
+/// Like [indices], with the two xxh3 seeds given separately as in
+/// [crate::FilterBuilder::hash_seeds].
+pub(crate) fn seeded_indices(element: &[u8], size: u64, hashes: u32, seeds: (u64, u64))
+                             -> SmallVec<[u64; 8]> {
+    probes(element, size, hashes as u64, seeds).collect()
+}
+
+/// Returns the `k` probes of `element` in `[0, m)`, `start + i * step (mod m)` for `i` in `0..k`
+/// with `(start, step)` from [start_and_step]. Every probe, the first one included, comes from
+/// this one sequence, so all filters and operations treat the `k` probes alike. The indices are
+/// computed incrementally: both terms are below `m`, so a single conditional subtract keeps the
+/// running index in range without a modulo per probe.
+#[inline(always)]
+pub(crate) fn probes(element: &[u8], m: u64, k: u64, seeds: (u64, u64))
+                     -> impl Iterator<Item=u64> + Clone {
+    let (start, step) = start_and_step(element, m, seeds);
+    std::iter::successors(Some(start), move |index| Some(next_index(*index, step, m)))
+        .take(k as usize)
+}
+
+/// Like [probes], for the two hashes of an element already computed, e.g. by [hash_pair].
+#[inline(always)]
+pub(crate) fn pair_probes(pair: (u64, u64), m: u64, k: u64) -> impl Iterator<Item=u64> + Clone {
+    let (start, step) = pair_start_and_step(pair, m);
+    std::iter::successors(Some(start), move |index| Some(next_index(*index, step, m)))
+        .take(k as usize)
+}
+
+/// Returns the xxh3 hashes of `element` seeded with `seeds` reduced into `[0, m)`, the first
+/// index and the step between indices.
+///
+/// About one element in `m` gets a step of 0, which puts all its probes on the first index. This
+/// is deliberately left as is. [crate::BloomFilter::fold] and [crate::BloomFilter::union_resample]
+/// rely on the step mod a divisor `m'` of `m` being `hash2 mod m'`, so a guard keeping them
+/// lossless must change nonzero steps as well, e.g. forcing an odd step. That moves the bits of
+/// half the elements of every filter persisted so far, and forcing it for power of two sizes
+/// only breaks a union of a power of two size into a size it divides, such as 64 into 192.
+/// Filters where the collapse matters can use [crate::HashWidth::Bits128], see [indices_128].
+#[inline(always)]
+pub(crate) fn start_and_step(element: &[u8], m: u64, seeds: (u64, u64)) -> (u64, u64) {
+    let hash1 = xxh3_64_with_seed(element, seeds.0);
+    let hash2 = xxh3_64_with_seed(element, seeds.1);
+    pair_start_and_step((hash1, hash2), m)
+}
+
+/// The first index and the step of [start_and_step] from the two hashes of an element.
+#[inline(always)]
+pub(crate) fn pair_start_and_step((hash1, hash2): (u64, u64), m: u64) -> (u64, u64) {
+    (reduce(hash1, m), reduce(hash2, m))
+}
+
+/// Hash indices of `value` for [crate::HashWidth::Bits128]. The halves of a 128 bit murmur3 hash
+/// are combined by enhanced double hashing, `hash1 + i * hash2 + (i^3 - i) / 6`, in 128 bit
+/// arithmetic and only the result is reduced mod `m`, so a `hash2` that is a multiple of `m` does
+/// not collapse all probes onto a single bit.
+///
+/// For [crate::HashWidth::LegacyMurmur128] these are the indices of the murmur3 scheme older
+/// versions of the crate used: `hash1 = murmur3(value, 0) % m`, `hash2 = murmur3(value, 32) % m`
+/// and plain double hashing, `(hash1 + i * hash2) % m`.
+#[inline]
+pub(crate) fn indices_128(value: &[u8], m: u64, k: u64, width: HashWidth)
+                          -> impl Iterator<Item=u64> + Clone {
+    let (hash1, hash2, cubic) = if width == HashWidth::LegacyMurmur128 {
+        (murmur3_x64_128(value, 0) % m as u128, murmur3_x64_128(value, 32) % m as u128, 0)
+    } else {
+        let hash = murmur3_x64_128(value, 0);
+        (hash >> 64, hash & u64::MAX as u128, 1)
+    };
+    (0..k as u128).map(move |i| {
+        let index = hash1 + i * hash2 + cubic * (i * i * i - i) / 6;
+        (index % m as u128) as u64
+    })
+}
+
+/// Reduces a hash into `[0, m)`. When `m` is a power of two (see
+/// [crate::FilterBuilder::power_of_two_size]) the modulo is replaced by a mask with `m - 1`.
+#[inline(always)]
+pub(crate) fn reduce(hash: u64, m: u64) -> u64 {
+    if m.is_power_of_two() { hash & (m - 1) } else { hash % m }
+}
+
+/// Returns `(index + step) % m` for `index < m` and `step < m` without a division.
+#[inline(always)]
+pub(crate) fn next_index(index: u64, step: u64, m: u64) -> u64 {
+    let next = index + step;
+    if next >= m { next - m } else { next }
+}
+
+#[test]
+fn indices_test() {
+    use crate::{FilterBuilder, Membership};
+
+    let bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    let config = bloom.config();
+    for x in 0..10_000u64 {
+        let element = x.to_le_bytes();
+        assert_eq!(indices(&element, config.size, config.hashes, 0).to_vec(),
+                   bloom.get_hash_indices(&element));
+    }
+    assert_eq!(hash_pair(b"hello", 0),
+               (xxh3_64_with_seed(b"hello", 0), xxh3_64_with_seed(b"hello", 32)));
+    assert_ne!(indices(b"hello", config.size, config.hashes, 1),
+               indices(b"hello", config.size, config.hashes, 0));
+}
+
+#[test]
+fn zero_step_test() {
+    use crate::{BloomFilter, FilterBuilder, Membership};
+
+    // a step of 64 in a 128 bit filter, which is a zero step in 64 bits.
+    let element = (0..u64::MAX).map(u64::to_le_bytes)
+        .find(|element| reduce(xxh3_64_with_seed(element, 32), 128) == 64)
+        .unwrap();
+    let mut bloom = FilterBuilder::from_size_and_hashes(128, 4).build_bloom_filter();
+    bloom.add(&element);
+    assert!(bloom.contains(&element));
+    let folded = bloom.fold(1).unwrap();
+    assert_eq!(start_and_step(&element, 64, (0, 32)).1, 0);
+    assert!(folded.contains(&element));
+    assert!(folded.freeze().contains(&element));
+    let mut resampled = FilterBuilder::from_size_and_hashes(64, 4).build_bloom_filter();
+    assert!(resampled.union_resample(&bloom));
+    assert!(resampled.contains(&element));
+
+    // a zero step keeps every probe on the first index.
+    let mut bloom = BloomFilter::from_u8_array(&[0u8; 8], 4);
+    let (start, _) = start_and_step(&element, 64, (0, 32));
+    assert_eq!(bloom.get_hash_indices(&element), vec![start; 4]);
+    bloom.add(&element);
+    assert!(bloom.contains(&element));
+
+    // with 128 bit hashes the cubic term spreads a step that is a multiple of `m`.
+    let element = (0..u64::MAX).map(u64::to_le_bytes)
+        .find(|element| (murmur3_x64_128(element, 0) as u64).is_multiple_of(64))
+        .unwrap();
+    let mut probes: Vec<u64> = indices_128(&element, 64, 4, HashWidth::Bits128).collect();
+    probes.sort();
+    probes.dedup();
+    assert_eq!(probes.len(), 3);
+}
+
+#[test]
+fn xxh3_64_u64_test() {
+    use crate::{FilterBuilder, Membership};
+
+    let values = [0, 1, 87, u32::MAX as u64, u64::MAX, 0x0123_4567_89ab_cdef];
+    let seeds = [0, 32, 7, u32::MAX as u64, u64::MAX, 0xdead_beef_0000_0001];
+    let spread = (0..10_000u64).map(|x| x.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    for value in values.into_iter().chain(spread) {
+        for seed in seeds {
+            assert_eq!(xxh3_64_u64(value, seed), xxh3_64_with_seed(&value.to_le_bytes(), seed));
+        }
+    }
+
+    // pseudo-random values and seeds from splitmix64.
+    let mut state = 0x0bad_5eed_u64;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let z = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        let z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    for _ in 0..100_000 {
+        let (value, seed) = (next(), next());
+        assert_eq!(xxh3_64_u64(value, seed), xxh3_64_with_seed(&value.to_le_bytes(), seed),
+                   "{value:#x} {seed:#x}");
+    }
+
+    // a default filter hashes with the seeds (0, 32), and add_u64 sets the same bits as add.
+    let mut builder = FilterBuilder::new(10_000, 0.01);
+    assert_eq!(builder.hash_seeds, (0, 32));
+    let mut by_u64 = builder.clone().build_bloom_filter();
+    let mut by_bytes = builder.build_bloom_filter();
+    for _ in 0..1000 {
+        let value = next();
+        by_u64.add_u64(value);
+        by_bytes.add(&value.to_le_bytes());
+        assert!(by_bytes.contains_u64(value));
+    }
+    assert_eq!(by_u64.get_u8_array(), by_bytes.get_u8_array());
+}
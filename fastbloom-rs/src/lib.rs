@@ -1,25 +1,87 @@
 extern crate core;
 
-pub use bloom::{BloomFilter, CountingBloomFilter};
-pub use builder::FilterBuilder;
+pub use bloom::{BloomFilter, CachedBloomFilter, CountingBloomFilter,
+                DEFAULT_FALSE_POSITIVE_PROBABILITY, ExportFormat, FrozenBloomFilter,
+                RollingBloomFilter, SmallMembership};
+pub use builder::{FilterBuilder, HashWidth, MAX_HASHES};
+pub use error::{ConfigIssue, FilterError};
+#[cfg(feature = "metrics")]
+pub use metrics::OpStats;
 
+pub mod bench;
 mod builder;
 mod bloom;
+mod error;
+#[cfg(feature = "metrics")]
+mod metrics;
+pub mod hashing;
 mod vec;
 mod cuckoo;
 mod sketch;
 
 /// filter for check whether membership.
+///
+/// Elements are always hashed as bytes. The canonical encoding shared by the Rust, Python and JVM
+/// APIs is the UTF-8 bytes of a string and the 8 little-endian bytes of an `i64` integer, which
+/// is what [Membership::add_str], [Membership::contains_str] and [Membership::contains_int] use.
+///
+/// The empty element `b""` (or `""`) is a member like any other: adding it makes `contains(b"")`
+/// true and sets the bits of its own hashes only, so it is distinct from nothing being added.
 pub trait Membership {
     fn add(&mut self, element: &[u8]);
 
+    /// Adds a string, encoded as its UTF-8 bytes, to the filter. This matches Python `str`
+    /// elements and Java `String` elements.
+    fn add_str(&mut self, element: &str) {
+        self.add(element.as_bytes())
+    }
+
+    /// Adds every non-empty prefix of `key`, `key[..1]` up to `key` itself, so that prefixes can be
+    /// queried with [Membership::contains], e.g. for autocomplete. This adds `key.len()` elements,
+    /// which must be accounted for when sizing the filter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastbloom_rs::{FilterBuilder, Membership};
+    ///
+    /// let mut bloom = FilterBuilder::new(10_000, 0.01).build_bloom_filter();
+    /// bloom.add_all_prefixes(b"hello");
+    /// assert!(bloom.contains(b"hel"));
+    /// ```
+    fn add_all_prefixes(&mut self, key: &[u8]) {
+        for end in 1..=key.len() {
+            self.add(&key[..end]);
+        }
+    }
+
     fn contains(&self, element: &[u8]) -> bool;
 
+    /// Tests whether a string, encoded as its UTF-8 bytes, is present in the filter.
+    fn contains_str(&self, element: &str) -> bool {
+        self.contains(element.as_bytes())
+    }
+
+    /// Tests whether an integer, encoded as the 8 little-endian bytes of an `i64`, is present in
+    /// the filter. This matches Python `int` elements and Java `long` elements.
+    fn contains_int(&self, element: i64) -> bool {
+        self.contains(&element.to_le_bytes())
+    }
+
     fn get_hash_indices(&self, element: &[u8]) -> Vec<u64>;
 
-    fn contains_hash_indices(&self, indices: &Vec<u64>) -> bool;
+    /// Tests whether all the hash indices are present in the filter. Indices can be passed from
+    /// any slice, e.g. a `&Vec<u64>` from [Membership::get_hash_indices], an array or the result
+    /// of [hashing::indices], without collecting them into a `Vec`.
+    fn contains_hash_indices(&self, indices: &[u64]) -> bool;
 
     fn clear(&mut self);
+
+    /// Returns the filter as [Deletable] if it supports removing elements, e.g. to remove from a
+    /// `Box<dyn Membership>` returned by [FilterBuilder::build_auto].
+    fn as_deletable(&mut self) -> Option<&mut dyn Deletable> {
+        None
+    }
 }
 
 pub trait Hashes {
@@ -30,6 +92,13 @@ pub trait Hashes {
 pub trait Deletable {
     /// remove element from this data structures.
     fn remove(&mut self, element: &[u8]);
+
+    /// Removes every non-empty prefix of `key`, undoing [Membership::add_all_prefixes].
+    fn remove_all_prefixes(&mut self, key: &[u8]) {
+        for end in 1..=key.len() {
+            self.remove(&key[..end]);
+        }
+    }
 }
 
 
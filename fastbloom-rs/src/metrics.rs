@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Operation counts and total time spent in [crate::Membership::add] and
+/// [crate::Membership::contains] of a [crate::BloomFilter], see
+/// [crate::BloomFilter::op_stats].
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(PartialEq, Eq)]
+pub struct OpStats {
+    /// Number of calls to `add`.
+    pub adds: u64,
+    /// Total nanoseconds spent in `add`.
+    pub add_nanos: u64,
+    /// Number of calls to `contains`.
+    pub contains: u64,
+    /// Total nanoseconds spent in `contains`.
+    pub contains_nanos: u64,
+}
+
+/// The counters behind [OpStats]. They are atomic so that `contains` can record through `&self`
+/// while the filter stays `Sync`.
+#[derive(Debug)]
+#[derive(Default)]
+pub(crate) struct OpCounters {
+    adds: AtomicU64,
+    add_nanos: AtomicU64,
+    contains: AtomicU64,
+    contains_nanos: AtomicU64,
+}
+
+impl OpCounters {
+    pub(crate) fn record_add(&self, start: Instant) {
+        self.adds.fetch_add(1, Ordering::Relaxed);
+        self.add_nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_contains(&self, start: Instant) {
+        self.contains.fetch_add(1, Ordering::Relaxed);
+        self.contains_nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> OpStats {
+        OpStats {
+            adds: self.adds.load(Ordering::Relaxed),
+            add_nanos: self.add_nanos.load(Ordering::Relaxed),
+            contains: self.contains.load(Ordering::Relaxed),
+            contains_nanos: self.contains_nanos.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A clone starts from the stats of the original and counts separately from then on.
+impl Clone for OpCounters {
+    fn clone(&self) -> Self {
+        let stats = self.snapshot();
+        OpCounters {
+            adds: AtomicU64::new(stats.adds),
+            add_nanos: AtomicU64::new(stats.add_nanos),
+            contains: AtomicU64::new(stats.contains),
+            contains_nanos: AtomicU64::new(stats.contains_nanos),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use core::slice;
 use std::{fs::File, io::{Read, Seek}};
+use std::cmp::min;
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
 
 use crate::builder::SUFFIX;
 
@@ -8,32 +11,233 @@ fn get_usize_len() -> usize {
     if cfg!(target_pointer_width = "64") { 64 } else if cfg!(target_pointer_width = "32") { 32 } else { panic!() }
 }
 
+/// Combines the words of `$other` into `$storage` with `$op`. Four words are combined per step
+/// through fixed size arrays, which the compiler turns into vector instructions without bounds
+/// checks, the remaining words one by one.
+macro_rules! combine_words {
+    ($storage:expr, $other:expr, |$m:ident, $o:ident| $op:expr) => {{
+        let storage: &mut [usize] = $storage;
+        let other: &[usize] = $other;
+        let len = min(storage.len(), other.len());
+        let (storage, other) = (&mut storage[..len], &other[..len]);
+        let (storage, other) = {
+            let mut words = storage.chunks_exact_mut(4);
+            let mut others = other.chunks_exact(4);
+            for (word, o) in (&mut words).zip(&mut others) {
+                let word: &mut [usize; 4] = word.try_into().unwrap();
+                let o: &[usize; 4] = o.try_into().unwrap();
+                *word = std::array::from_fn(|i| {
+                    let ($m, $o) = (word[i], o[i]);
+                    $op
+                });
+            }
+            (words.into_remainder(), others.remainder())
+        };
+        for (word, o) in storage.iter_mut().zip(other.iter()) {
+            let ($m, $o) = (*word, *o);
+            *word = $op;
+        }
+    }};
+}
+
+/// Backing memory of [BloomBitVec], a heap vector or (with the `mmap` feature) a memory map.
+pub(crate) enum Storage {
+    Heap(Vec<usize>),
+    /// Anonymous or file backed mapping whose pages are only committed when first touched.
+    #[cfg(feature = "mmap")]
+    Mmap(memmap2::MmapMut),
+}
+
+impl Storage {
+    /// Maps `slots` zeroed words anonymously, so untouched pages stay uncommitted.
+    #[cfg(feature = "mmap")]
+    pub fn anonymous(slots: usize) -> std::io::Result<Self> {
+        if slots == 0 { return Ok(Storage::Heap(Vec::new())); }
+        let map = memmap2::MmapMut::map_anon(slots * std::mem::size_of::<usize>())?;
+        Ok(Storage::Mmap(map))
+    }
+
+    /// Maps the whole of `file` writable, so that writes to the words go to the file.
+    #[cfg(feature = "mmap")]
+    pub fn file(file: &File) -> std::io::Result<Self> {
+        // the mapping stays valid after `file` is closed; concurrent modification of the file by
+        // other processes is the caller's responsibility, as for any shared mapping.
+        let map = unsafe { memmap2::MmapMut::map_mut(file)? };
+        Ok(Storage::Mmap(map))
+    }
+
+    /// Writes modified pages of a file backed mapping to disk. Nothing to do for heap storage.
+    pub fn flush(&self) -> std::io::Result<()> {
+        match self {
+            Storage::Heap(_) => Ok(()),
+            #[cfg(feature = "mmap")]
+            Storage::Mmap(map) => map.flush(),
+        }
+    }
+}
+
+impl From<Vec<usize>> for Storage {
+    fn from(vec: Vec<usize>) -> Self {
+        Storage::Heap(vec)
+    }
+}
+
+impl Deref for Storage {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        match self {
+            Storage::Heap(vec) => vec,
+            // mappings are page aligned and sized in whole words.
+            #[cfg(feature = "mmap")]
+            Storage::Mmap(map) => unsafe {
+                slice::from_raw_parts(map.as_ptr() as *const usize,
+                                      map.len() / std::mem::size_of::<usize>())
+            },
+        }
+    }
+}
+
+impl DerefMut for Storage {
+    fn deref_mut(&mut self) -> &mut [usize] {
+        match self {
+            Storage::Heap(vec) => vec,
+            #[cfg(feature = "mmap")]
+            Storage::Mmap(map) => unsafe {
+                slice::from_raw_parts_mut(map.as_mut_ptr() as *mut usize,
+                                          map.len() / std::mem::size_of::<usize>())
+            },
+        }
+    }
+}
+
+/// Cloning always copies into a heap vector.
+impl Clone for Storage {
+    fn clone(&self) -> Self {
+        Storage::Heap(self.to_vec())
+    }
+}
+
+impl Debug for Storage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for Storage {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+/// Serialized exactly like the former `Vec<usize>` representation.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Storage {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Storage {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<usize>::deserialize(deserializer).map(Storage::Heap)
+    }
+}
+
+/// The largest value a [CountingVec] counter holds before it saturates.
+#[cfg(target_pointer_width = "64")]
+pub(crate) const COUNTER_MAX: usize = 0b1111;
+#[cfg(target_pointer_width = "32")]
+pub(crate) const COUNTER_MAX: usize = 0b111;
+
 /// bitmap only for bloom filter.
 #[derive(Debug)]
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct BloomBitVec {
     /// Internal representation of the bit vector
-    pub(crate) storage: Vec<usize>,
+    pub(crate) storage: Storage,
     /// The number of valid bits in the internal representation
     pub(crate) nbits: u64,
+    /// Indices of the words which became non-zero since construction or the last clear, only
+    /// present when dirty tracking is enabled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) dirty: Option<Vec<usize>>,
 }
 
 impl BloomBitVec {
     pub fn new(slots: usize) -> Self {
         BloomBitVec {
-            storage: vec![0; slots],
+            storage: vec![0; slots].into(),
             nbits: (slots * get_usize_len()) as u64,
+            dirty: None,
         }
     }
 
+    /// Like [BloomBitVec::new] but returns `None` instead of aborting when the allocator can not
+    /// provide the words. They are requested zeroed from the allocator, which takes fresh pages
+    /// from the system without touching them, so memory is only committed as words are written.
+    pub fn try_new(slots: usize) -> Option<Self> {
+        let layout = std::alloc::Layout::array::<usize>(slots).ok()?;
+        if layout.size() == 0 {
+            return Some(BloomBitVec::new(0));
+        }
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) } as *mut usize;
+        if ptr.is_null() {
+            return None;
+        }
+        let storage = unsafe { Vec::from_raw_parts(ptr, slots, slots) };
+        Some(BloomBitVec {
+            storage: storage.into(),
+            nbits: (slots * get_usize_len()) as u64,
+            dirty: None,
+        })
+    }
+
+    /// Create a zeroed bit vector backed by an anonymous memory map.
+    #[cfg(feature = "mmap")]
+    pub fn new_lazy(slots: usize) -> std::io::Result<Self> {
+        Ok(BloomBitVec {
+            storage: Storage::anonymous(slots)?,
+            nbits: (slots * get_usize_len()) as u64,
+            dirty: None,
+        })
+    }
+
+    /// Create a bit vector backed by `file` mapped writable, whose length must be a whole number
+    /// of words.
+    #[cfg(feature = "mmap")]
+    pub fn map_file(file: &File) -> std::io::Result<Self> {
+        let storage = Storage::file(file)?;
+        let nbits = (storage.len() * get_usize_len()) as u64;
+        Ok(BloomBitVec { storage, nbits, dirty: None })
+    }
+
     pub fn from_elem(slots: usize, bit: bool) -> Self {
         BloomBitVec {
-            storage: vec![if bit { !0 } else { 0 }; slots],
+            storage: vec![if bit { !0 } else { 0 }; slots].into(),
             nbits: (slots * get_usize_len()) as u64,
+            dirty: None,
         }
     }
     
+    /// Create a bit vector from its canonical byte stream, where bit `i` is bit `i % 8` of byte
+    /// `i / 8`. The bytes are regrouped into native words, so this is independent of both the
+    /// word width and the endianness of the target. `bytes` must hold whole 64 bit words.
+    pub fn from_le_bytes(bytes: &[u8]) -> Self {
+        const WORD: usize = std::mem::size_of::<usize>();
+        assert_eq!(bytes.len() % 8, 0, "bytes must hold whole 64 bit words");
+        let storage: Vec<usize> = bytes.chunks_exact(WORD)
+            .map(|chunk| usize::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        BloomBitVec {
+            storage: storage.into(),
+            nbits: bytes.len() as u64 * 8,
+            dirty: None,
+        }
+    }
+
     pub fn from_file(file: &mut File, seek: u64, bytes_len: u64) -> Self {
         #[cfg(target_pointer_width = "64")]
             let length = bytes_len / 8;
@@ -53,8 +257,9 @@ impl BloomBitVec {
         file.read_exact(buf).unwrap();
 
         BloomBitVec {
-            storage,
-            nbits: nbits.try_into().unwrap()
+            storage: storage.into(),
+            nbits: nbits.try_into().unwrap(),
+            dirty: None,
         }
     }
 
@@ -66,9 +271,39 @@ impl BloomBitVec {
             let w = index >> 5;
         let b = index & SUFFIX;
         let flag = 1usize << b;
+        if let Some(dirty) = &mut self.dirty {
+            if self.storage[w] == 0 { dirty.push(w); }
+        }
         self.storage[w] = self.storage[w] | flag;
     }
 
+    /// Sets every bit in `indices`. Whether dirty tracking is enabled is checked once for all of
+    /// them rather than per bit as in [BloomBitVec::set], so a vector without tracking pays
+    /// nothing for it on the add path.
+    #[inline]
+    pub fn set_all(&mut self, indices: impl Iterator<Item=u64>) {
+        match &mut self.dirty {
+            None => for index in indices {
+                self.storage[index as usize / usize::BITS as usize] |= 1usize << (index as usize & SUFFIX);
+            },
+            Some(dirty) => for index in indices {
+                let w = index as usize / usize::BITS as usize;
+                if self.storage[w] == 0 { dirty.push(w); }
+                self.storage[w] |= 1usize << (index as usize & SUFFIX);
+            },
+        }
+    }
+
+    #[inline]
+    pub fn unset(&mut self, index: usize) {
+        #[cfg(target_pointer_width = "64")]
+            let w = index >> 6;
+        #[cfg(target_pointer_width = "32")]
+            let w = index >> 5;
+        let b = index & SUFFIX;
+        self.storage[w] &= !(1usize << b);
+    }
+
     #[inline]
     pub fn get(&self, index: usize) -> bool {
         #[cfg(target_pointer_width = "64")]
@@ -80,58 +315,239 @@ impl BloomBitVec {
         (self.storage[w] & flag) != 0
     }
 
+    /// Like [BloomBitVec::set] without the bounds check on the storage.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be smaller than the number of bits of the storage.
+    #[inline]
+    pub unsafe fn set_unchecked(&mut self, index: usize) {
+        let w = index / usize::BITS as usize;
+        let flag = 1usize << (index & SUFFIX);
+        let word = self.storage.get_unchecked_mut(w);
+        if let Some(dirty) = &mut self.dirty {
+            if *word == 0 { dirty.push(w); }
+        }
+        *word |= flag;
+    }
+
+    /// Like [BloomBitVec::get] without the bounds check on the storage.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be smaller than the number of bits of the storage.
+    #[inline]
+    pub unsafe fn get_unchecked(&self, index: usize) -> bool {
+        let word = self.storage.get_unchecked(index / usize::BITS as usize);
+        word & (1 << (index & SUFFIX)) != 0
+    }
+
+    /// Like [BloomBitVec::get], but returns [None] instead of panicking when `index` is past the
+    /// storage.
+    #[inline]
+    pub fn get_checked(&self, index: usize) -> Option<bool> {
+        let word = self.storage.get(index / usize::BITS as usize)?;
+        Some(word & (1 << (index & SUFFIX)) != 0)
+    }
+
     pub fn or(&mut self, other: &BloomBitVec) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
-            *m |= *o;
+        combine_words!(&mut self.storage, &other.storage, |m, o| m | o);
+        self.retrack_dirty();
+    }
+
+    /// OR the little-endian words stored in `bytes`, in the canonical byte order of
+    /// [BloomBitVec::from_le_bytes], into this vector. `bytes` must hold exactly as many bytes as
+    /// the storage.
+    pub fn or_bytes(&mut self, bytes: &[u8]) {
+        const WORD: usize = std::mem::size_of::<usize>();
+        for (m, o) in self.storage.iter_mut().zip(bytes.chunks_exact(WORD)) {
+            *m |= usize::from_le_bytes(o.try_into().unwrap());
         }
+        self.retrack_dirty();
     }
 
-    pub fn xor(&mut self, other: &BloomBitVec) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
-            *m ^= *o;
+    /// OR `other` into this vector when one size divides the other: bit `i` of the smaller vector
+    /// is ORed into every bit `j` of the larger one with `j % smaller == i`, tiling a smaller
+    /// `other` or folding a larger one. Whole words are combined when the smaller size is a
+    /// multiple of the word size.
+    pub fn or_resampled(&mut self, other: &BloomBitVec) {
+        let (m, n) = (self.nbits as usize, other.nbits as usize);
+        let word_bits = get_usize_len();
+        if m >= n {
+            if n % word_bits == 0 {
+                let words = other.storage.len();
+                for (i, word) in self.storage.iter_mut().enumerate() {
+                    *word |= other.storage[i % words];
+                }
+            } else {
+                for j in (0..m).filter(|j| other.get(j % n)) {
+                    self.storage[j / word_bits] |= 1 << (j % word_bits);
+                }
+            }
+        } else if m % word_bits == 0 {
+            let words = self.storage.len();
+            for (i, word) in other.storage.iter().enumerate() {
+                self.storage[i % words] |= *word;
+            }
+        } else {
+            for i in (0..n).filter(|i| other.get(*i)) {
+                let j = i % m;
+                self.storage[j / word_bits] |= 1 << (j % word_bits);
+            }
         }
+        self.retrack_dirty();
+    }
+
+    pub fn xor(&mut self, other: &BloomBitVec) {
+        combine_words!(&mut self.storage, &other.storage, |m, o| m ^ o);
+        self.retrack_dirty();
     }
 
     pub fn nor(&mut self, other: &Self) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
+        for (m, o) in self.storage.iter_mut().zip(other.storage.iter()) {
             *m = !(*m | *o);
         }
+        self.retrack_dirty();
     }
 
     pub fn xnor(&mut self, other: &Self) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
+        for (m, o) in self.storage.iter_mut().zip(other.storage.iter()) {
             *m = !(*m ^ *o);
         }
+        self.retrack_dirty();
     }
 
     pub fn and(&mut self, other: &BloomBitVec) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
-            *m &= *o;
-        }
+        combine_words!(&mut self.storage, &other.storage, |m, o| m & o);
     }
 
     pub fn nand(&mut self, other: &Self) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
+        for (m, o) in self.storage.iter_mut().zip(other.storage.iter()) {
             *m = !(*m & *o);
         }
+        self.retrack_dirty();
     }
 
     pub fn difference(&mut self, other: &Self) {
-        for (m, o) in self.storage.iter_mut().zip(&other.storage) {
-            *m &= !*o;
+        combine_words!(&mut self.storage, &other.storage, |m, o| m & !o);
+    }
+
+    /// Counts the one bits in `start..end`, whole words at a time.
+    pub fn count_ones_in(&self, start: usize, end: usize) -> u64 {
+        let word_bits = get_usize_len();
+        let mut count = 0;
+        let mut index = start;
+        while index < end {
+            let word = index / word_bits;
+            let offset = index % word_bits;
+            let len = min(word_bits - offset, end - index);
+            let mask = if len == word_bits { !0 } else { ((1usize << len) - 1) << offset };
+            count += (self.storage[word] & mask).count_ones() as u64;
+            index += len;
         }
+        count
     }
 
     pub fn count_zeros(&self)->u32 {
         self.storage.iter().fold(0, |acc, x| acc + x.count_zeros())
     }
 
+    /// Counts the one bits of `self ^ other` word by word, without materializing the difference.
+    pub fn xor_count_ones(&self, other: &BloomBitVec) -> u64 {
+        self.storage.iter().zip(other.storage.iter())
+            .fold(0, |acc, (x, y)| acc + (x ^ y).count_ones() as u64)
+    }
+
+    /// Counts the zero bits of `self | other` word by word, without materializing the union.
+    pub fn union_count_zeros(&self, other: &BloomBitVec) -> u64 {
+        self.storage.iter().zip(other.storage.iter())
+            .fold(0, |acc, (x, y)| acc + (x | y).count_zeros() as u64)
+    }
+
     pub fn clear(&mut self) {
         self.storage.fill(0);
+        if let Some(dirty) = &mut self.dirty { dirty.clear(); }
+    }
+
+    /// Zeroes the bits in `[start, end)`: the edge words are masked, the interior words filled.
+    pub fn clear_range(&mut self, start: usize, end: usize) {
+        if start >= end { return; }
+        let len = get_usize_len();
+        let (first, last) = (start / len, (end - 1) / len);
+        let head = !0usize << (start & SUFFIX);
+        let tail = !0usize >> (len - 1 - ((end - 1) & SUFFIX));
+        if first == last {
+            self.storage[first] &= !(head & tail);
+        } else {
+            self.storage[first] &= !head;
+            self.storage[first + 1..last].fill(0);
+            self.storage[last] &= !tail;
+        }
     }
 
+    /// Zeroes only the dirty words when dirty tracking is enabled, otherwise the whole storage.
+    /// Returns the number of words written.
+    pub fn clear_dirty(&mut self) -> usize {
+        match &mut self.dirty {
+            Some(dirty) => {
+                let written = dirty.len();
+                for w in dirty.drain(..) {
+                    self.storage[w] = 0;
+                }
+                written
+            }
+            None => {
+                self.storage.fill(0);
+                self.storage.len()
+            }
+        }
+    }
+
+    /// Start remembering the words that become non-zero.
+    pub fn enable_dirty_tracking(&mut self) {
+        self.dirty = Some(Vec::new());
+        self.retrack_dirty();
+    }
+
+    /// Stop remembering dirty words, e.g. after the storage was handed out for external writes.
+    pub fn untrack_dirty(&mut self) {
+        self.dirty = None;
+    }
+
+    /// Re-collects the dirty words after a bulk operation may have set bits in any word.
+    fn retrack_dirty(&mut self) {
+        if let Some(dirty) = &mut self.dirty {
+            dirty.clear();
+            dirty.extend(self.storage.iter().enumerate().filter(|(_, w)| **w != 0).map(|(i, _)| i));
+        }
+    }
+
+    /// Returns whether every bit is zero.
     pub fn is_empty(&self) -> bool {
-        self.storage.is_empty()
+        self.storage.iter().all(|word| *word == 0)
+    }
+}
+
+/// Adds the 4 bit counters packed in two slots pairwise without a carry crossing into the next
+/// counter, saturating each at 15 (at 7 on 32 bit targets, where counters only use 3 bits).
+#[inline]
+fn saturating_add_counters(a: usize, b: usize) -> usize {
+    const ONES: usize = usize::MAX / 0xF;
+    const LOW: usize = ONES * 0x7;
+    const HIGH: usize = ONES * 0x8;
+    // the low 3 bits of two counters sum to at most 14, which stays inside the counter.
+    let low = (a & LOW) + (b & LOW);
+    #[cfg(target_pointer_width = "64")]
+    {
+        let (a_high, b_high) = (a & HIGH, b & HIGH);
+        // carry out of the top bit: both top bits set, or one of them plus the carry into it.
+        let overflow = (a_high & b_high) | (low & HIGH & (a_high ^ b_high));
+        let sum = low ^ a_high ^ b_high;
+        sum | ((overflow >> 3) * 0xF)
+    }
+    #[cfg(target_pointer_width = "32")]
+    {
+        (low & LOW) | (((low & HIGH) >> 3) * 0x7)
     }
 }
 
@@ -221,9 +637,71 @@ impl CountingVec {
         return (slot >> ((7 - b) * 4)) & 0b111;
     }
 
+    /// Returns whether every counter is zero.
+    pub fn is_zero(&self) -> bool {
+        self.storage.iter().all(|word| *word == 0)
+    }
+
+    /// Set the counter at `index` to `value`, saturating at the largest value a counter holds.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: usize) {
+        #[cfg(target_pointer_width = "64")]
+            let max = 0b1111;
+        #[cfg(target_pointer_width = "32")]
+            let max = 0b111;
+        let w = index / self.counter_per_slot;
+        let b = index % self.counter_per_slot;
+        let move_bits = (self.counter_per_slot - 1 - b) * 4;
+        self.storage[w] =
+            (self.storage[w] & !(0b1111 << move_bits)) | (min(value, max) << move_bits)
+    }
+
+    /// Add `n` to the counter at `index`, saturating like [CountingVec::increment].
+    #[inline]
+    pub fn increment_by(&mut self, index: usize, n: usize) {
+        self.set(index, self.get(index).saturating_add(n));
+    }
+
+    /// Subtract `n` from the counter at `index`, saturating at zero.
+    #[inline]
+    pub fn decrement_by(&mut self, index: usize, n: usize) {
+        self.set(index, self.get(index).saturating_sub(n));
+    }
+
     pub fn clear(&mut self) {
         self.storage.fill(0);
     }
+
+    /// Add the counters of the native-endian slots stored in `bytes` to this vector counter by
+    /// counter, saturating like [CountingVec::increment]. `bytes` must hold exactly as many bytes
+    /// as the storage.
+    pub fn saturating_add_bytes(&mut self, bytes: &[u8]) {
+        const WORD: usize = std::mem::size_of::<usize>();
+        for (m, o) in self.storage.iter_mut().zip(bytes.chunks_exact(WORD)) {
+            *m = saturating_add_counters(*m, usize::from_ne_bytes(o.try_into().unwrap()));
+        }
+    }
+
+    /// Subtract the counters of `other` from this vector counter by counter, saturating at zero.
+    /// All-zero slots of `other` are skipped.
+    pub fn saturating_sub(&mut self, other: &CountingVec) {
+        let per_slot = self.counter_per_slot;
+        for (w, slot) in other.storage.iter().enumerate() {
+            if *slot == 0 { continue; }
+            for i in w * per_slot..(w + 1) * per_slot {
+                self.decrement_by(i, other.get(i));
+            }
+        }
+    }
+
+    /// Iterate `(index, count)` of the non-zero counters, skipping all-zero slots entirely.
+    pub fn nonzero(&self) -> impl Iterator<Item=(usize, usize)> + '_ {
+        let per_slot = self.counter_per_slot;
+        self.storage.iter().enumerate()
+            .filter(|(_, slot)| **slot != 0)
+            .flat_map(move |(w, _)| (w * per_slot..(w + 1) * per_slot).map(move |i| (i, self.get(i))))
+            .filter(|(_, count)| *count != 0)
+    }
 }
 
 #[test]
@@ -264,4 +742,48 @@ fn test_count_zeros() {
     assert_eq!(vec.count_zeros(), 253);
     #[cfg(target_pointer_width = "32")]
     assert_eq!(vec.count_zeros(), 125);
-}
\ No newline at end of file
+}
+#[test]
+fn saturating_add_counters_test() {
+    let max = if cfg!(target_pointer_width = "64") { 15 } else { 7 };
+    let mut a = CountingVec::new(1);
+    let mut b = CountingVec::new(1);
+    let per_slot = a.counter_per_slot;
+    for x in 0..=max {
+        for y in 0..=max {
+            for i in 0..per_slot {
+                a.set(i, (x + i) % (max + 1));
+                b.set(i, (y + 2 * i) % (max + 1));
+            }
+            let mut sum = a.clone();
+            sum.storage[0] = saturating_add_counters(a.storage[0], b.storage[0]);
+            for i in 0..per_slot {
+                assert_eq!(sum.get(i), min(a.get(i) + b.get(i), max), "{x} {y} {i}");
+            }
+        }
+    }
+}
+
+#[test]
+fn combine_words_test() {
+    // 11 words leave a remainder after the four word steps.
+    let words = |seed: usize| -> Vec<usize> {
+        (0..11usize).map(|i| (i + seed).wrapping_mul(0x9E37_79B9_7F4A_7C15_u64 as usize)).collect()
+    };
+    let (a, b) = (words(1), words(7));
+    let vec = |storage: &Vec<usize>| BloomBitVec {
+        storage: storage.clone().into(),
+        nbits: (storage.len() * get_usize_len()) as u64,
+        dirty: None,
+    };
+    let check = |op: fn(&mut BloomBitVec, &BloomBitVec), scalar: fn(usize, usize) -> usize| {
+        let mut combined = vec(&a);
+        op(&mut combined, &vec(&b));
+        let expected: Vec<usize> = a.iter().zip(b.iter()).map(|(m, o)| scalar(*m, *o)).collect();
+        assert_eq!(&combined.storage[..], &expected[..]);
+    };
+    check(BloomBitVec::or, |m, o| m | o);
+    check(BloomBitVec::and, |m, o| m & o);
+    check(BloomBitVec::xor, |m, o| m ^ o);
+    check(BloomBitVec::difference, |m, o| m & !o);
+}
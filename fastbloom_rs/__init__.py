@@ -1,7 +1,8 @@
 # -*- coding: utf-8 -*-
 
 from . import *
-from .fastbloom_rs import PyFilterBuilder, PyBloomFilter, PyCountingBloomFilter
-from .filter import BloomFilter, FilterBuilder, CountingBloomFilter
+from .fastbloom_rs import PyFilterBuilder, PyBloomFilter, PyCountingBloomFilter, PyRollingBloomFilter
+from .filter import BloomFilter, FilterBuilder, CountingBloomFilter, RollingBloomFilter
 
-__all__ = ["filter", "BloomFilter", "FilterBuilder", "PyBloomFilter", "PyCountingBloomFilter", "CountingBloomFilter"]
+__all__ = ["filter", "BloomFilter", "FilterBuilder", "PyBloomFilter", "PyCountingBloomFilter", "CountingBloomFilter",
+           "PyRollingBloomFilter", "RollingBloomFilter"]
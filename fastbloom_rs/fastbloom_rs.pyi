@@ -1,18 +1,35 @@
 # -*- coding: utf-8 -*-
 
-from typing import Union, Sequence
+from typing import Callable, Dict, List, Union, Sequence, Optional, Tuple
 
 
 class PyFilterBuilder(object):
     def __int__(self, expected_elements: int, false_positive_probability: float):
         ...
 
+    @staticmethod
+    def from_fpr_and_hashes(expected_elements: int, false_positive_probability: float,
+                            hashes: int) -> "PyFilterBuilder":
+        ...
+
+    @staticmethod
+    def for_cardinality(expected_elements: int, relative_error: float) -> "PyFilterBuilder":
+        ...
+
+    @staticmethod
+    def optimally_build_from(false_positive_probability: float,
+                             elements: Sequence[Union[str, int, bytes]]) -> "PyBloomFilter":
+        ...
+
     def build_bloom_filter(self) -> "PyBloomFilter":
         ...
 
     def build_counting_bloom_filter(self) -> "PyCountingBloomFilter":
         ...
 
+    def build_rolling_bloom_filter(self, windows: int) -> "PyRollingBloomFilter":
+        ...
+
     def expected_elements(self) -> int:
         ...
 
@@ -22,12 +39,30 @@ class PyFilterBuilder(object):
     def enable_repeat_insert(self, enable: bool):
         ...
 
+    def hash_seeds(self, s1: int, s2: int):
+        ...
+
     def size(self) -> int:
         ...
 
     def hashes(self) -> int:
         ...
 
+    def final_size(self) -> int:
+        ...
+
+    def final_hashes(self) -> int:
+        ...
+
+    def merge_conservative(self, other: "PyFilterBuilder") -> "PyFilterBuilder":
+        ...
+
+    def validate(self) -> List[str]:
+        ...
+
+    def run_profile(self, n_insert: int, n_query: int) -> Dict[str, Union[int, float]]:
+        ...
+
 
 class PyBloomFilter(object):
     def add(self, element: Union[str, int, bytes]):
@@ -48,6 +83,12 @@ class PyBloomFilter(object):
     def add_str_if_not_contains(self, element: str) -> bool:
         ...
 
+    def add_all_prefixes(self, key: bytes):
+        ...
+
+    def add_all_prefixes_str(self, key: str):
+        ...
+
     def add_str_batch(self, array: Sequence[str]):
         ...
 
@@ -84,6 +125,21 @@ class PyBloomFilter(object):
     def contains_hash_indices(self, indices: Sequence[int]) -> bool:
         ...
 
+    def contains_with_confidence_int(self, element: int) -> Optional[float]:
+        ...
+
+    def contains_with_confidence_str(self, element: str) -> Optional[float]:
+        ...
+
+    def contains_with_confidence_bytes(self, element: bytes) -> Optional[float]:
+        ...
+
+    def fill_ratio(self) -> float:
+        ...
+
+    def probe_histogram(self, buckets: int) -> Sequence[int]:
+        ...
+
     def config(self) -> PyFilterBuilder:
         ...
 
@@ -93,6 +149,20 @@ class PyBloomFilter(object):
     def get_bytes(self) -> bytes:
         ...
 
+    def to_bytes_with_header(self) -> bytes:
+        ...
+
+    @staticmethod
+    def empty_like(other: PyBloomFilter) -> "PyBloomFilter":
+        ...
+
+    @staticmethod
+    def from_bytes_with_header(array: bytes) -> "PyBloomFilter":
+        ...
+
+    def __reduce__(self) -> Tuple[Callable[[bytes], "PyBloomFilter"], Tuple[bytes]]:
+        ...
+
     def get_int_array(self) -> Sequence[int]:
         ...
 
@@ -105,9 +175,54 @@ class PyBloomFilter(object):
     def clear(self):
         ...
 
+    def clear_range(self, bit_start: int, bit_end: int):
+        ...
+
+    def set_bit(self, index: int):
+        ...
+
+    def clear_bit(self, index: int):
+        ...
+
+    def get_bit(self, index: int) -> bool:
+        ...
+
     def estimate_set_cardinality(self):
         ...
 
+    def suggested_hashes(self) -> int:
+        ...
+
+    def current_false_positive_probability(self) -> float:
+        ...
+
+    def should_stop_inserting(self, target_fpr: float) -> bool:
+        ...
+
+    def sample_fpr(self, samples: int, seed: int) -> float:
+        ...
+
+    def __len__(self) -> int:
+        ...
+
+    def capacity(self) -> int:
+        ...
+
+    def estimate_union_cardinality(self, other: "PyBloomFilter") -> Optional[float]:
+        ...
+
+    def present_only_in(self, other: "PyBloomFilter", candidates: Sequence[bytes]) -> List[int]:
+        ...
+
+    def size_bits(self) -> int:
+        ...
+
+    def size_bytes(self) -> int:
+        ...
+
+    def hamming_distance(self, other: "PyBloomFilter") -> Optional[int]:
+        ...
+
     def get_hash_indices(self, element: bytes) -> Sequence[int]:
         ...
 
@@ -117,12 +232,33 @@ class PyBloomFilter(object):
     def get_hash_indices_str(self, element: str) -> Sequence[int]:
         ...
 
+    def get_word_bit_indices(self, element: bytes) -> List[Tuple[int, int]]:
+        ...
+
+    def get_word_bit_indices_int(self, element: int) -> List[Tuple[int, int]]:
+        ...
+
+    def get_word_bit_indices_str(self, element: str) -> List[Tuple[int, int]]:
+        ...
+
+    def add_hash_pairs(self, pairs: Sequence[Tuple[int, int]]):
+        ...
+
+    def contains_hash_pairs(self, pairs: Sequence[Tuple[int, int]]) -> Sequence[bool]:
+        ...
+
     def is_empty(self) -> bool:
         ...
 
     def union(self, other: PyBloomFilter) -> bool:
         ...
 
+    def union_bytes(self, array: bytes, hashes: int):
+        ...
+
+    def union_bytes_many(self, shards: Sequence[Tuple[bytes, int]]) -> int:
+        ...
+
     def intersect(self, other: PyBloomFilter) -> bool:
         ...
 
@@ -147,6 +283,24 @@ class PyCountingBloomFilter(object):
     def add(self, element: Union[str, int, bytes]):
         ...
 
+    def add_n(self, element: bytes, n: int):
+        ...
+
+    def add_n_int(self, element: int, n: int):
+        ...
+
+    def add_n_str(self, element: str, n: int):
+        ...
+
+    def remove_n(self, element: bytes, n: int):
+        ...
+
+    def remove_n_int(self, element: int, n: int):
+        ...
+
+    def remove_n_str(self, element: str, n: int):
+        ...
+
     def add_int(self, element: int):
         ...
 
@@ -231,13 +385,104 @@ class PyCountingBloomFilter(object):
     def estimate_count_str(self, element: str) -> int:
         ...
 
+    def estimate_count_int_batch(self, elements: Sequence[int]) -> Sequence[int]:
+        ...
+
+    def estimate_count_str_batch(self, elements: Sequence[str]) -> Sequence[int]:
+        ...
+
+    def estimate_count_bytes_batch(self, elements: Sequence[bytes]) -> Sequence[int]:
+        ...
+
+    def min_count_hash_indices(self, indices: Sequence[int]) -> int:
+        ...
+
     def counter_at(self, index: int) -> int:
         ...
 
+    def total_insertions(self) -> int:
+        ...
+
+    def is_empty(self) -> bool:
+        ...
+
+    def saturated_counters(self) -> int:
+        ...
+
+    def reset_if_saturated(self, threshold: float) -> bool:
+        ...
+
+    def size_counters(self) -> int:
+        ...
+
+    def size_bytes(self) -> int:
+        ...
+
+    def to_portable_bytes(self) -> bytes:
+        ...
+
+    def to_bloom_bytes(self) -> bytes:
+        ...
+
     @staticmethod
     def from_bytes(array: bytes, hashes: int, enable_repeat_insert: bool) -> PyCountingBloomFilter:
         ...
 
+    @staticmethod
+    def from_portable_bytes(array: bytes) -> PyCountingBloomFilter:
+        ...
+
+    def __reduce__(self) -> Tuple[Callable[[bytes], "PyCountingBloomFilter"], Tuple[bytes]]:
+        ...
+
+    def subtract(self, other: PyCountingBloomFilter) -> bool:
+        ...
+
+    def union_bytes(self, array: bytes, hashes: int):
+        ...
+
+    def counters_as_u8(self) -> bytes:
+        ...
+
+    @staticmethod
+    def from_counters_u8(counters: bytes, hashes: int, enable_repeat_insert: bool) -> PyCountingBloomFilter:
+        ...
+
     @staticmethod
     def from_int_array(array: Sequence[int], hashes: int, enable_repeat_insert: bool) -> PyCountingBloomFilter:
         ...
+
+
+class PyRollingBloomFilter(object):
+    def add_int(self, element: int):
+        ...
+
+    def add_str(self, element: str):
+        ...
+
+    def add_bytes(self, element: bytes):
+        ...
+
+    def contains_int(self, element: int) -> bool:
+        ...
+
+    def contains_str(self, element: str) -> bool:
+        ...
+
+    def contains_bytes(self, element: bytes) -> bool:
+        ...
+
+    def rotate(self):
+        ...
+
+    def windows(self) -> int:
+        ...
+
+    def config(self) -> PyFilterBuilder:
+        ...
+
+    def hashes(self) -> int:
+        ...
+
+    def clear(self):
+        ...
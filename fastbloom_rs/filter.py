@@ -1,8 +1,8 @@
 # -*- coding: utf-8 -*-
 
-from typing import Union, Sequence
+from typing import Dict, List, Union, Sequence, Optional, Tuple
 
-from fastbloom_rs import PyFilterBuilder, PyBloomFilter, PyCountingBloomFilter
+from fastbloom_rs import PyFilterBuilder, PyBloomFilter, PyCountingBloomFilter, PyRollingBloomFilter
 
 
 class FilterBuilder(object):
@@ -23,6 +23,46 @@ class FilterBuilder(object):
         else:
             raise Exception("expected_elements must be integer")
 
+    @staticmethod
+    def from_fpr_and_hashes(expected_elements: int, false_positive_probability: float,
+                            hashes: int) -> "FilterBuilder":
+        """
+        Constructs a new Bloom Filter Builder with a fixed number of hashes. The size of the Bloom filter in bits is
+        derived so that `expected_elements` reach `false_positive_probability`.
+
+        :param expected_elements: expected size of the filter
+        :param false_positive_probability: tolerable false positive probability
+        :param hashes: fixed hash function number
+        :return:
+        """
+        return FilterBuilder(PyFilterBuilder.from_fpr_and_hashes(expected_elements, false_positive_probability, hashes))
+
+    @staticmethod
+    def for_cardinality(expected_elements: int, relative_error: float) -> "FilterBuilder":
+        """
+        Constructs a new Bloom Filter Builder for estimating the number of distinct elements with
+        estimate_set_cardinality rather than for membership. The filter uses a single hash and is sized so that the
+        estimate for `expected_elements` is within `relative_error` of the true count about 95% of the time.
+
+        :param expected_elements: expected number of distinct elements
+        :param relative_error: tolerable relative error of the estimate
+        :return:
+        """
+        return FilterBuilder(PyFilterBuilder.for_cardinality(expected_elements, relative_error))
+
+    @staticmethod
+    def optimally_build_from(false_positive_probability: float,
+                             elements: Sequence[Union[str, int, bytes]]) -> "BloomFilter":
+        """
+        Builds a Bloom filter holding elements, sized for exactly len(elements) elements at
+        false_positive_probability. Every element counts, so duplicates size the filter larger than needed.
+
+        :param false_positive_probability: tolerable false positive probability
+        :param elements: elements to add
+        :return:
+        """
+        return BloomFilter(PyFilterBuilder.optimally_build_from(false_positive_probability, elements))
+
     def expected_elements(self) -> int:
         """
         expected size of the filter
@@ -48,6 +88,17 @@ class FilterBuilder(object):
         """
         self._py_builder.enable_repeat_insert(enable)
 
+    def hash_seeds(self, s1: int, s2: int):
+        """
+        Set the seeds of the two xxh3 hashes the indices of an element are derived from, (0, 32) by
+        default. Use this to interoperate with filters built by another xxh3 implementation.
+
+        :param s1: seed of the first hash.
+        :param s2: seed of the second hash.
+        :return:
+        """
+        self._py_builder.hash_seeds(s1, s2)
+
     def size(self) -> int:
         """
         the size of the bloom filter in bits.
@@ -64,12 +115,31 @@ class FilterBuilder(object):
         """
         return self._py_builder.hashes()
 
+    def final_size(self) -> int:
+        """
+        The size in bits of the filters built from this builder, rounded up to whole words, computed
+        without building a filter.
+
+        :return:
+        """
+        return self._py_builder.final_size()
+
+    def final_hashes(self) -> int:
+        """
+        The hash function number of the filters built from this builder, computed without building a
+        filter.
+
+        :return:
+        """
+        return self._py_builder.final_hashes()
+
     def build_bloom_filter(self) -> "BloomFilter":
         """
         Constructs a Bloom filter using the specified parameters and computing missing parameters
         if possible (e.g. the optimal Bloom filter bit size).
 
         :return:
+        :raises ValueError: if the parameters are invalid, see `validate`.
         """
         return BloomFilter(self._py_builder.build_bloom_filter())
 
@@ -79,9 +149,50 @@ class FilterBuilder(object):
         if possible (e.g. the optimal Bloom filter counter size).
 
         :return:
+        :raises ValueError: if the parameters are invalid, see `validate`.
         """
         return CountingBloomFilter(self._py_builder.build_counting_bloom_filter())
 
+    def build_rolling_bloom_filter(self, windows: int) -> "RollingBloomFilter":
+        """
+        Constructs a Rolling Bloom filter of `windows` Bloom filters using the specified parameters and computing
+        missing parameters if possible (e.g. the optimal Bloom filter bit size).
+
+        :param windows: number of windows an element stays in the filter
+        :return:
+        """
+        return RollingBloomFilter(self._py_builder.build_rolling_bloom_filter(windows))
+
+    def merge_conservative(self, other: "FilterBuilder") -> "FilterBuilder":
+        """
+        Returns a configuration at least as capable as both builders: the larger expected elements at the smaller
+        false positive probability, never smaller than either size. Other options are taken from this builder.
+
+        :param other: the other builder
+        :return:
+        """
+        return FilterBuilder(self._py_builder.merge_conservative(other._py_builder))
+
+    def validate(self) -> List[str]:
+        """
+        Checks all parameters without building a filter.
+
+        :return: a description of every problem found, empty if the parameters are valid.
+        """
+        return self._py_builder.validate()
+
+    def run_profile(self, n_insert: int, n_query: int) -> Dict[str, Union[int, float]]:
+        """
+        Builds a Bloom filter from this builder, adds `n_insert` integers and queries `n_query` inserted and
+        `n_query` never inserted integers in the Rust core. Returns a dict with `insert_time`, `hit_query_time`
+        and `miss_query_time` in seconds, the measured `false_positive_rate` and `memory_bytes` of the filter.
+
+        :param n_insert: number of elements to add
+        :param n_query: number of hit and of miss queries
+        :return:
+        """
+        return self._py_builder.run_profile(n_insert, n_query)
+
 
 class BloomFilter(object):
     """
@@ -167,6 +278,19 @@ class BloomFilter(object):
         """
         self._py_bloom.add_bytes(element)
 
+    def add_all_prefixes(self, key: Union[str, bytes]):
+        """
+        Add every non-empty prefix of key, so that prefixes can be queried with `contains`, e.g. for autocomplete.
+        The prefixes of a str end on character boundaries, those of bytes on every byte.
+
+        :param key: value whose prefixes to add
+        :return:
+        """
+        if isinstance(key, str):
+            self._py_bloom.add_all_prefixes_str(key)
+        else:
+            self._py_bloom.add_all_prefixes(key)
+
     def add_bytes_batch(self, elements: Sequence[bytes]):
         """
         Add all bytes to the filter.
@@ -265,6 +389,41 @@ class BloomFilter(object):
         """
         return self._py_bloom.contains_hash_indices(indices)
 
+    def contains_with_confidence(self, element: Union[str, int, bytes]) -> Optional[float]:
+        """
+        Tests whether an element is present in the filter, returning None if it is absent. Otherwise returns the
+        probability that the answer is a false positive given the current fill of the filter.
+
+        :param element: to test
+        :return: None or the false positive probability
+        """
+        if isinstance(element, int):
+            return self._py_bloom.contains_with_confidence_int(element)
+        elif isinstance(element, str):
+            return self._py_bloom.contains_with_confidence_str(element)
+        elif isinstance(element, bytes):
+            return self._py_bloom.contains_with_confidence_bytes(element)
+        else:
+            return self._py_bloom.contains_with_confidence_str(str(element))
+
+    def fill_ratio(self) -> float:
+        """
+        Returns the fraction of bits that are set in the Bloom filter.
+
+        :return:
+        """
+        return self._py_bloom.fill_ratio()
+
+    def probe_histogram(self, buckets: int) -> Sequence[int]:
+        """
+        Returns the number of set bits in each of `buckets` equal regions of the bit space, as a diagnostic of hash
+        quality: uniformly hashed keys give a roughly flat histogram.
+
+        :param buckets: number of regions
+        :return: set bits per region
+        """
+        return self._py_bloom.probe_histogram(buckets)
+
     def get_hash_indices(self, element: Union[str, int, bytes]) -> Sequence[int]:
         """
         Get the hashes indices of the element in the filter.
@@ -280,6 +439,40 @@ class BloomFilter(object):
         else:
             return self._py_bloom.get_hash_indices_str(str(element))
 
+    def get_word_bit_indices(self, element: Union[str, int, bytes]) -> List[Tuple[int, int]]:
+        """
+        Get the hashes indices of the element split into (word, bit) pairs, where word is the index of the machine
+        word holding the bit and bit its position in that word, e.g. to route each probe to the shard of a custom
+        bitmap store.
+        :param element: to compute
+        :return: list of (word, bit) pairs
+        """
+        if isinstance(element, int):
+            return self._py_bloom.get_word_bit_indices_int(element)
+        elif isinstance(element, str):
+            return self._py_bloom.get_word_bit_indices_str(element)
+        elif isinstance(element, bytes):
+            return self._py_bloom.get_word_bit_indices(element)
+        else:
+            return self._py_bloom.get_word_bit_indices_str(str(element))
+
+    def add_hash_pairs(self, pairs: Sequence[Tuple[int, int]]):
+        """
+        Add elements by the two 64 bit xxh3 hashes their indices are derived from, e.g. precomputed elsewhere in
+        bulk, without hashing them again.
+        :param pairs: (hash1, hash2) pair of each element
+        :return:
+        """
+        self._py_bloom.add_hash_pairs(pairs)
+
+    def contains_hash_pairs(self, pairs: Sequence[Tuple[int, int]]) -> Sequence[bool]:
+        """
+        Check whether each element given by its (hash1, hash2) pair is in the bloom filter.
+        :param pairs: (hash1, hash2) pair of each element
+        :return: whether each element is in the bloom filter
+        """
+        return self._py_bloom.contains_hash_pairs(pairs)
+
     def config(self) -> FilterBuilder:
         """
         Returns the configuration/builder of the Bloom filter.
@@ -298,12 +491,47 @@ class BloomFilter(object):
 
     def get_bytes(self) -> bytes:
         """
-        Return the underlying byte vector of the Bloom filter.
+        Return the underlying byte vector of the Bloom filter. The returned bytes support the buffer protocol, so
+        `numpy.frombuffer(bloom.get_bytes(), dtype=numpy.uint8)` wraps them without another copy.
 
         :return:
         """
         return self._py_bloom.get_bytes()
 
+    def to_bytes_with_header(self) -> bytes:
+        """
+        Return the bytes of the Bloom filter behind a header recording its hashes, hash width and seeds, so that
+        `BloomFilter.from_bytes_with_header` restores it without any other parameter.
+
+        :return:
+        """
+        return self._py_bloom.to_bytes_with_header()
+
+    @staticmethod
+    def empty_like(other: "BloomFilter") -> "BloomFilter":
+        """
+        Build an empty Bloom filter with the same size, hashes and seeds as other, e.g. as the accumulator to union
+        filters into.
+
+        :param other: filter to copy the configuration of
+        :return:
+        """
+        return BloomFilter(PyBloomFilter.empty_like(other._py_bloom))
+
+    @staticmethod
+    def from_bytes_with_header(array: bytes) -> "BloomFilter":
+        """
+        Build a Bloom filter from the bytes returned by `to_bytes_with_header`. Raises ValueError when the header is
+        missing or invalid.
+
+        :param array: header and byte array
+        :return:
+        """
+        return BloomFilter(PyBloomFilter.from_bytes_with_header(array))
+
+    def __reduce__(self):
+        return BloomFilter, (self._py_bloom,)
+
     def get_int_array(self) -> Sequence[int]:
         """
         Return the underlying u32 vector of the Bloom filter.
@@ -334,6 +562,43 @@ class BloomFilter(object):
         """
         self._py_bloom.clear()
 
+    def clear_range(self, bit_start: int, bit_end: int):
+        """
+        Resets the bits in [bit_start, bit_end) to zero, leaving the rest of the filter intact.
+
+        :param bit_start: first bit to clear
+        :param bit_end: bit after the last bit to clear
+        :return:
+        """
+        self._py_bloom.clear_range(bit_start, bit_end)
+
+    def set_bit(self, index: int):
+        """
+        Sets a single bit of the filter. Raises ValueError if index is not smaller than the size.
+
+        :param index: bit to set
+        :return:
+        """
+        self._py_bloom.set_bit(index)
+
+    def clear_bit(self, index: int):
+        """
+        Clears a single bit of the filter. Raises ValueError if index is not smaller than the size.
+
+        :param index: bit to clear
+        :return:
+        """
+        self._py_bloom.clear_bit(index)
+
+    def get_bit(self, index: int) -> bool:
+        """
+        Returns a single bit of the filter. Raises ValueError if index is not smaller than the size.
+
+        :param index: bit to read
+        :return:
+        """
+        return self._py_bloom.get_bit(index)
+
     def is_empty(self) -> bool:
         """
         Returns [true] if the Bloom filter does not contain any elements
@@ -350,6 +615,110 @@ class BloomFilter(object):
         """
         return self._py_bloom.estimate_set_cardinality()
 
+    def current_false_positive_probability(self) -> float:
+        """
+        Returns the probability that an element which was never added is reported present, given the current fill.
+
+        :return:
+        """
+        return self._py_bloom.current_false_positive_probability()
+
+    def sample_fpr(self, samples: int, seed: int = 0) -> float:
+        """
+        Measures the false positive rate by querying `samples` pseudo-random 32 byte strings, generated
+        deterministically from `seed`, and returns the fraction reported present. This is an empirical complement to
+        current_false_positive_probability, e.g. for health checks.
+
+        :param samples: number of random probes
+        :param seed: seed of the random probes
+        :return: observed false positive rate
+        """
+        return self._py_bloom.sample_fpr(samples, seed)
+
+    def should_stop_inserting(self, target_fpr: float) -> bool:
+        """
+        Returns whether the current false positive probability has reached target_fpr, as a termination signal when
+        filling the filter from a stream. The estimate is only recomputed every 4096 adds, so it can lag by that many
+        elements.
+
+        :param target_fpr: false positive probability to stop at
+        :return:
+        """
+        return self._py_bloom.should_stop_inserting(target_fpr)
+
+    def suggested_hashes(self) -> int:
+        """
+        Returns the optimal hash function number for the size of the filter and its estimated cardinality. A value
+        lower than `hashes()` means a rebuild with fewer hashes queries faster, this is advisory only.
+
+        :return:
+        """
+        return self._py_bloom.suggested_hashes()
+
+    def capacity(self) -> int:
+        """
+        Returns the number of elements the filter was sized for.
+
+        :return:
+        """
+        return self._py_bloom.capacity()
+
+    def __len__(self) -> int:
+        """
+        Returns the estimated number of distinct elements in the filter (rounded `estimate_set_cardinality`).
+        This is an estimate, not an exact count.
+
+        :return:
+        """
+        return len(self._py_bloom)
+
+    def estimate_union_cardinality(self, other: "BloomFilter") -> Optional[float]:
+        """
+        Returns estimated cardinality of the union of two compatible bloom filters, or None if
+        they are not compatible. Neither filter is modified.
+
+        :param other: the other bloom filter
+        :return:
+        """
+        return self._py_bloom.estimate_union_cardinality(other._py_bloom)
+
+    def present_only_in(self, other: "BloomFilter", candidates: Sequence[bytes]) -> List[int]:
+        """
+        Returns the positions of the candidates that may be in this filter but are definitely not in other, in order.
+        This is a set difference over a known universe of candidates, unlike the bit level difference.
+
+        :param other: the other bloom filter
+        :param candidates: bytes elements to check
+        :return:
+        """
+        return self._py_bloom.present_only_in(other._py_bloom, candidates)
+
+    def size_bits(self) -> int:
+        """
+        Returns the size of the Bloom filter in bits.
+
+        :return:
+        """
+        return self._py_bloom.size_bits()
+
+    def size_bytes(self) -> int:
+        """
+        Returns the size of the Bloom filter in bytes, the length of `get_bytes`.
+
+        :return:
+        """
+        return self._py_bloom.size_bytes()
+
+    def hamming_distance(self, other: "BloomFilter") -> Optional[int]:
+        """
+        Returns the number of bits that differ between two compatible bloom filters, or None if they are not
+        compatible. Neither filter is modified.
+
+        :param other: the other bloom filter
+        :return:
+        """
+        return self._py_bloom.hamming_distance(other._py_bloom)
+
     def union(self, other: "BloomFilter") -> bool:
         """
         Performs the union operation on two compatible bloom filters. This is achieved through a
@@ -362,6 +731,29 @@ class BloomFilter(object):
         """
         return self._py_bloom.union(other._py_bloom)
 
+    def union_bytes(self, array: bytes, hashes: int):
+        """
+        Performs the union operation with a serialized Bloom filter (see `get_bytes`) without constructing an
+        intermediate filter.
+
+        :param array: underlying byte vector of a compatible Bloom filter
+        :param hashes: hash function number of that Bloom filter
+        :return:
+        :raises ValueError: if the size or hashes are not compatible with this filter.
+        """
+        self._py_bloom.union_bytes(array, hashes)
+
+    def union_bytes_many(self, shards: Sequence[Tuple[bytes, int]]) -> int:
+        """
+        Folds serialized Bloom filters given as (bytes, hashes) pairs into this one with `union_bytes`, returning how
+        many were merged. Stops at the first incompatible pair; the pairs before it stay merged.
+
+        :param shards: pairs of the underlying byte vector and hash function number of compatible Bloom filters
+        :return:
+        :raises ValueError: if the size or hashes of a pair are not compatible with this filter.
+        """
+        return self._py_bloom.union_bytes_many(shards)
+
     def intersect(self, other: "BloomFilter") -> bool:
         """
         Performs the intersection operation on two compatible bloom filters. This is achieved
@@ -381,7 +773,7 @@ class BloomFilter(object):
     @staticmethod
     def from_bytes(array: bytes, hashes: int) -> "BloomFilter":
         """
-        Build a Bloom filter form [u8].
+        Build a Bloom filter form [u8]. Raises ValueError when the length of the array is not a multiple of 8.
 
         :param array: byte array
         :param hashes: hash function number of the Bloom filter
@@ -455,6 +847,42 @@ class CountingBloomFilter(object):
         else:
             self._py_counting_bloom.add_str(str(element))
 
+    def add_n(self, element: Union[str, int, bytes], n: int):
+        """
+        Add element to the filter n times, incrementing each of its counters by n at once (saturating at the largest
+        counter value). Without repeat insert this is the same as a single add.
+
+        :param element: value to add
+        :param n: number of times to add it
+        :return:
+        """
+        if isinstance(element, int):
+            self._py_counting_bloom.add_n_int(element, n)
+        elif isinstance(element, str):
+            self._py_counting_bloom.add_n_str(element, n)
+        elif isinstance(element, bytes):
+            self._py_counting_bloom.add_n(element, n)
+        else:
+            self._py_counting_bloom.add_n_str(str(element), n)
+
+    def remove_n(self, element: Union[str, int, bytes], n: int):
+        """
+        Remove element from the filter n times, decrementing each of its counters by n at once (saturating at zero).
+        Nothing happens unless the element appears present. Without repeat insert this is the same as a single remove.
+
+        :param element: value to remove
+        :param n: number of times to remove it
+        :return:
+        """
+        if isinstance(element, int):
+            self._py_counting_bloom.remove_n_int(element, n)
+        elif isinstance(element, str):
+            self._py_counting_bloom.remove_n_str(element, n)
+        elif isinstance(element, bytes):
+            self._py_counting_bloom.remove_n(element, n)
+        else:
+            self._py_counting_bloom.remove_n_str(str(element), n)
+
     def remove(self, element: Union[str, int, bytes]):
         """
         Remove element to the filter.
@@ -662,16 +1090,117 @@ class CountingBloomFilter(object):
         else:
             return self._py_counting_bloom.estimate_count_str(str(element))
 
+    def estimate_count_int_batch(self, elements: Sequence[int], check_type: bool = True) -> Sequence[int]:
+        """
+        Get the estimate count of every int element, looping in Rust.
+
+        :param elements: elements to count
+        :param check_type: whether to check elements in elements is type int
+        :return: the estimate count of each element in elements.
+        """
+        if check_type:
+            for ele in elements:
+                assert isinstance(ele, int)
+        return self._py_counting_bloom.estimate_count_int_batch(elements)
+
+    def estimate_count_str_batch(self, elements: Sequence[str], check_type: bool = True) -> Sequence[int]:
+        """
+        Get the estimate count of every str element, looping in Rust.
+
+        :param elements: elements to count
+        :param check_type: whether to check elements in elements is type str
+        :return: the estimate count of each element in elements.
+        """
+        if check_type:
+            for ele in elements:
+                assert isinstance(ele, str)
+        return self._py_counting_bloom.estimate_count_str_batch(elements)
+
+    def estimate_count_bytes_batch(self, elements: Sequence[bytes], check_type: bool = True) -> Sequence[int]:
+        """
+        Get the estimate count of every bytes element, looping in Rust.
+
+        :param elements: elements to count
+        :param check_type: whether to check elements in elements is type bytes
+        :return: the estimate count of each element in elements.
+        """
+        if check_type:
+            for ele in elements:
+                assert isinstance(ele, bytes)
+        return self._py_counting_bloom.estimate_count_bytes_batch(elements)
+
+    def min_count_hash_indices(self, indices: Sequence[int]) -> int:
+        """
+        Return the minimum counter over indices, e.g. from `get_hash_indices`, which is the `estimate_count` of the
+        element they belong to, without hashing it again. Returns 0 if any counter is zero.
+
+        :param indices: hash indices of an element
+        :return:
+        """
+        return self._py_counting_bloom.min_count_hash_indices(indices)
+
     def counter_at(self, index: int) -> int:
         """
         Get the underlying counter at index.
 
-        :param index: index of counter slot.
+        :param index: index of counter slot, must be non-negative.
         :return:
+        :raises ValueError: if index is negative.
         """
-        assert index >= 0
         return self._py_counting_bloom.counter_at(index)
 
+    def total_insertions(self) -> int:
+        """
+        Estimate the total number of insertions, repeats included, as the sum of all counters divided by the hash
+        function number. Collisions and saturated counters make this an estimate.
+
+        :return:
+        """
+        return self._py_counting_bloom.total_insertions()
+
+    def is_empty(self) -> bool:
+        """
+        Returns true if every counter is zero, e.g. once all added elements were removed again.
+
+        :return:
+        """
+        return self._py_counting_bloom.is_empty()
+
+    def saturated_counters(self) -> int:
+        """
+        Returns the number of counters stuck at their largest value. A growing number of them means the filter is
+        overloaded.
+
+        :return:
+        """
+        return self._py_counting_bloom.saturated_counters()
+
+    def reset_if_saturated(self, threshold: float) -> bool:
+        """
+        Clears the filter if more than threshold of its counters are saturated, and returns whether it did. This trades
+        all the elements for bounded error in long running services where the filter slowly saturates.
+
+        :param threshold: fraction of saturated counters above which the filter is cleared
+        :return: whether the filter was cleared
+        """
+        return self._py_counting_bloom.reset_if_saturated(threshold)
+
+    def size_counters(self) -> int:
+        """
+        Returns the number of counters of the Counting Bloom filter, 4 bits each.
+
+        :return:
+        """
+        return self._py_counting_bloom.size_counters()
+
+    def size_bytes(self) -> int:
+        """
+        Returns the size of the Counting Bloom filter in bytes, the length of `get_bytes`.
+
+        :return:
+        """
+        return self._py_counting_bloom.size_bytes()
+
     def config(self) -> FilterBuilder:
         """
         Returns the configuration/builder of the Bloom filter.
@@ -704,6 +1233,28 @@ class CountingBloomFilter(object):
         """
         return self._py_counting_bloom.get_int_array()
 
+    def to_portable_bytes(self) -> bytes:
+        """
+        Return the Counting Bloom filter in a format independent of the platform: a header with the hash function
+        number, enable_repeat_insert, the counter width, the number of counters and the hash seeds, followed by the
+        counters packed two per byte. Load it with `CountingBloomFilter.from_portable_bytes`.
+
+        :return:
+        """
+        return self._py_counting_bloom.to_portable_bytes()
+
+    def __reduce__(self):
+        return CountingBloomFilter, (self._py_counting_bloom,)
+
+    def to_bloom_bytes(self) -> bytes:
+        """
+        Return the membership of the Counting Bloom filter as Bloom filter bytes, one bit per counter set iff the
+        counter is nonzero. Load it with `BloomFilter.from_bytes` and the same hash function number.
+
+        :return:
+        """
+        return self._py_counting_bloom.to_bloom_bytes()
+
     def clear(self):
         """
         Removes all elements from the filter (i.e. resets all bits to zero).
@@ -740,3 +1291,156 @@ class CountingBloomFilter(object):
         """
         py_bloom = PyCountingBloomFilter.from_int_array(array, hashes, enable_repeat_insert)
         return CountingBloomFilter(py_bloom)
+
+    @staticmethod
+    def from_portable_bytes(array: bytes) -> "CountingBloomFilter":
+        """
+        Build a Counting Bloom filter from the bytes returned by `to_portable_bytes`. Raises ValueError when the bytes
+        are not in that format.
+
+        :param array: byte array
+        :return:
+        """
+        py_bloom = PyCountingBloomFilter.from_portable_bytes(array)
+        return CountingBloomFilter(py_bloom)
+
+    def subtract(self, other: "CountingBloomFilter") -> bool:
+        """
+        Subtracts the counters of a compatible Counting Bloom filter from this one counter by counter, saturating at
+        zero.
+
+        :param other: compatible Counting Bloom filter
+        :return: False without changing anything if the filters are not compatible.
+        """
+        return self._py_counting_bloom.subtract(other._py_counting_bloom)
+
+    def union_bytes(self, array: bytes, hashes: int):
+        """
+        Adds the counters of a serialized Counting Bloom filter (see `get_bytes`) to this one without constructing an
+        intermediate filter, saturating each counter at its largest value.
+
+        :param array: underlying byte vector of a compatible Counting Bloom filter
+        :param hashes: hash function number of that Counting Bloom filter
+        :return:
+        :raises ValueError: if the size or hashes are not compatible with this filter.
+        """
+        self._py_counting_bloom.union_bytes(array, hashes)
+
+    def counters_as_u8(self) -> bytes:
+        """
+        Return every counter unpacked into its own byte, byte i holding the value of counter i. Rebuild the filter with
+        `CountingBloomFilter.from_counters_u8`.
+
+        :return:
+        """
+        return self._py_counting_bloom.counters_as_u8()
+
+    @staticmethod
+    def from_counters_u8(counters: bytes, hashes: int, enable_repeat_insert: bool = True) -> "CountingBloomFilter":
+        """
+        Build a Counting Bloom filter from the bytes returned by `counters_as_u8`, one counter per byte. Raises
+        ValueError when the number of counters does not fill whole native slots.
+
+        :param counters: one byte per counter
+        :param hashes: hash function number
+        :param enable_repeat_insert: whether to enable repeat insert
+        :return:
+        """
+        py_bloom = PyCountingBloomFilter.from_counters_u8(counters, hashes, enable_repeat_insert)
+        return CountingBloomFilter(py_bloom)
+
+
+class RollingBloomFilter(object):
+    """
+    A Rolling Bloom Filter powered by rust, for "seen within the last N windows" membership. Elements are added to
+    the active window and age out after `windows` calls to `rotate`.
+
+    :param expected_elements: expected elements in each window
+    :param false_positive_probability: tolerable false positive probability of each window
+    :param windows: number of windows
+    """
+
+    def __init__(self, expected_elements: Union[int, PyRollingBloomFilter], false_positive_probability: float = 0.01,
+                 windows: int = 2):
+        if isinstance(expected_elements, int):
+            self._py_builder = PyFilterBuilder(expected_elements, false_positive_probability)
+            self._py_rolling_bloom = self._py_builder.build_rolling_bloom_filter(windows)
+        elif isinstance(expected_elements, PyRollingBloomFilter):
+            self._py_rolling_bloom = expected_elements
+        else:
+            raise Exception("expected_elements must be integer")
+
+    def add(self, element: Union[str, int, bytes]):
+        """
+        Add element to the active window.
+
+        :param element: value to add
+        :return:
+        """
+        if isinstance(element, int):
+            self._py_rolling_bloom.add_int(element)
+        elif isinstance(element, str):
+            self._py_rolling_bloom.add_str(element)
+        elif isinstance(element, bytes):
+            self._py_rolling_bloom.add_bytes(element)
+        else:
+            self._py_rolling_bloom.add_str(str(element))
+
+    def contains(self, element: Union[str, int, bytes]) -> bool:
+        """
+        Tests whether an element is present in any window (subject to the specified false positive rate).
+
+        :param element: to test
+        :return: bool
+        """
+        if isinstance(element, int):
+            return self._py_rolling_bloom.contains_int(element)
+        elif isinstance(element, str):
+            return self._py_rolling_bloom.contains_str(element)
+        elif isinstance(element, bytes):
+            return self._py_rolling_bloom.contains_bytes(element)
+        else:
+            return self._py_rolling_bloom.contains_str(str(element))
+
+    def rotate(self):
+        """
+        Clears the oldest window and makes it the active one.
+
+        :return:
+        """
+        self._py_rolling_bloom.rotate()
+
+    def windows(self) -> int:
+        """
+        Returns the number of windows.
+
+        :return:
+        """
+        return self._py_rolling_bloom.windows()
+
+    def config(self) -> FilterBuilder:
+        """
+        Returns the configuration/builder of each window.
+
+        :return:
+        """
+        return FilterBuilder(self._py_rolling_bloom.config())
+
+    def hashes(self) -> int:
+        """
+        Returns the hash function number of the Bloom filter.
+
+        :return:
+        """
+        return self._py_rolling_bloom.hashes()
+
+    def clear(self):
+        """
+        Removes all elements from every window.
+
+        :return:
+        """
+        self._py_rolling_bloom.clear()
+
+    def __contains__(self, item: Union[str, int, bytes]):
+        return self.contains(item)
@@ -1,10 +1,50 @@
 use std::ptr::slice_from_raw_parts;
+#[cfg(feature = "debug-registry")]
+use std::sync::atomic::{AtomicI64, Ordering};
 
 use fastbloom_rs::{BloomFilter, CountingBloomFilter, Deletable, FilterBuilder, Hashes, Membership};
 use jni::JNIEnv;
 use jni::objects::*;
 use jni::sys::*;
 
+/// Number of builders and filters handed to Java and not closed yet. Only tracked with the
+/// `debug-registry` feature, so that integration tests can check that nothing leaks.
+#[cfg(feature = "debug-registry")]
+static LIVE_OBJECTS: AtomicI64 = AtomicI64::new(0);
+
+/// Records a builder or filter handed to Java as a raw pointer.
+#[inline]
+fn track_created() {
+    #[cfg(feature = "debug-registry")]
+    LIVE_OBJECTS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Records a builder or filter dropped by `close0`.
+#[inline]
+fn track_closed() {
+    #[cfg(feature = "debug-registry")]
+    LIVE_OBJECTS.fetch_sub(1, Ordering::SeqCst);
+}
+
+/// Returns the UTF-8 bytes of a Java `String`, the encoding Rust `add_str` and Python `str` use.
+/// `JavaStr::to_bytes` would give the JVM's modified UTF-8 instead, which encodes `"\0"` and
+/// characters outside the Basic Multilingual Plane differently.
+fn utf8_string(env: &mut JNIEnv, element: &JString) -> String {
+    env.get_string(element).unwrap().into()
+}
+
+/// Returns the number of builders and filters not closed yet, or -1 without the `debug-registry`
+/// feature.
+#[no_mangle]
+pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_debugLiveFilterCount0<'local>(
+    mut env: JNIEnv<'local>, clz: JClass<'local>,
+) -> jlong {
+    #[cfg(feature = "debug-registry")]
+    return LIVE_OBJECTS.load(Ordering::SeqCst);
+    #[cfg(not(feature = "debug-registry"))]
+    return -1;
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_new0<'local>(
     mut env: JNIEnv<'local>, clz: JClass<'local>, expected_elements: jlong, false_positive_probability: jdouble,
@@ -13,6 +53,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_new0<'loc
 
     let builder = Box::new(builder);
 
+    track_created();
     Box::into_raw(builder) as jlong
 }
 
@@ -24,6 +65,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_fromSizeA
 
     let builder = Box::new(builder);
 
+    track_created();
     Box::into_raw(builder) as jlong
 }
 
@@ -49,6 +91,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_buildBloo
 
     Box::into_raw(builder); // keep builder alive.
 
+    track_created();
     Box::into_raw(filter) as jlong
 }
 
@@ -62,6 +105,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_buildCoun
 
     Box::into_raw(builder); // keep builder alive.
 
+    track_created();
     Box::into_raw(filter) as jlong
 }
 
@@ -72,6 +116,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_FilterBuilder_close0<'l
     let mut builder = Box::from_raw(raw as *mut FilterBuilder);
 
     drop(builder);
+    track_closed();
 }
 
 
@@ -141,9 +186,9 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_addStr0<'lo
 ) {
     let mut filter = Box::from_raw(raw as *mut BloomFilter);
 
-    let element = env.get_string(&element).unwrap();
+    let element = utf8_string(&mut env, &element);
 
-    filter.add(element.to_bytes());
+    filter.add(element.as_bytes());
 
     Box::into_raw(filter); // keep builder alive.
 }
@@ -184,9 +229,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_containsLon
 ) -> jboolean {
     let mut filter = Box::from_raw(raw as *mut BloomFilter);
 
-    let element = element as i64;
-
-    let res = filter.contains(&i64::to_le_bytes(element));
+    let res = filter.contains_int(element as i64);
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -199,9 +242,9 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_containsStr
 ) -> jboolean {
     let mut filter = Box::from_raw(raw as *mut BloomFilter);
 
-    let element = env.get_string(&element).unwrap();
+    let element = utf8_string(&mut env, &element);
 
-    let res = filter.contains(element.to_bytes());
+    let res = filter.contains(element.as_bytes());
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -244,6 +287,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_fromBytes0<
 
     let filter = Box::new(BloomFilter::from_u8_array(&bytes, hashes as u32));
 
+    track_created();
     Box::into_raw(filter) as jlong
 }
 
@@ -268,7 +312,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_getSize0<'l
     mut env: JNIEnv<'local>, clz: JClass<'local>, raw: jlong,
 ) -> jint {
     let mut filter = Box::from_raw(raw as *mut BloomFilter);
-    let size = filter.config().size >> 3;
+    let size = filter.size_bytes();
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -343,6 +387,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_BloomFilter_close0<'loc
     let filter = Box::from_raw(raw as *mut BloomFilter);
 
     drop(filter);
+    track_closed();
 }
 
 
@@ -417,9 +462,9 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_add
 ) {
     let mut filter = Box::from_raw(raw as *mut CountingBloomFilter);
 
-    let element = env.get_string(&element).unwrap();
+    let element = utf8_string(&mut env, &element);
 
-    filter.add(element.to_bytes());
+    filter.add(element.as_bytes());
 
     Box::into_raw(filter); // keep builder alive.
 }
@@ -430,9 +475,9 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_rem
 ) {
     let mut filter = Box::from_raw(raw as *mut CountingBloomFilter);
 
-    let element = env.get_string(&element).unwrap();
+    let element = utf8_string(&mut env, &element);
 
-    filter.remove(element.to_bytes());
+    filter.remove(element.as_bytes());
 
     Box::into_raw(filter); // keep builder alive.
 }
@@ -484,9 +529,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_con
 ) -> jboolean {
     let mut filter = Box::from_raw(raw as *mut CountingBloomFilter);
 
-    let element = element as i64;
-
-    let res = filter.contains(&i64::to_le_bytes(element));
+    let res = filter.contains_int(element as i64);
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -499,9 +542,9 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_con
 ) -> jboolean {
     let mut filter = Box::from_raw(raw as *mut CountingBloomFilter);
 
-    let element = env.get_string(&element).unwrap();
+    let element = utf8_string(&mut env, &element);
 
-    let res = filter.contains(element.to_bytes());
+    let res = filter.contains(element.as_bytes());
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -549,7 +592,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_get
     mut env: JNIEnv<'local>, clz: JClass<'local>, raw: jlong,
 ) -> jint {
     let mut filter = Box::from_raw(raw as *mut CountingBloomFilter);
-    let size = filter.config().size >> 1;
+    let size = filter.size_bytes();
 
     Box::into_raw(filter); // keep builder alive.
 
@@ -589,6 +632,19 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_cle
     Box::into_raw(filter); // keep builder alive.
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_isEmpty0<'local>(
+    mut env: JNIEnv<'local>, clz: JClass<'local>, raw: jlong,
+) -> jboolean {
+    let filter = Box::from_raw(raw as *mut CountingBloomFilter);
+
+    let res = filter.is_empty();
+
+    Box::into_raw(filter); // keep builder alive.
+
+    res as jboolean
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_close0<'local>(
     mut env: JNIEnv<'local>, clz: JClass<'local>, raw: jlong,
@@ -596,6 +652,7 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_clo
     let mut builder = Box::from_raw(raw as *mut CountingBloomFilter);
 
     drop(builder);
+    track_closed();
 }
 
 #[no_mangle]
@@ -608,5 +665,6 @@ pub unsafe extern "C" fn Java_io_github_yankun1992_bloom_CountingBloomFilter_fro
 
     let filter = Box::new(CountingBloomFilter::from_u8_array(&bytes, hashes as u32, enable_repeat_insert));
 
+    track_created();
     Box::into_raw(filter) as jlong
 }
\ No newline at end of file
@@ -185,7 +185,17 @@ public class BloomFilter extends NativeLoader implements AutoCloseable {
     }
 
 
+    /**
+     * Build a Bloom filter form [u8].
+     *
+     * @param array  byte array, its length must be a multiple of 8
+     * @param hashes hash function number of the Bloom filter
+     * @return BloomFilter
+     */
     public static BloomFilter fromBytes(byte[] array, int hashes) throws IOException {
+        if (array.length % 8 != 0) {
+            throw new IllegalArgumentException(array.length + " bytes do not hold whole 64 bit words");
+        }
         NativeLoader.load("fastbloom");
         long raw = fromBytes0(array, hashes);
 
@@ -171,6 +171,13 @@ public class CountingBloomFilter extends NativeLoader implements AutoCloseable {
         clear0(raw);
     }
 
+    /**
+     * @return true if every counter is zero, e.g. once all added elements were removed again
+     */
+    public boolean isEmpty() {
+        return isEmpty0(raw);
+    }
+
     @Override
     public void close() throws Exception {
         close0(raw);
@@ -236,6 +243,8 @@ public class CountingBloomFilter extends NativeLoader implements AutoCloseable {
 
     private static native void clear0(long raw);
 
+    private static native boolean isEmpty0(long raw);
+
 
     private static native void close0(long raw);
 
@@ -97,6 +97,17 @@ public class FilterBuilder extends NativeLoader implements AutoCloseable {
         close0(raw);
     }
 
+    /**
+     * Returns the number of builders and filters created by the native library and not closed yet, for leak checks
+     * in tests. Only tracked when the native library is built with the {@code debug-registry} feature.
+     *
+     * @return number of live native objects, or -1 if they are not tracked
+     */
+    public static long debugLiveFilterCount() throws IOException {
+        NativeLoader.load("fastbloom");
+        return debugLiveFilterCount0();
+    }
+
     private static long open(long expected_elements, double false_positive_probability) throws IOException {
         NativeLoader.load("fastbloom");
         return new0(expected_elements, false_positive_probability);
@@ -118,5 +129,7 @@ public class FilterBuilder extends NativeLoader implements AutoCloseable {
 
     private static native long buildCountingBloomFilter0(long raw);
 
+    private static native long debugLiveFilterCount0();
+
 
 }
@@ -19,6 +19,8 @@ package io.github.yankun1992.bloom;
 import org.junit.Assert;
 import org.junit.Test;
 
+import java.nio.charset.StandardCharsets;
+
 public class BloomFilterTest {
 
     @Test
@@ -160,4 +162,28 @@ public class BloomFilterTest {
         }
     }
 
+    @Test
+    public void testEmptyElement() {
+        try (FilterBuilder builder = new FilterBuilder(100_000, 0.01)) {
+            try (BloomFilter bloom = builder.buildBloomFilter()) {
+                Assert.assertFalse(bloom.containsStr(""));
+                bloom.addStr("");
+                Assert.assertTrue(bloom.containsStr(""));
+                Assert.assertTrue(bloom.containsBytes(new byte[0]));
+                Assert.assertFalse(bloom.containsStr("\0"));
+                for (int i = 0; i < 10_000; i++) {
+                    Assert.assertFalse(bloom.containsInt(i));
+                }
+
+                // strings are hashed as their standard UTF-8 bytes, like in Rust and Python.
+                bloom.addStr("a\0b");
+                bloom.addStr("\uD83D\uDE00");
+                Assert.assertTrue(bloom.containsBytes("a\0b".getBytes(StandardCharsets.UTF_8)));
+                Assert.assertTrue(bloom.containsBytes("\uD83D\uDE00".getBytes(StandardCharsets.UTF_8)));
+            }
+        } catch (Exception e) {
+            throw new RuntimeException(e);
+        }
+    }
+
 }
@@ -89,4 +89,17 @@ public class CountingBloomFilterTest {
         }
     }
 
+    @Test
+    public void testIsEmpty() throws Exception {
+        try (FilterBuilder builder = new FilterBuilder(100_000, 0.01)) {
+            try (CountingBloomFilter bloom = builder.buildCountingBloomFilter()) {
+                Assert.assertTrue(bloom.isEmpty());
+                bloom.addStr("hello");
+                Assert.assertFalse(bloom.isEmpty());
+                bloom.removeStr("hello");
+                Assert.assertTrue(bloom.isEmpty());
+            }
+        }
+    }
+
 }
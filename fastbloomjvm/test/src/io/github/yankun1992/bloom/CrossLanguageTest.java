@@ -23,6 +23,9 @@ public class CrossLanguageTest {
 
             // In Python API, `add_int` is same as `addLong` in java, because python `int` type is `i64` in Rust
             Assert.assertTrue(filter.containsLong(87));
+            // Python `add('')` adds the empty element.
+            Assert.assertTrue(filter.containsStr(""));
+            Assert.assertTrue(filter.containsBytes(new byte[0]));
         } catch (Exception e) {
             throw new RuntimeException(e);
         }
@@ -44,6 +47,9 @@ public class CrossLanguageTest {
             Assert.assertTrue(filter.containsBytes("hello".getBytes()));
             // In Python API, `add_int` is same as `addLong` in java, because python `int` type is `i64` in Rust
             Assert.assertTrue(filter.containsLong(87));
+            // Python `add('')` adds the empty element.
+            Assert.assertTrue(filter.containsStr(""));
+            Assert.assertTrue(filter.containsBytes(new byte[0]));
         } catch (Exception e) {
             throw new RuntimeException(e);
         }
@@ -18,6 +18,7 @@ package io.github.yankun1992.bloom;
 
 import org.junit.After;
 import org.junit.Assert;
+import org.junit.Assume;
 import org.junit.Before;
 import org.junit.Test;
 
@@ -110,4 +111,22 @@ public class FilterBuilderTest {
         }
 
     }
+
+    @Test
+    public void testNoLeaks() throws Exception {
+        long before = FilterBuilder.debugLiveFilterCount();
+        Assume.assumeTrue("native library built without the debug-registry feature", before >= 0);
+
+        FilterBuilder builder1 = new FilterBuilder(10000, 0.01);
+        BloomFilter bloom = builder1.buildBloomFilter();
+        CountingBloomFilter counting = builder1.buildCountingBloomFilter();
+        BloomFilter copy = BloomFilter.fromBytes(bloom.getBytes(), bloom.hashes());
+        Assert.assertEquals(before + 4, FilterBuilder.debugLiveFilterCount());
+
+        copy.close();
+        counting.close();
+        bloom.close();
+        builder1.close();
+        Assert.assertEquals(before, FilterBuilder.debugLiveFilterCount());
+    }
 }
@@ -2,6 +2,7 @@
 
 from fastbloom_rs import BloomFilter, FilterBuilder
 import os
+import pytest
 
 
 def test_bloom_builder():
@@ -50,6 +51,9 @@ def test_bloom_builder():
     assert b'hello' in bloom4
     assert not bloom4.contains_bytes(b'hello world')
 
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(bloom.get_bytes()[:-4], bloom.hashes())
+
 
 def test_bloom_add():
     bloom = BloomFilter(100_000_000, 0.01)
@@ -72,6 +76,147 @@ def test_bloom_estimate_set_cardinality():
         bloom.estimate_set_cardinality() > 9_900_000)
 
 
+def test_bloom_suggested_hashes():
+    bloom = BloomFilter.from_bytes(bytes(12_504), 30)
+    for data in range(0, 5_000):
+        bloom.add_int(data)
+    assert bloom.suggested_hashes() < bloom.hashes()
+
+
+def test_bloom_should_stop_inserting():
+    bloom = BloomFilter(10_000, 0.01)
+    inserted = 0
+    while not bloom.should_stop_inserting(0.01):
+        bloom.add_int(inserted)
+        inserted += 1
+    assert inserted >= 9_000
+    assert bloom.current_false_positive_probability() >= 0.01
+
+
+def test_bloom_sample_fpr():
+    bloom = BloomFilter(10_000, 0.01)
+    for x in range(10_000):
+        bloom.add_int(x)
+    sampled = bloom.sample_fpr(100_000, 3)
+    analytical = bloom.current_false_positive_probability()
+    assert analytical / 1.5 < sampled < analytical * 1.5
+    with pytest.raises(ValueError):
+        bloom.sample_fpr(0, 3)
+
+
+def test_bloom_estimate_union_cardinality():
+    a = BloomFilter(100_000, 0.01)
+    b = BloomFilter(100_000, 0.01)
+    for data in range(0, 30_000):
+        a.add_int(data)
+    for data in range(20_000, 60_000):
+        b.add_int(data)
+
+    estimate = a.estimate_union_cardinality(b)
+    assert 59_400 < estimate < 60_600
+    assert 29_700 < a.estimate_set_cardinality() < 30_300
+    assert a.estimate_union_cardinality(BloomFilter(1_000, 0.01)) is None
+
+
+def test_bloom_present_only_in():
+    a = BloomFilter(10_000, 0.001)
+    b = BloomFilter(10_000, 0.001)
+    a.add(b'hello')
+    a.add(b'world')
+    b.add(b'hello')
+    b.add(b'other')
+    assert a.present_only_in(b, [b'hello', b'world', b'other', b'none']) == [1]
+    assert b.present_only_in(a, [b'hello', b'world', b'other', b'none']) == [2]
+
+
+def test_bloom_clear_range():
+    bloom = BloomFilter(10_000, 0.01)
+    bloom.add_int(1)
+    size = bloom.config().size()
+    bloom.clear_range(0, 0)
+    assert bloom.contains_int(1)
+    bloom.clear_range(0, size)
+    assert not bloom.contains_int(1)
+    assert not any(bloom.get_bytes())
+    with pytest.raises(ValueError):
+        bloom.clear_range(0, size + 1)
+
+
+def test_bloom_bit_accessors():
+    bloom = BloomFilter(10_000, 0.01)
+    size = bloom.config().size()
+    bloom.set_bit(7)
+    assert bloom.get_bit(7)
+    bloom.clear_bit(7)
+    assert not bloom.get_bit(7)
+    with pytest.raises(ValueError):
+        bloom.set_bit(size)
+
+
+def test_builder_merge_conservative():
+    merged = FilterBuilder(1_000_000, 0.01).merge_conservative(FilterBuilder(10_000_000, 0.001))
+    assert merged.expected_elements() == 10_000_000
+    assert merged.false_positive_probability() == 0.001
+    assert merged.size() >= FilterBuilder(10_000_000, 0.001).build_bloom_filter().config().size()
+
+
+def test_builder_for_cardinality():
+    bloom = FilterBuilder.for_cardinality(100_000, 0.02).build_bloom_filter()
+    assert bloom.hashes() == 1
+    for x in range(100_000):
+        bloom.add(x)
+    assert abs(bloom.estimate_set_cardinality() - 100_000) <= 0.02 * 100_000
+    with pytest.raises(ValueError):
+        FilterBuilder.for_cardinality(100_000, 0.0)
+
+
+def test_builder_validate():
+    assert FilterBuilder(100_000, 0.01).validate() == []
+    issues = FilterBuilder(0, 2.0).validate()
+    assert len(issues) == 2
+    assert 'expected_elements' in issues[0]
+    assert 'false_positive_probability' in issues[1]
+
+
+def test_builder_build_invalid():
+    with pytest.raises(ValueError, match='expected_elements'):
+        FilterBuilder(0, 2.0).build_bloom_filter()
+    with pytest.raises(ValueError):
+        FilterBuilder(0, 2.0).build_counting_bloom_filter()
+    with pytest.raises(ValueError, match='expected_elements'):
+        FilterBuilder(0, 2.0).build_rolling_bloom_filter(2)
+    with pytest.raises(ValueError, match='windows'):
+        FilterBuilder(100_000, 0.01).build_rolling_bloom_filter(0)
+
+
+def test_run_profile():
+    result = FilterBuilder(100_000, 0.01).run_profile(100_000, 100_000)
+    assert result['insert_time'] > 0
+    assert result['hit_query_time'] > 0
+    assert result['miss_query_time'] > 0
+    assert 0 < result['false_positive_rate'] < 0.02
+    assert result['memory_bytes'] * 8 == FilterBuilder(100_000, 0.01).build_bloom_filter().config().size()
+
+
+def test_bloom_len():
+    bloom = BloomFilter(100_000, 0.01)
+    assert len(bloom) == 0
+    assert bloom.capacity() == 100_000
+    for data in range(0, 50_000):
+        bloom.add_int(data)
+
+    assert 49_500 < len(bloom) < 50_500
+
+
+def test_bloom_hamming_distance():
+    bloom = BloomFilter(10_000, 0.01)
+    snapshot = BloomFilter(10_000, 0.01)
+    assert bloom.hamming_distance(snapshot) == 0
+    bloom.add('hello')
+    assert bloom.hamming_distance(snapshot) == bloom.hashes()
+    assert bloom.hamming_distance(BloomFilter(1_000, 0.01)) is None
+
+
 def test_bloom_op():
     bloom = BloomFilter(100_000_000, 0.001)
     bloom.add_bytes(b'hello')
@@ -201,3 +346,181 @@ def test_save_load_file():
     assert not bloom.contains('world')
 
     os.remove('fst.bloom')
+
+
+def test_contains_with_confidence():
+    bloom = BloomFilter(10_000, 0.01)
+    assert bloom.fill_ratio() == 0.0
+    for i in range(10_000):
+        bloom.add(i)
+
+    error = bloom.contains_with_confidence(42)
+    assert 0.0 < error < 0.02
+    assert bloom.contains_with_confidence('hello') is None
+
+
+def test_from_fpr_and_hashes():
+    builder = FilterBuilder.from_fpr_and_hashes(100_000, 0.01, 4)
+    assert builder.hashes() == 4
+    assert builder.size() > FilterBuilder(100_000, 0.01).build_bloom_filter().config().size()
+
+    bloom = builder.build_bloom_filter()
+    bloom.add('hello')
+    assert 'hello' in bloom
+
+    with pytest.raises(ValueError, match='hashes'):
+        FilterBuilder.from_fpr_and_hashes(100_000, 0.01, 0)
+    for fpr in [0.0, 1.0, -0.5, 2.0]:
+        with pytest.raises(ValueError, match='false_positive_probability'):
+            FilterBuilder.from_fpr_and_hashes(100_000, fpr, 4)
+    with pytest.raises(ValueError, match='expected_elements'):
+        FilterBuilder.from_fpr_and_hashes(0, 0.01, 4)
+
+
+def test_canonical_int_encoding():
+    bloom = BloomFilter(10_000, 0.01)
+    bloom.add(87)
+    bloom.add(-1)
+
+    # ints are hashed as the 8 little-endian bytes of an i64, same as Rust `contains_int`.
+    assert bloom.contains((87).to_bytes(8, 'little', signed=True))
+    assert bloom.contains((-1).to_bytes(8, 'little', signed=True))
+    assert not bloom.contains((87).to_bytes(4, 'little', signed=True))
+
+
+def test_union_bytes():
+    bloom = BloomFilter(10_000, 0.01)
+    other = BloomFilter(10_000, 0.01)
+    bloom.add('hello')
+    other.add('world')
+
+    expected = BloomFilter.from_bytes(bloom.get_bytes(), bloom.hashes())
+    expected.union(other)
+
+    bloom.union_bytes(other.get_bytes(), other.hashes())
+    assert bloom.get_bytes() == expected.get_bytes()
+    assert 'world' in bloom
+
+    with pytest.raises(ValueError):
+        bloom.union_bytes(other.get_bytes()[8:], other.hashes())
+
+
+def test_hash_seeds():
+    builder = FilterBuilder(10_000, 0.01)
+    builder.hash_seeds(42, 4242)
+    bloom = builder.build_bloom_filter()
+    bloom.add('hello')
+    assert bloom.contains('hello')
+    assert bloom.get_hash_indices('hello') != BloomFilter(10_000, 0.01).get_hash_indices('hello')
+
+
+def test_union_bytes_many():
+    shards = []
+    for shard in range(10):
+        other = BloomFilter(10_000, 0.01)
+        other.add_int_batch(list(range(shard * 100, (shard + 1) * 100)))
+        shards.append((other.get_bytes(), other.hashes()))
+
+    bloom = BloomFilter(10_000, 0.01)
+    assert bloom.union_bytes_many(shards) == 10
+    assert all(bloom.contains_int_batch(list(range(1000))))
+
+    with pytest.raises(ValueError):
+        bloom.union_bytes_many([(shards[0][0], shards[0][1] + 1)])
+
+
+def test_size_bytes():
+    for n, p in [(1000, 0.01), (10_000, 0.001), (123_457, 1e-6)]:
+        bloom = BloomFilter(n, p)
+        assert bloom.size_bytes() == len(bloom.get_bytes())
+        assert bloom.size_bits() == bloom.size_bytes() * 8
+
+
+def test_add_all_prefixes():
+    bloom = BloomFilter(10_000, 0.01)
+    bloom.add_all_prefixes('héllo')
+    assert 'hél' in bloom
+    assert 'héllo' in bloom
+    assert 'hello' not in bloom
+
+    bloom.add_all_prefixes(b'world')
+    assert bloom.contains(b'wor')
+
+
+def test_get_word_bit_indices():
+    bloom = BloomFilter(100_000, 0.01)
+    for element in [b'hello', 'world', 87]:
+        pairs = bloom.get_word_bit_indices(element)
+        assert [word * 64 + bit for word, bit in pairs] == list(bloom.get_hash_indices(element))
+
+
+def test_hash_pairs():
+    bloom = BloomFilter(100_000, 0.01)
+    m, k = bloom.config().size(), bloom.hashes()
+    pairs = [(x * 0x9E3779B97F4A7C15 % 2 ** 64, x * 0xC2B2AE3D27D4EB4F % 2 ** 64 + 1) for x in range(1, 100)]
+    bloom.add_hash_pairs(pairs)
+    for hash1, hash2 in pairs:
+        start, step = hash1 % m, (hash2 % m) or 1
+        assert bloom.contains_hash_indices([(start + i * step) % m for i in range(k)])
+    assert bloom.contains_hash_pairs(pairs) == [True] * len(pairs)
+    assert bloom.contains_hash_pairs([(1, 2), (3, 4)]) == [False, False]
+    assert bloom.contains_hash_pairs([]) == []
+
+
+def test_empty_like():
+    bloom = BloomFilter(100_000, 0.01)
+    bloom.add('hello')
+    acc = BloomFilter.empty_like(bloom)
+    assert acc.config().size() == bloom.config().size()
+    assert acc.hashes() == bloom.hashes()
+    assert not any(acc.get_bytes())
+    assert acc.is_empty()
+    assert not bloom.is_empty()
+    assert not acc.contains('hello')
+    assert acc.union(bloom)
+    assert acc.contains('hello')
+    assert acc.get_bytes() == bloom.get_bytes()
+
+
+def test_optimally_build_from():
+    elements = [x if x % 3 == 0 else str(x) if x % 3 == 1 else str(x).encode() for x in range(1000)]
+    bloom = FilterBuilder.optimally_build_from(0.01, elements)
+    assert bloom.config().expected_elements() == 1000
+    for element in elements:
+        assert element in bloom
+    with pytest.raises(ValueError):
+        FilterBuilder.optimally_build_from(1.5, elements)
+
+
+def test_probe_histogram():
+    bloom = BloomFilter(100_000, 0.01)
+    for x in range(20_000):
+        bloom.add(x)
+    histogram = bloom.probe_histogram(32)
+    assert len(histogram) == 32
+    expected = sum(histogram) / 32
+    # chi-square with 31 degrees of freedom, whose 99.9% quantile is about 61.
+    assert sum((observed - expected) ** 2 / expected for observed in histogram) < 62
+    with pytest.raises(ValueError):
+        bloom.probe_histogram(0)
+
+
+def test_empty_element():
+    bloom = BloomFilter(10_000, 0.01)
+    assert '' not in bloom
+    bloom.add('')
+    assert '' in bloom
+    assert b'' in bloom
+    assert bloom.get_hash_indices('') == bloom.get_hash_indices(b'')
+    assert not any(x in bloom for x in range(10_000))
+    assert '\0' not in bloom
+
+
+def test_final_size():
+    builder = FilterBuilder(100_000, 0.01)
+    size, hashes = builder.final_size(), builder.final_hashes()
+    assert size % 64 == 0
+    assert builder.size() == 0
+    config = builder.build_bloom_filter().config()
+    assert config.size() == size
+    assert config.hashes() == hashes
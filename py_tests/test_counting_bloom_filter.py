@@ -1,6 +1,8 @@
 # -*- coding: utf-8 -*-
 
-from fastbloom_rs import CountingBloomFilter, FilterBuilder
+import pytest
+
+from fastbloom_rs import BloomFilter, CountingBloomFilter, FilterBuilder
 
 
 def test_builder():
@@ -26,6 +28,9 @@ def test_from_builder():
     cbf_copy = CountingBloomFilter.from_int_array(cbf.get_int_array(), cbf.hashes())
     assert 'hello' in cbf_copy
 
+    with pytest.raises(ValueError):
+        CountingBloomFilter.from_bytes(cbf.get_bytes()[:-1], cbf.hashes())
+
 
 def test_repeat_insert():
     builder = FilterBuilder(100_000, 0.01)
@@ -114,6 +119,47 @@ def test_estimate_count():
     assert cbf.estimate_count(b'hello') == 2
 
 
+def test_counter_at_negative_index():
+    builder = FilterBuilder(100_000, 0.01)
+    cbf = builder.build_counting_bloom_filter()  # type: CountingBloomFilter
+
+    with pytest.raises(ValueError):
+        cbf.counter_at(-1)
+
+
+def test_estimate_count_not_truncated():
+    builder = FilterBuilder(100_000, 0.01)
+    # enable repeat insert
+    builder.enable_repeat_insert(True)
+    cbf = builder.build_counting_bloom_filter()  # type: CountingBloomFilter
+
+    for _ in range(10):
+        cbf.add(2 ** 40)
+        cbf.add('hello')
+        cbf.add(b'world')
+
+    # counts round-trip as plain ints for every key type, and the element is not cast to 32 bits.
+    assert cbf.estimate_count(2 ** 40) == 10
+    assert cbf.estimate_count(2 ** 40 + 2 ** 32) == 0
+    assert cbf.estimate_count('hello') == 10
+    assert cbf.estimate_count(b'world') == 10
+    for index in cbf.get_hash_indices(b'world'):
+        assert cbf.counter_at(index) >= 10
+
+
+def test_total_insertions():
+    builder = FilterBuilder(100_000, 0.01)
+    builder.enable_repeat_insert(True)
+    cbf = builder.build_counting_bloom_filter()  # type: CountingBloomFilter
+    assert cbf.total_insertions() == 0
+
+    for _ in range(3):
+        for data in range(1_000):
+            cbf.add(data)
+
+    assert 2_970 < cbf.total_insertions() <= 3_000
+
+
 def test_batch():
     builder = FilterBuilder(100_000, 0.01)
     # enable repeat insert
@@ -133,3 +179,157 @@ def test_batch():
 
     bloom.add_bytes_batch(list(map(lambda x: bytes(x), inserts)))
     assert bloom.contains_bytes_batch(list(map(lambda x: bytes(x), checks))) == results
+
+
+def test_portable_bytes():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add('hello')
+    cbf.add('hello')
+    cbf.add('world')
+
+    loaded = CountingBloomFilter.from_portable_bytes(cbf.to_portable_bytes())
+    assert loaded.estimate_count('hello') == 2
+    assert loaded.estimate_count('world') == 1
+    assert loaded.estimate_count('other') == cbf.estimate_count('other')
+
+    with pytest.raises(ValueError):
+        CountingBloomFilter.from_portable_bytes(cbf.to_portable_bytes()[:-1])
+
+
+def test_union_bytes():
+    a = CountingBloomFilter(10_000, 0.01)
+    b = CountingBloomFilter(10_000, 0.01)
+    a.add_n('hello', 2)
+    b.add_n('hello', 3)
+    b.add('world')
+
+    total = CountingBloomFilter(10_000, 0.01)
+    total.union_bytes(a.get_bytes(), a.hashes())
+    total.union_bytes(b.get_bytes(), b.hashes())
+    assert total.estimate_count('hello') == 5
+    assert total.estimate_count('world') == 1
+    with pytest.raises(ValueError):
+        total.union_bytes(a.get_bytes()[8:], a.hashes())
+
+
+def test_counters_u8():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add_n('hello', 3)
+    cbf.add('world')
+    counters = cbf.counters_as_u8()
+    assert len(counters) == cbf.size_counters()
+    assert max(counters) >= 3
+
+    loaded = CountingBloomFilter.from_counters_u8(counters, cbf.hashes())
+    assert loaded.counters_as_u8() == counters
+    assert loaded.estimate_count('hello') == 3
+    with pytest.raises(ValueError):
+        CountingBloomFilter.from_counters_u8(counters[:-1], cbf.hashes())
+
+
+def test_to_bloom_bytes():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add('hello')
+    cbf.add('world')
+    cbf.remove('world')
+
+    bloom = BloomFilter.from_bytes(cbf.to_bloom_bytes(), cbf.hashes())
+    assert bloom.contains('hello')
+    assert bloom.contains('world') == cbf.contains('world')
+
+
+def test_min_count_hash_indices():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add('hello')
+    cbf.add('hello')
+
+    assert cbf.min_count_hash_indices(cbf.get_hash_indices('hello')) == cbf.estimate_count('hello') == 2
+    assert cbf.min_count_hash_indices(cbf.get_hash_indices('world')) == 0
+
+
+def test_size_bytes():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    assert cbf.size_bytes() == len(cbf.get_bytes())
+    assert cbf.size_counters() == cbf.size_bytes() * 2
+
+
+def test_add_n():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add_n('hello', 5)
+    cbf.add_n(42, 3)
+    cbf.add_n(b'world', 2)
+    assert cbf.estimate_count('hello') == 5
+    assert cbf.estimate_count(42) == 3
+    assert cbf.estimate_count(b'world') == 2
+
+
+def test_remove_n():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add_n('key', 10)
+    cbf.remove_n('key', 3)
+    assert cbf.estimate_count('key') == 7
+    cbf.add_n(42, 2)
+    cbf.remove_n(42, 5)
+    assert cbf.estimate_count(42) == 0
+
+
+def test_reset_if_saturated():
+    cbf = CountingBloomFilter(100, 0.01)
+    cbf.add('hello')
+    assert not cbf.reset_if_saturated(0.5)
+    for x in range(2000):
+        cbf.add_n(x, 15)
+    assert cbf.saturated_counters() > cbf.size_counters() // 2
+    assert cbf.reset_if_saturated(0.5)
+    assert cbf.saturated_counters() == 0
+    assert 'hello' not in cbf
+
+
+def test_estimate_count_batch():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add_n(7, 3)
+    cbf.add_n('hello', 2)
+    cbf.add_n(b'world', 4)
+    ints = [7, 8, 2 ** 40]
+    strs = ['hello', 'missing']
+    bts = [b'world', b'hello']
+    assert cbf.estimate_count_int_batch(ints) == [cbf.estimate_count(x) for x in ints]
+    assert cbf.estimate_count_str_batch(strs) == [cbf.estimate_count(x) for x in strs]
+    assert cbf.estimate_count_bytes_batch(bts) == [cbf.estimate_count(x) for x in bts]
+    assert cbf.estimate_count_int_batch(ints)[0] == 3
+    assert cbf.estimate_count_str_batch(strs) == [2, 0]
+    assert cbf.estimate_count_bytes_batch(bts)[0] == 4
+
+
+def test_is_empty():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    assert cbf.is_empty()
+    cbf.add('hello')
+    cbf.add(87)
+    assert not cbf.is_empty()
+    cbf.remove('hello')
+    assert not cbf.is_empty()
+    cbf.remove(87)
+    assert cbf.is_empty()
+
+
+def test_subtract():
+    a = CountingBloomFilter(10_000, 0.01)
+    b = CountingBloomFilter(10_000, 0.01)
+    a.add_n('key', 5)
+    b.add_n('key', 2)
+    assert a.subtract(b)
+    assert a.estimate_count('key') == 3
+    assert b.estimate_count('key') == 2
+    assert not a.subtract(CountingBloomFilter(1_000, 0.01))
+    assert a.estimate_count('key') == 3
+
+
+def test_empty_element():
+    cbf = CountingBloomFilter(10_000, 0.01)
+    cbf.add(b'')
+    assert '' in cbf
+    assert cbf.estimate_count('') == 1
+    cbf.remove('')
+    assert b'' not in cbf
+    assert cbf.is_empty()
@@ -0,0 +1,34 @@
+import pickle
+
+from fastbloom_rs import BloomFilter, CountingBloomFilter, FilterBuilder
+
+
+def test_pickle_bloom():
+    bloom = BloomFilter(10_000, 0.01)
+    bloom.add('hello')
+    bloom.add(42)
+    loaded = pickle.loads(pickle.dumps(bloom))
+    assert 'hello' in loaded
+    assert 42 in loaded
+    assert 'world' not in loaded
+    assert loaded.hashes() == bloom.hashes()
+    assert loaded.get_bytes() == bloom.get_bytes()
+
+    built = FilterBuilder(10_000, 0.001).build_bloom_filter()
+    built.add(b'world')
+    assert b'world' in pickle.loads(pickle.dumps(built))
+
+    raw = pickle.loads(pickle.dumps(bloom._py_bloom))
+    assert raw.get_bytes() == bloom.get_bytes()
+
+
+def test_pickle_counting_bloom():
+    cbf = CountingBloomFilter(10_000, 0.01, enable_repeat_insert=False)
+    cbf.add('hello')
+    cbf.add('hello')
+    loaded = pickle.loads(pickle.dumps(cbf))
+    assert 'hello' in loaded
+    assert loaded.estimate_count('hello') == 1
+    loaded.remove('hello')
+    assert 'hello' not in loaded
+    assert 'hello' in cbf
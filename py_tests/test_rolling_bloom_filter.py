@@ -0,0 +1,37 @@
+# -*- coding: utf-8 -*-
+
+from fastbloom_rs import FilterBuilder, RollingBloomFilter
+
+
+def test_rotate():
+    rolling = RollingBloomFilter(100_000, 0.01, windows=3)  # type: RollingBloomFilter
+    assert rolling.windows() == 3
+
+    rolling.add('hello')
+    rolling.add(87)
+    assert 'hello' in rolling
+    assert 87 in rolling
+
+    rolling.rotate()
+    rolling.add(b'world')
+    rolling.rotate()
+    assert 'hello' in rolling
+    assert b'world' in rolling
+
+    rolling.rotate()
+    assert 'hello' not in rolling
+    assert 87 not in rolling
+    assert b'world' in rolling
+
+    rolling.rotate()
+    assert b'world' not in rolling
+
+
+def test_builder():
+    builder = FilterBuilder(100_000, 0.01)
+    rolling = builder.build_rolling_bloom_filter(2)  # type: RollingBloomFilter
+    rolling.add('hello')
+    assert rolling.hashes() == builder.hashes()
+
+    rolling.clear()
+    assert 'hello' not in rolling
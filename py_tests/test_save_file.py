@@ -9,12 +9,14 @@ def test_save():
     bloom = BloomFilter(100_000, 0.01)
     bloom.add_bytes(b'hello')
     bloom.add(87)
+    bloom.add('')
 
     builder = FilterBuilder(100_000, 0.01)
     cbf = builder.build_counting_bloom_filter()  # type: CountingBloomFilter
 
     cbf.add('hello')
     cbf.add(87)
+    cbf.add('')
 
     if os.path.exists('data'):
         shutil.rmtree('data')
@@ -1,6 +1,6 @@
 use pyo3::prelude::*;
 
-use crate::pybloom::{PyBloomFilter, PyFilterBuilder, PyCountingBloomFilter};
+use crate::pybloom::{PyBloomFilter, PyFilterBuilder, PyCountingBloomFilter, PyRollingBloomFilter};
 
 pub mod pybloom;
 
@@ -9,6 +9,7 @@ fn fastbloom_rs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyBloomFilter>().unwrap();
     m.add_class::<PyFilterBuilder>().unwrap();
     m.add_class::<PyCountingBloomFilter>().unwrap();
+    m.add_class::<PyRollingBloomFilter>().unwrap();
     Ok(())
 }
 
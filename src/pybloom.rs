@@ -1,13 +1,31 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict, PyLong};
 
-use fastbloom_rs::{BloomFilter, CountingBloomFilter, Deletable, FilterBuilder, Hashes, Membership};
+use fastbloom_rs::bench::run_profile;
+use fastbloom_rs::{BloomFilter, ConfigIssue, CountingBloomFilter, Deletable, FilterBuilder, HashWidth, Hashes, Membership, RollingBloomFilter};
 
 #[pyclass]
 pub struct PyFilterBuilder {
     filter_builder: FilterBuilder,
 }
 
+/// Raises the issues reported by [FilterBuilder::validate] as a `ValueError`.
+fn config_error(issues: Vec<ConfigIssue>) -> PyErr {
+    let issues: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+    PyValueError::new_err(issues.join(", "))
+}
+
+/// Rejects native counter arrays of `len` bytes that do not fill whole `usize` slots, which
+/// [CountingBloomFilter::from_u8_array] and friends would panic on.
+fn check_slot_aligned(len: usize) -> PyResult<()> {
+    if len % std::mem::size_of::<usize>() != 0 {
+        return Err(PyValueError::new_err(
+            format!("{len} bytes do not fill whole {} byte counter slots", std::mem::size_of::<usize>())));
+    }
+    Ok(())
+}
+
 #[pymethods]
 impl PyFilterBuilder {
     #[new]
@@ -19,16 +37,75 @@ impl PyFilterBuilder {
         )
     }
 
+    #[staticmethod]
+    pub fn from_fpr_and_hashes(expected_elements: u64, false_positive_probability: f64, hashes: u32) -> PyResult<Self> {
+        if !(false_positive_probability > 0.0 && false_positive_probability < 1.0) {
+            return Err(PyValueError::new_err(
+                format!("false_positive_probability must between (0.0, 1.0), got {false_positive_probability}")));
+        }
+        if expected_elements == 0 {
+            return Err(PyValueError::new_err("expected_elements must larger than 0"));
+        }
+        if hashes == 0 {
+            return Err(PyValueError::new_err("hashes must larger than 0"));
+        }
+        Ok(
+            PyFilterBuilder {
+                filter_builder: FilterBuilder::from_fpr_and_hashes(expected_elements, false_positive_probability, hashes)
+            }
+        )
+    }
+
+    #[staticmethod]
+    pub fn for_cardinality(expected_elements: u64, relative_error: f64) -> PyResult<Self> {
+        if !(relative_error > 0.0 && relative_error < 1.0) {
+            return Err(PyValueError::new_err(format!("relative_error must between (0.0, 1.0), got {relative_error}")));
+        }
+        if expected_elements == 0 {
+            return Err(PyValueError::new_err("expected_elements must larger than 0"));
+        }
+        Ok(PyFilterBuilder { filter_builder: FilterBuilder::for_cardinality(expected_elements, relative_error) })
+    }
+
+    #[staticmethod]
+    pub fn optimally_build_from(false_positive_probability: f64, elements: Vec<&PyAny>) -> PyResult<PyBloomFilter> {
+        if !(false_positive_probability > 0.0 && false_positive_probability < 1.0) {
+            return Err(PyValueError::new_err(
+                format!("false_positive_probability must between (0.0, 1.0), got {false_positive_probability}")));
+        }
+        let mut encoded = Vec::<Vec<u8>>::with_capacity(elements.len());
+        for element in elements {
+            // the same encodings the `add_int`/`add_str`/`add_bytes` variants use.
+            if let Ok(bytes) = element.downcast::<PyBytes>() {
+                encoded.push(bytes.as_bytes().to_vec());
+            } else if element.downcast::<PyLong>().is_ok() {
+                encoded.push(element.extract::<i64>()?.to_le_bytes().to_vec());
+            } else {
+                encoded.push(element.str()?.to_str()?.as_bytes().to_vec());
+            }
+        }
+        Ok(PyBloomFilter { bloomfilter: FilterBuilder::optimally_build_from(false_positive_probability, &encoded) })
+    }
+
     pub fn build_bloom_filter(&mut self) -> PyResult<PyBloomFilter> {
-        let filter = self.filter_builder.build_bloom_filter();
+        let filter = self.filter_builder.try_build_bloom_filter().map_err(config_error)?;
         Ok(PyBloomFilter { bloomfilter: filter })
     }
 
     pub fn build_counting_bloom_filter(&mut self) -> PyResult<PyCountingBloomFilter> {
-        let filter = self.filter_builder.build_counting_bloom_filter();
+        let filter = self.filter_builder.try_build_counting_bloom_filter().map_err(config_error)?;
         Ok(PyCountingBloomFilter { counting_bloom_filter: filter })
     }
 
+    pub fn build_rolling_bloom_filter(&mut self, windows: usize) -> PyResult<PyRollingBloomFilter> {
+        if windows == 0 {
+            return Err(PyValueError::new_err("windows must larger than 0"));
+        }
+        self.filter_builder.validate().map_err(config_error)?;
+        let filter = self.filter_builder.build_rolling_bloom_filter(windows);
+        Ok(PyRollingBloomFilter { rolling_bloom_filter: filter })
+    }
+
     pub fn expected_elements(&self) -> u64 {
         self.filter_builder.expected_elements
     }
@@ -41,6 +118,10 @@ impl PyFilterBuilder {
         self.filter_builder.enable_repeat_insert(enable);
     }
 
+    pub fn hash_seeds(&mut self, s1: u64, s2: u64) {
+        self.filter_builder.hash_seeds(s1, s2);
+    }
+
     pub fn size(&self) -> u64 {
         self.filter_builder.size
     }
@@ -48,10 +129,43 @@ impl PyFilterBuilder {
     pub fn hashes(&self) -> u32 {
         self.filter_builder.hashes
     }
+
+    pub fn final_size(&self) -> u64 {
+        self.filter_builder.final_size()
+    }
+
+    pub fn final_hashes(&self) -> u32 {
+        self.filter_builder.final_hashes()
+    }
+
+    pub fn merge_conservative(&self, other: &PyFilterBuilder) -> PyFilterBuilder {
+        PyFilterBuilder { filter_builder: self.filter_builder.merge_conservative(&other.filter_builder) }
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        match self.filter_builder.validate() {
+            Ok(()) => Vec::new(),
+            Err(issues) => issues.iter().map(|issue| issue.to_string()).collect(),
+        }
+    }
+
+    pub fn run_profile<'py>(&self, py: Python<'py>, n_insert: u64, n_query: u64) -> PyResult<&'py PyDict> {
+        if n_insert == 0 {
+            return Err(PyValueError::new_err("n_insert must larger than 0!"));
+        }
+        let result = run_profile(self.filter_builder.clone(), n_insert, n_query);
+        let dict = PyDict::new(py);
+        dict.set_item("insert_time", result.insert_time.as_secs_f64())?;
+        dict.set_item("hit_query_time", result.hit_query_time.as_secs_f64())?;
+        dict.set_item("miss_query_time", result.miss_query_time.as_secs_f64())?;
+        dict.set_item("false_positive_rate", result.false_positive_rate)?;
+        dict.set_item("memory_bytes", result.memory_bytes)?;
+        Ok(dict)
+    }
 }
 
 
-#[pyclass]
+#[pyclass(module = "fastbloom_rs")]
 pub struct PyBloomFilter {
     bloomfilter: BloomFilter,
 }
@@ -76,6 +190,16 @@ impl PyBloomFilter {
         self.bloomfilter.add(element.as_bytes());
     }
 
+    pub fn add_all_prefixes(&mut self, key: &PyBytes) {
+        self.bloomfilter.add_all_prefixes(key.as_bytes());
+    }
+
+    pub fn add_all_prefixes_str(&mut self, key: &str) {
+        for (end, c) in key.char_indices() {
+            self.bloomfilter.add(key[..end + c.len_utf8()].as_bytes());
+        }
+    }
+
     pub fn add_str_if_not_contains(&mut self, element: &str) -> bool {
         self.bloomfilter.add_if_not_contains(element.as_bytes())
     }
@@ -101,20 +225,20 @@ impl PyBloomFilter {
     }
 
     pub fn contains_int(&mut self, element: i64) -> bool {
-        self.bloomfilter.contains(&i64::to_le_bytes(element))
+        self.bloomfilter.contains_int(element)
     }
 
     pub fn contains_int_batch(&mut self, elements: Vec<i64>) -> PyResult<Vec<bool>> {
         let mut res = Vec::<bool>::with_capacity(elements.len());
         for ele in elements {
-            let value = self.bloomfilter.contains(&i64::to_le_bytes(ele));
+            let value = self.bloomfilter.contains_int(ele);
             res.push(value);
         }
         Ok(res)
     }
 
     pub fn contains_str(&mut self, element: &str) -> bool {
-        self.bloomfilter.contains(element.as_bytes())
+        self.bloomfilter.contains_str(element)
     }
 
     pub fn contains_str_batch(&mut self, elements: Vec<&str>) -> PyResult<Vec<bool>> {
@@ -143,6 +267,29 @@ impl PyBloomFilter {
         self.bloomfilter.contains_hash_indices(&indices)
     }
 
+    pub fn contains_with_confidence_int(&self, element: i64) -> Option<f64> {
+        self.bloomfilter.contains_with_confidence(&i64::to_le_bytes(element))
+    }
+
+    pub fn contains_with_confidence_str(&self, element: &str) -> Option<f64> {
+        self.bloomfilter.contains_with_confidence(element.as_bytes())
+    }
+
+    pub fn contains_with_confidence_bytes(&self, bts: &PyBytes) -> Option<f64> {
+        self.bloomfilter.contains_with_confidence(bts.as_bytes())
+    }
+
+    pub fn fill_ratio(&self) -> PyResult<f64> {
+        Ok(self.bloomfilter.fill_ratio())
+    }
+
+    pub fn probe_histogram(&self, buckets: usize) -> PyResult<Vec<u64>> {
+        if buckets == 0 {
+            return Err(PyValueError::new_err("buckets must larger than 0"));
+        }
+        Ok(self.bloomfilter.probe_histogram(buckets))
+    }
+
     pub fn config(&self) -> PyResult<PyFilterBuilder> {
         Ok(PyFilterBuilder { filter_builder: self.bloomfilter.config() })
     }
@@ -155,6 +302,27 @@ impl PyBloomFilter {
         Ok(self.bloomfilter.get_u8_array())
     }
 
+    pub fn to_bytes_with_header<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.bloomfilter.to_u8_array_with_header())
+    }
+
+    #[staticmethod]
+    pub fn empty_like(other: &PyBloomFilter) -> PyResult<Self> {
+        Ok(PyBloomFilter { bloomfilter: BloomFilter::empty_like(&other.bloomfilter) })
+    }
+
+    #[staticmethod]
+    pub fn from_bytes_with_header(bytes: &[u8]) -> PyResult<Self> {
+        let filter = BloomFilter::from_u8_array_with_header(bytes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyBloomFilter { bloomfilter: filter })
+    }
+
+    pub fn __reduce__<'py>(slf: &'py PyCell<Self>, py: Python<'py>) -> PyResult<(&'py PyAny, (&'py PyBytes,))> {
+        let from_bytes = slf.get_type().getattr("from_bytes_with_header")?;
+        Ok((from_bytes, (slf.borrow().to_bytes_with_header(py),)))
+    }
+
     pub fn get_int_array(&self) -> PyResult<Vec<u32>> {
         Ok(Vec::from(self.bloomfilter.get_u32_array()))
     }
@@ -171,6 +339,38 @@ impl PyBloomFilter {
         self.bloomfilter.clear()
     }
 
+    pub fn clear_range(&mut self, bit_start: u64, bit_end: u64) -> PyResult<()> {
+        if bit_start > bit_end || bit_end > self.bloomfilter.config().size {
+            return Err(PyValueError::new_err(
+                format!("invalid bit range [{bit_start}, {bit_end})")));
+        }
+        self.bloomfilter.clear_range(bit_start, bit_end);
+        Ok(())
+    }
+
+    pub fn set_bit(&mut self, index: u64) -> PyResult<()> {
+        if index >= self.bloomfilter.config().size {
+            return Err(PyValueError::new_err(format!("bit index {index} out of range")));
+        }
+        self.bloomfilter.set_bit(index);
+        Ok(())
+    }
+
+    pub fn clear_bit(&mut self, index: u64) -> PyResult<()> {
+        if index >= self.bloomfilter.config().size {
+            return Err(PyValueError::new_err(format!("bit index {index} out of range")));
+        }
+        self.bloomfilter.clear_bit(index);
+        Ok(())
+    }
+
+    pub fn get_bit(&self, index: u64) -> PyResult<bool> {
+        if index >= self.bloomfilter.config().size {
+            return Err(PyValueError::new_err(format!("bit index {index} out of range")));
+        }
+        Ok(self.bloomfilter.get_bit(index))
+    }
+
     pub fn is_empty(&self) -> PyResult<bool> {
         Ok(self.bloomfilter.is_empty())
     }
@@ -179,10 +379,67 @@ impl PyBloomFilter {
         Ok(self.bloomfilter.estimate_set_cardinality())
     }
 
+    pub fn current_false_positive_probability(&self) -> f64 {
+        self.bloomfilter.current_false_positive_probability()
+    }
+
+    pub fn should_stop_inserting(&self, target_fpr: f64) -> bool {
+        self.bloomfilter.should_stop_inserting(target_fpr)
+    }
+
+    pub fn sample_fpr(&self, samples: usize, seed: u64) -> PyResult<f64> {
+        if samples == 0 {
+            return Err(PyValueError::new_err("samples must larger than 0"));
+        }
+        Ok(self.bloomfilter.sample_fpr(samples, seed))
+    }
+
+    pub fn suggested_hashes(&self) -> PyResult<u32> {
+        Ok(self.bloomfilter.suggested_hashes())
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.bloomfilter.len().min(isize::MAX as u64) as usize
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.bloomfilter.capacity()
+    }
+
+    pub fn present_only_in(&self, other: &PyBloomFilter, candidates: Vec<&[u8]>) -> Vec<usize> {
+        self.bloomfilter.present_only_in(&other.bloomfilter, &candidates)
+    }
+
+    pub fn estimate_union_cardinality(&self, other: &PyBloomFilter) -> Option<f64> {
+        self.bloomfilter.estimate_union_cardinality(&other.bloomfilter)
+    }
+
+    pub fn size_bits(&self) -> u64 {
+        self.bloomfilter.size_bits()
+    }
+
+    pub fn size_bytes(&self) -> u64 {
+        self.bloomfilter.size_bytes()
+    }
+
+    pub fn hamming_distance(&self, other: &PyBloomFilter) -> Option<u64> {
+        self.bloomfilter.hamming_distance(&other.bloomfilter)
+    }
+
     pub fn union(&mut self, other: &PyBloomFilter) -> PyResult<bool> {
         Ok(self.bloomfilter.union(&other.bloomfilter))
     }
 
+    pub fn union_bytes(&mut self, array: &[u8], hashes: u32) -> PyResult<()> {
+        self.bloomfilter.union_bytes(array, hashes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    pub fn union_bytes_many(&mut self, shards: Vec<(Vec<u8>, u32)>) -> PyResult<usize> {
+        self.bloomfilter.union_bytes_many(shards)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
     pub fn intersect(&mut self, other: &PyBloomFilter) -> PyResult<bool> {
         Ok(self.bloomfilter.intersect(&other.bloomfilter))
     }
@@ -199,9 +456,39 @@ impl PyBloomFilter {
         Ok(self.bloomfilter.get_hash_indices(bts.as_bytes()))
     }
 
+    pub fn get_word_bit_indices_int(&self, element: i64) -> PyResult<Vec<(u64, u32)>> {
+        Ok(self.bloomfilter.get_word_bit_indices(&i64::to_le_bytes(element)))
+    }
+
+    pub fn get_word_bit_indices_str(&self, element: &str) -> PyResult<Vec<(u64, u32)>> {
+        Ok(self.bloomfilter.get_word_bit_indices(element.as_bytes()))
+    }
+
+    pub fn get_word_bit_indices(&self, bts: &PyBytes) -> PyResult<Vec<(u64, u32)>> {
+        Ok(self.bloomfilter.get_word_bit_indices(bts.as_bytes()))
+    }
+
+    pub fn add_hash_pairs(&mut self, pairs: Vec<(u64, u64)>) -> PyResult<()> {
+        if self.bloomfilter.config().hash_width != HashWidth::Bits64 {
+            return Err(PyValueError::new_err("hash pairs need a filter with 64 bit hash width"));
+        }
+        self.bloomfilter.add_hash_pairs(&pairs);
+        Ok(())
+    }
+
+    pub fn contains_hash_pairs(&self, pairs: Vec<(u64, u64)>) -> PyResult<Vec<bool>> {
+        if self.bloomfilter.config().hash_width != HashWidth::Bits64 {
+            return Err(PyValueError::new_err("hash pairs need a filter with 64 bit hash width"));
+        }
+        Ok(self.bloomfilter.contains_hash_pairs(&pairs))
+    }
+
 
     #[staticmethod]
     pub fn from_bytes(array: &[u8], hashes: u32) -> PyResult<Self> {
+        if array.len() % 8 != 0 {
+            return Err(PyValueError::new_err(format!("{} bytes do not hold whole 64 bit words", array.len())));
+        }
         Ok(PyBloomFilter { bloomfilter: BloomFilter::from_u8_array(array, hashes) })
     }
 
@@ -221,7 +508,7 @@ impl PyBloomFilter {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "fastbloom_rs")]
 pub struct PyCountingBloomFilter {
     counting_bloom_filter: CountingBloomFilter,
 }
@@ -271,19 +558,19 @@ impl PyCountingBloomFilter {
     }
 
     pub fn contains_int(&mut self, element: i64) -> bool {
-        self.counting_bloom_filter.contains(&i64::to_le_bytes(element))
+        self.counting_bloom_filter.contains_int(element)
     }
 
     pub fn contains_int_batch(&mut self, elements: Vec<i64>) -> PyResult<Vec<bool>> {
         let mut res = Vec::<bool>::with_capacity(elements.len());
         for ele in elements {
-            res.push(self.counting_bloom_filter.contains(&i64::to_le_bytes(ele)));
+            res.push(self.counting_bloom_filter.contains_int(ele));
         }
         Ok(res)
     }
 
     pub fn contains_str(&mut self, element: &str) -> bool {
-        self.counting_bloom_filter.contains(element.as_bytes())
+        self.counting_bloom_filter.contains_str(element)
     }
 
     pub fn contains_str_batch(&mut self, elements: Vec<&str>) -> PyResult<Vec<bool>> {
@@ -342,24 +629,135 @@ impl PyCountingBloomFilter {
         Ok(self.counting_bloom_filter.get_hash_indices(bts.as_bytes()))
     }
 
-    pub fn estimate_count_int(&self, element: i64) -> PyResult<u32> {
-        Ok(self.counting_bloom_filter.estimate_count(&i64::to_le_bytes(element)) as u32)
+    pub fn estimate_count_int(&self, element: i64) -> PyResult<u64> {
+        Ok(self.counting_bloom_filter.estimate_count(&i64::to_le_bytes(element)) as u64)
+    }
+
+    pub fn estimate_count_str(&self, element: &str) -> PyResult<u64> {
+        Ok(self.counting_bloom_filter.estimate_count(element.as_bytes()) as u64)
+    }
+
+    pub fn estimate_count(&self, element: &PyBytes) -> PyResult<u64> {
+        Ok(self.counting_bloom_filter.estimate_count(element.as_bytes()) as u64)
+    }
+
+    pub fn estimate_count_int_batch(&self, elements: Vec<i64>) -> PyResult<Vec<u64>> {
+        let counts = self.counting_bloom_filter.estimate_count_all(elements.iter().map(|e| e.to_le_bytes()));
+        Ok(counts.into_iter().map(|count| count as u64).collect())
+    }
+
+    pub fn estimate_count_str_batch(&self, elements: Vec<&str>) -> PyResult<Vec<u64>> {
+        let counts = self.counting_bloom_filter.estimate_count_all(elements);
+        Ok(counts.into_iter().map(|count| count as u64).collect())
     }
 
-    pub fn estimate_count_str(&self, element: &str) -> PyResult<u32> {
-        Ok(self.counting_bloom_filter.estimate_count(element.as_bytes()) as u32)
+    pub fn estimate_count_bytes_batch(&self, elements: Vec<&PyBytes>) -> PyResult<Vec<u64>> {
+        let counts = self.counting_bloom_filter.estimate_count_all(elements.iter().map(|e| e.as_bytes()));
+        Ok(counts.into_iter().map(|count| count as u64).collect())
     }
 
-    pub fn estimate_count(&self, element: &PyBytes) -> PyResult<u32> {
-        Ok(self.counting_bloom_filter.estimate_count(element.as_bytes()) as u32)
+    pub fn add_n(&mut self, element: &PyBytes, n: usize) {
+        self.counting_bloom_filter.add_n(element.as_bytes(), n);
+    }
+
+    pub fn add_n_int(&mut self, element: i64, n: usize) {
+        self.counting_bloom_filter.add_n(&i64::to_le_bytes(element), n);
+    }
+
+    pub fn add_n_str(&mut self, element: &str, n: usize) {
+        self.counting_bloom_filter.add_n(element.as_bytes(), n);
+    }
+
+    pub fn remove_n(&mut self, element: &PyBytes, n: usize) {
+        self.counting_bloom_filter.remove_n(element.as_bytes(), n);
+    }
+
+    pub fn remove_n_int(&mut self, element: i64, n: usize) {
+        self.counting_bloom_filter.remove_n(&i64::to_le_bytes(element), n);
+    }
+
+    pub fn remove_n_str(&mut self, element: &str, n: usize) {
+        self.counting_bloom_filter.remove_n(element.as_bytes(), n);
+    }
+
+    pub fn min_count_hash_indices(&self, indices: Vec<u64>) -> PyResult<u64> {
+        Ok(self.counting_bloom_filter.min_count_hash_indices(&indices) as u64)
     }
 
     pub fn counter_at(&self, index: i64) -> PyResult<u64> {
+        if index < 0 {
+            return Err(PyValueError::new_err(format!("counter index must be non-negative, got {index}")));
+        }
         Ok(self.counting_bloom_filter.counter_at(index as u64) as u64)
     }
 
+    pub fn total_insertions(&self) -> u64 {
+        self.counting_bloom_filter.total_insertions()
+    }
+
+    pub fn is_empty(&self) -> PyResult<bool> {
+        Ok(self.counting_bloom_filter.is_empty())
+    }
+
+    pub fn saturated_counters(&self) -> u64 {
+        self.counting_bloom_filter.saturated_counters()
+    }
+
+    pub fn reset_if_saturated(&mut self, threshold: f64) -> bool {
+        self.counting_bloom_filter.reset_if_saturated(threshold)
+    }
+
+    pub fn size_counters(&self) -> u64 {
+        self.counting_bloom_filter.size_counters()
+    }
+
+    pub fn size_bytes(&self) -> u64 {
+        self.counting_bloom_filter.size_bytes()
+    }
+
+    pub fn to_portable_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.counting_bloom_filter.to_portable_bytes())
+    }
+
+    pub fn __reduce__<'py>(slf: &'py PyCell<Self>, py: Python<'py>) -> PyResult<(&'py PyAny, (&'py PyBytes,))> {
+        let from_bytes = slf.get_type().getattr("from_portable_bytes")?;
+        Ok((from_bytes, (slf.borrow().to_portable_bytes(py),)))
+    }
+
+    pub fn to_bloom_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.counting_bloom_filter.to_bloom_bytes())
+    }
+
+    #[staticmethod]
+    pub fn from_portable_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let filter = CountingBloomFilter::from_portable_bytes(bytes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyCountingBloomFilter { counting_bloom_filter: filter })
+    }
+
+    pub fn subtract(&mut self, other: &PyCountingBloomFilter) -> PyResult<bool> {
+        Ok(self.counting_bloom_filter.subtract(&other.counting_bloom_filter))
+    }
+
+    pub fn union_bytes(&mut self, array: &[u8], hashes: u32) -> PyResult<()> {
+        self.counting_bloom_filter.union_bytes(array, hashes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    pub fn counters_as_u8<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.counting_bloom_filter.counters_as_u8())
+    }
+
+    #[staticmethod]
+    pub fn from_counters_u8(counters: &[u8], hashes: u32, enable_repeat_insert: bool) -> PyResult<Self> {
+        let filter = CountingBloomFilter::from_counters_u8(counters, hashes, enable_repeat_insert)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyCountingBloomFilter { counting_bloom_filter: filter })
+    }
+
     #[staticmethod]
     pub fn from_bytes(array: &[u8], hashes: u32, enable_repeat_insert: bool) -> PyResult<Self> {
+        check_slot_aligned(std::mem::size_of_val(array))?;
         Ok(PyCountingBloomFilter {
             counting_bloom_filter: CountingBloomFilter::from_u8_array(array, hashes, enable_repeat_insert)
         })
@@ -367,6 +765,7 @@ impl PyCountingBloomFilter {
 
     #[staticmethod]
     pub fn from_int_array(array: Vec<u32>, hashes: u32, enable_repeat_insert: bool) -> PyResult<Self> {
+        check_slot_aligned(std::mem::size_of_val(array.as_slice()))?;
         Ok(PyCountingBloomFilter {
             counting_bloom_filter:
             CountingBloomFilter::from_u32_array(array.as_slice(), hashes, enable_repeat_insert)
@@ -375,3 +774,55 @@ impl PyCountingBloomFilter {
 }
 
 
+
+#[pyclass(module = "fastbloom_rs")]
+pub struct PyRollingBloomFilter {
+    rolling_bloom_filter: RollingBloomFilter,
+}
+
+#[pymethods]
+impl PyRollingBloomFilter {
+    pub fn add_int(&mut self, element: i64) {
+        self.rolling_bloom_filter.add(&i64::to_le_bytes(element));
+    }
+
+    pub fn add_str(&mut self, element: &str) {
+        self.rolling_bloom_filter.add(element.as_bytes());
+    }
+
+    pub fn add_bytes(&mut self, bts: &PyBytes) {
+        self.rolling_bloom_filter.add(bts.as_bytes());
+    }
+
+    pub fn contains_int(&self, element: i64) -> bool {
+        self.rolling_bloom_filter.contains_int(element)
+    }
+
+    pub fn contains_str(&self, element: &str) -> bool {
+        self.rolling_bloom_filter.contains_str(element)
+    }
+
+    pub fn contains_bytes(&self, bts: &PyBytes) -> bool {
+        self.rolling_bloom_filter.contains(bts.as_bytes())
+    }
+
+    pub fn rotate(&mut self) {
+        self.rolling_bloom_filter.rotate()
+    }
+
+    pub fn windows(&self) -> PyResult<usize> {
+        Ok(self.rolling_bloom_filter.windows())
+    }
+
+    pub fn config(&self) -> PyResult<PyFilterBuilder> {
+        Ok(PyFilterBuilder { filter_builder: self.rolling_bloom_filter.config() })
+    }
+
+    pub fn hashes(&self) -> PyResult<u32> {
+        Ok(self.rolling_bloom_filter.hashes())
+    }
+
+    pub fn clear(&mut self) {
+        self.rolling_bloom_filter.clear()
+    }
+}